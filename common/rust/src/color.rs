@@ -0,0 +1,37 @@
+//! # sRGB Transfer
+//! Renderers compute linear light, displays and image files expect sRGB encoded values.
+
+/// Linear-light RGB, `1` is the display white but brighter values are allowed
+pub type Rgb = [f32; 3];
+
+/// sRGB transfer function, linear to display-encoded
+pub fn srgb_encode(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Inverse sRGB transfer function, display-encoded to linear
+pub fn srgb_decode(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_test() {
+        for i in 0..=255 {
+            let c = i as f32 / 255.;
+            assert!((srgb_encode(srgb_decode(c)) - c).abs() < 1e-5);
+        }
+        assert!((srgb_encode(0.5) - 0.7354).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,415 @@
+//! # Post-Processing Filters
+//! Image effects on a finished frame of linear-light RGB, rows from the top.
+//! A chain is a slice of filters applied in order, pixels beyond the edges repeat the edge.
+//! Filters work on plain pixel slices: ppmdemo filters its float frames directly,
+//! simple3d converts its sRGB `Canvas` to linear light and back in `Canvas::filter`.
+
+use crate::color::Rgb;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// standard deviation in pixels
+    GaussianBlur { sigma: f32 },
+    /// mean of the square of `2 * radius + 1` pixels
+    BoxBlur { radius: usize },
+    /// the light above a luminance of `threshold`, blurred and added back times `intensity`
+    Bloom {
+        threshold: f32,
+        sigma: f32,
+        intensity: f32,
+    },
+    /// darkens by up to `strength` at the corners, starting at `radius` of the half diagonal
+    Vignette { strength: f32, radius: f32 },
+    /// red is magnified and blue shrunk about the center by `amount` pixels at the corners
+    ChromaticAberration { amount: f32 },
+    /// red, green and blue tone curves
+    Curves([Curve; 3]),
+    /// unsharp masking, adds `amount` times the difference to a slight blur
+    Sharpen { amount: f32 },
+    /// gradient magnitude of the luminance in gray
+    Sobel,
+}
+
+/// # Tone Curve
+/// monotone cubic through control points, inputs are clamped to `[0, 1]`
+/// and the curve stays flat before the first point and after the last
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    points: Vec<[f32; 2]>,
+    /// slopes at the points
+    tangents: Vec<f32>,
+}
+
+impl Curve {
+    pub fn identity() -> Self {
+        Self::new(vec![[0., 0.], [1., 1.]])
+    }
+
+    /// Fritsch-Carlson tangents, a point repeating an earlier x replaces it
+    pub fn new(mut points: Vec<[f32; 2]>) -> Self {
+        assert!(!points.is_empty());
+        points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        points.reverse();
+        points.dedup_by(|a, b| a[0] == b[0]);
+        points.reverse();
+        let n = points.len();
+        let secants = points
+            .windows(2)
+            .map(|p| (p[1][1] - p[0][1]) / (p[1][0] - p[0][0]))
+            .collect::<Vec<_>>();
+        let mut tangents = (0..n)
+            .map(
+                |k| match (k.checked_sub(1).map(|k| secants[k]), secants.get(k)) {
+                    (Some(a), Some(&b)) if a * b > 0. => (a + b) / 2.,
+                    (Some(_), Some(_)) | (None, None) => 0.,
+                    (Some(a), None) => a,
+                    (None, Some(&b)) => b,
+                },
+            )
+            .collect::<Vec<_>>();
+        // limits the tangents so each segment stays monotone
+        for (k, &d) in secants.iter().enumerate() {
+            if d == 0. {
+                tangents[k] = 0.;
+                tangents[k + 1] = 0.;
+                continue;
+            }
+            let (a, b) = (tangents[k] / d, tangents[k + 1] / d);
+            let s = a * a + b * b;
+            if s > 9. {
+                let t = 3. / s.sqrt();
+                tangents[k] = t * a * d;
+                tangents[k + 1] = t * b * d;
+            }
+        }
+        Self { points, tangents }
+    }
+
+    pub fn eval(&self, x: f32) -> f32 {
+        let x = x.clamp(0., 1.);
+        let points = &self.points;
+        let k = points.partition_point(|p| p[0] <= x);
+        if k == 0 {
+            return points[0][1];
+        }
+        if k == points.len() {
+            return points[k - 1][1];
+        }
+        let ([x0, y0], [x1, y1]) = (points[k - 1], points[k]);
+        let h = x1 - x0;
+        let t = (x - x0) / h;
+        let (t2, t3) = (t * t, t * t * t);
+        (2. * t3 - 3. * t2 + 1.) * y0
+            + (t3 - 2. * t2 + t) * h * self.tangents[k - 1]
+            + (-2. * t3 + 3. * t2) * y1
+            + (t3 - t2) * h * self.tangents[k]
+    }
+}
+
+/// Rec. 709 luminance
+fn luminance([r, g, b]: Rgb) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Normalized weights from `-r` to `r` pixels
+fn gaussian(sigma: f32) -> Vec<f32> {
+    let r = (3. * sigma).ceil().max(1.) as i32;
+    let weights = (-r..=r)
+        .map(|d| (-(d * d) as f32 / (2. * sigma * sigma)).exp())
+        .collect::<Vec<_>>();
+    let sum = weights.iter().sum::<f32>();
+    weights.into_iter().map(|k| k / sum).collect()
+}
+
+/// Weighted sum of `sample` at the offsets of `kernel`, which is centered
+fn weigh(kernel: &[f32], sample: impl Fn(isize) -> Rgb) -> Rgb {
+    let r = (kernel.len() / 2) as isize;
+    let mut c = [0.; 3];
+    for (k, weight) in kernel.iter().enumerate() {
+        let s = sample(k as isize - r);
+        for i in 0..3 {
+            c[i] += weight * s[i];
+        }
+    }
+    c
+}
+
+/// `i + d` clamped to `0..n`
+fn clamped(i: usize, d: isize, n: usize) -> usize {
+    (i as isize + d).clamp(0, n as isize - 1) as usize
+}
+
+/// Convolves the rows and then the columns with `kernel`
+fn separable(w: usize, h: usize, pixels: &mut [Rgb], kernel: &[f32]) {
+    let mut rows = vec![[0.; 3]; pixels.len()];
+    for y in 0..h {
+        for x in 0..w {
+            rows[y * w + x] = weigh(kernel, |d| pixels[y * w + clamped(x, d, w)]);
+        }
+    }
+    for y in 0..h {
+        for x in 0..w {
+            pixels[y * w + x] = weigh(kernel, |d| rows[clamped(y, d, h) * w + x]);
+        }
+    }
+}
+
+/// Bilinear sample of channel `i` at a position in pixels
+fn bilinear(w: usize, h: usize, pixels: &[Rgb], [x, y]: [f32; 2], i: usize) -> f32 {
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let at = |dx: isize, dy: isize| {
+        let px = clamped(0, x0 as isize + dx, w);
+        let py = clamped(0, y0 as isize + dy, h);
+        pixels[py * w + px][i]
+    };
+    let top = at(0, 0) * (1. - fx) + at(1, 0) * fx;
+    let bottom = at(0, 1) * (1. - fx) + at(1, 1) * fx;
+    top * (1. - fy) + bottom * fy
+}
+
+impl Filter {
+    /// # Params
+    /// `name` and numeric `args` as written in scene files and on the command line,
+    /// missing trailing arguments take defaults. Curves take `x y` pairs in `[0, 1]`,
+    /// `curves` for all channels or `curves-r`, `curves-g` and `curves-b` for one.
+    pub fn parse(name: &str, args: &[f64]) -> Result<Self, String> {
+        let max = match name {
+            "blur" | "box" | "aberration" | "sharpen" => 1,
+            "vignette" => 2,
+            "bloom" => 3,
+            "sobel" => 0,
+            "curves" | "curves-r" | "curves-g" | "curves-b" => usize::MAX,
+            _ => return Err(format!("unknown filter `{}`", name)),
+        };
+        if args.len() > max {
+            return Err(format!("`{}` takes at most {} arguments", name, max));
+        }
+        let arg = |i: usize, default: f64| args.get(i).copied().unwrap_or(default) as f32;
+        let check = |i: usize, default: f64, ok: fn(f32) -> bool, msg: &str| {
+            let n = arg(i, default);
+            ok(n)
+                .then_some(n)
+                .ok_or_else(|| format!("`{}` expects {}", name, msg))
+        };
+        let positive = |i, default| check(i, default, |n| n > 0., "a positive number");
+        let unit = |i, default| check(i, default, |n| (0. ..=1.).contains(&n), "numbers in [0, 1]");
+        Ok(match name {
+            "blur" => Filter::GaussianBlur {
+                sigma: positive(0, 2.)?,
+            },
+            "box" => {
+                let integer = |n: f32| n >= 0. && n.fract() == 0.;
+                let radius = check(0, 2., integer, "a non-negative integer")?;
+                Filter::BoxBlur {
+                    radius: radius as usize,
+                }
+            }
+            "bloom" => Filter::Bloom {
+                threshold: check(0, 0.8, |n| n >= 0., "a non-negative threshold")?,
+                sigma: positive(1, 8.)?,
+                intensity: check(2, 0.5, |n| n >= 0., "a non-negative intensity")?,
+            },
+            "vignette" => Filter::Vignette {
+                strength: unit(0, 0.5)?,
+                radius: unit(1, 0.4)?,
+            },
+            "aberration" => Filter::ChromaticAberration { amount: arg(0, 2.) },
+            "sharpen" => Filter::Sharpen {
+                amount: check(0, 0.5, |n| n >= 0., "a non-negative amount")?,
+            },
+            "sobel" => Filter::Sobel,
+            _ => {
+                if args.is_empty() || args.len() % 2 == 1 {
+                    return Err(format!("`{}` expects x y pairs", name));
+                }
+                for i in 0..args.len() {
+                    unit(i, 0.)?;
+                }
+                let points = args.chunks(2).map(|p| [p[0] as f32, p[1] as f32]).collect();
+                let curve = Curve::new(points);
+                let mut curves = [Curve::identity(), Curve::identity(), Curve::identity()];
+                match name {
+                    "curves-r" => curves[0] = curve,
+                    "curves-g" => curves[1] = curve,
+                    "curves-b" => curves[2] = curve,
+                    _ => curves = [curve.clone(), curve.clone(), curve],
+                }
+                Filter::Curves(curves)
+            }
+        })
+    }
+
+    /// Filters `pixels` of a `w` by `h` frame in place
+    pub fn apply(&self, w: usize, h: usize, pixels: &mut [Rgb]) {
+        assert_eq!(pixels.len(), w * h);
+        // pixel centers relative to the frame center, in units of the half diagonal
+        let half = [w as f32 / 2., h as f32 / 2.];
+        let diagonal = (half[0] * half[0] + half[1] * half[1]).sqrt().max(1.);
+        let offset = |i: usize| {
+            let x = (i % w) as f32 + 0.5 - half[0];
+            let y = (i / w) as f32 + 0.5 - half[1];
+            [x / diagonal, y / diagonal]
+        };
+        match self {
+            Filter::GaussianBlur { sigma } => separable(w, h, pixels, &gaussian(*sigma)),
+            Filter::BoxBlur { radius } => {
+                let n = 2 * radius + 1;
+                separable(w, h, pixels, &vec![1. / n as f32; n]);
+            }
+            Filter::Bloom {
+                threshold,
+                sigma,
+                intensity,
+            } => {
+                let mut bright = pixels
+                    .iter()
+                    .map(|&c| {
+                        let l = luminance(c);
+                        let k = if l > *threshold {
+                            (l - threshold) / l
+                        } else {
+                            0.
+                        };
+                        c.map(|c| c * k)
+                    })
+                    .collect::<Vec<_>>();
+                separable(w, h, &mut bright, &gaussian(*sigma));
+                for (c, b) in pixels.iter_mut().zip(bright) {
+                    for i in 0..3 {
+                        c[i] += intensity * b[i];
+                    }
+                }
+            }
+            Filter::Vignette { strength, radius } => {
+                for (i, c) in pixels.iter_mut().enumerate() {
+                    let [x, y] = offset(i);
+                    let d = (x * x + y * y).sqrt();
+                    let t = ((d - radius) / (1. - radius).max(1e-6)).clamp(0., 1.);
+                    let k = 1. - strength * t * t * (3. - 2. * t);
+                    *c = c.map(|c| c * k);
+                }
+            }
+            Filter::ChromaticAberration { amount } => {
+                let source = pixels.to_vec();
+                let scale = amount / diagonal;
+                for (i, c) in pixels.iter_mut().enumerate() {
+                    let [x, y] = offset(i).map(|v| v * diagonal);
+                    let at = |k: f32| [half[0] + x * k, half[1] + y * k];
+                    // sampling nearer the center magnifies
+                    c[0] = bilinear(w, h, &source, at(1. - scale), 0);
+                    c[2] = bilinear(w, h, &source, at(1. + scale), 2);
+                }
+            }
+            Filter::Curves(curves) => {
+                for c in pixels.iter_mut() {
+                    for i in 0..3 {
+                        c[i] = curves[i].eval(c[i]);
+                    }
+                }
+            }
+            Filter::Sharpen { amount } => {
+                let mut blurred = pixels.to_vec();
+                separable(w, h, &mut blurred, &gaussian(1.));
+                for (c, b) in pixels.iter_mut().zip(blurred) {
+                    for i in 0..3 {
+                        c[i] = (c[i] + amount * (c[i] - b[i])).max(0.);
+                    }
+                }
+            }
+            Filter::Sobel => {
+                let l = pixels.iter().map(|&c| luminance(c)).collect::<Vec<_>>();
+                let at = |x: usize, y: usize, dx: isize, dy: isize| {
+                    l[clamped(y, dy, h) * w + clamped(x, dx, w)]
+                };
+                for (i, c) in pixels.iter_mut().enumerate() {
+                    let (x, y) = (i % w, i / w);
+                    let column = |dx| at(x, y, dx, -1) + 2. * at(x, y, dx, 0) + at(x, y, dx, 1);
+                    let row = |dy| at(x, y, -1, dy) + 2. * at(x, y, 0, dy) + at(x, y, 1, dy);
+                    let (gx, gy) = (column(1) - column(-1), row(1) - row(-1));
+                    // a step from 0 to 1 gives 1
+                    *c = [(gx * gx + gy * gy).sqrt() / 4.; 3];
+                }
+            }
+        }
+    }
+}
+
+/// Applies every filter of `chain` in order
+pub fn apply(chain: &[Filter], w: usize, h: usize, pixels: &mut [Rgb]) {
+    for filter in chain {
+        filter.apply(w, h, pixels);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_test() {
+        let (w, h) = (7, 5);
+        let flat = vec![[0.5, 0.25, 0.125]; w * h];
+        // blurs and sharpening keep flat images
+        for name in ["blur", "box", "sharpen", "aberration"] {
+            let mut pixels = flat.clone();
+            Filter::parse(name, &[]).unwrap().apply(w, h, &mut pixels);
+            for (a, b) in pixels.iter().zip(&flat) {
+                assert!((0..3).all(|i| (a[i] - b[i]).abs() < 1e-5), "{}", name);
+            }
+        }
+        // a box blur spreads a dot evenly over its square
+        let mut dot = vec![[0.; 3]; w * h];
+        dot[2 * w + 3] = [9.; 3];
+        Filter::BoxBlur { radius: 1 }.apply(w, h, &mut dot);
+        assert!((dot[w + 2][0] - 1.).abs() < 1e-5);
+        assert_eq!(dot[3], [0.; 3]);
+        // only light above the threshold blooms
+        let mut pixels = flat.clone();
+        Filter::parse("bloom", &[1.])
+            .unwrap()
+            .apply(w, h, &mut pixels);
+        assert_eq!(pixels, flat);
+        let mut pixels = dot.clone();
+        apply(
+            &[Filter::parse("bloom", &[0.5, 2., 1.]).unwrap()],
+            w,
+            h,
+            &mut pixels,
+        );
+        assert!(pixels[0][0] > dot[0][0] && pixels[w + 2][0] > dot[w + 2][0]);
+        // the vignette keeps the center and darkens the corners
+        let mut pixels = flat.clone();
+        Filter::parse("vignette", &[1., 0.2])
+            .unwrap()
+            .apply(w, h, &mut pixels);
+        assert_eq!(pixels[2 * w + 3], flat[0]);
+        assert!(pixels[0][0] < 0.1);
+        // curves pass through their points and keep their order
+        let curve = Curve::new(vec![[0., 0.], [0.25, 0.1], [0.75, 0.9], [1., 1.]]);
+        assert!((curve.eval(0.25) - 0.1).abs() < 1e-6);
+        assert_eq!(curve.eval(2.), 1.);
+        let ys = (0..=100)
+            .map(|i| curve.eval(i as f32 / 100.))
+            .collect::<Vec<_>>();
+        assert!(ys.windows(2).all(|y| y[0] <= y[1]));
+        assert!((Curve::identity().eval(0.3) - 0.3).abs() < 1e-6);
+        let Ok(Filter::Curves([r, g, _])) = Filter::parse("curves-r", &[0., 1., 1., 1.]) else {
+            panic!()
+        };
+        assert_eq!((r.eval(0.5), g.eval(0.5)), (1., 0.5));
+        // edges show where the luminance steps
+        let mut step = (0..w * h)
+            .map(|i| if i % w < 3 { [0.; 3] } else { [1.; 3] })
+            .collect::<Vec<_>>();
+        Filter::Sobel.apply(w, h, &mut step);
+        assert_eq!(step[w], [0.; 3]);
+        assert!((step[w + 3][0] - 1.).abs() < 1e-5);
+        assert!(Filter::parse("glow", &[]).is_err());
+        assert!(Filter::parse("blur", &[0.]).is_err());
+        assert!(Filter::parse("box", &[1.5]).is_err());
+        assert!(Filter::parse("sobel", &[1.]).is_err());
+        assert!(Filter::parse("curves", &[0.5]).is_err());
+    }
+}
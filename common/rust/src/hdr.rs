@@ -0,0 +1,165 @@
+//! # Floating Point Frames
+//! Portable FloatMap and Radiance RGBE files keep linear-light values above `1`
+//! for grading and compositing in external tools.
+
+use crate::color::Rgb;
+
+pub fn pfm_header(w: usize, h: usize) -> String {
+    format!("PF\n{} {}\n-1.0\n", w, h)
+}
+
+/// Little-endian PFM, rows from the bottom
+pub fn encode_pfm(w: usize, h: usize, pixels: &[Rgb]) -> Vec<u8> {
+    let mut buf = pfm_header(w, h).into_bytes();
+    buf.reserve(w * h * 12);
+    for row in pixels.chunks(w.max(1)).rev() {
+        for c in row.iter().flatten() {
+            buf.extend(c.to_le_bytes());
+        }
+    }
+    buf
+}
+
+/// Shared exponent encoding, the largest component keeps 8 bits of mantissa
+pub fn rgbe(rgb: Rgb) -> [u8; 4] {
+    let m = rgb.iter().fold(0f32, |m, &c| m.max(c));
+    if m < 1e-32 {
+        return [0; 4];
+    }
+    // m = f * 2^e with f in [0.5, 1)
+    let mut e = m.log2().floor() as i32 + 1;
+    if m / 2f32.powi(e) >= 1. {
+        e += 1;
+    }
+    let scale = 256. / 2f32.powi(e);
+    let [r, g, b] = rgb.map(|c| (c.max(0.) * scale) as u8);
+    [r, g, b, (e + 128) as u8]
+}
+
+/// Inverse of `rgbe`, up to the lost precision
+pub fn from_rgbe([r, g, b, e]: [u8; 4]) -> Rgb {
+    if e == 0 {
+        return [0.; 3];
+    }
+    let scale = 2f32.powi(e as i32 - 128 - 8);
+    [r, g, b].map(|c| (c as f32 + 0.5) * scale)
+}
+
+/// Runs of at least 4 equal bytes become `128 + n, value`,
+/// anything else is copied as `n, bytes...`
+fn rle(channel: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < channel.len() {
+        let run = channel[i..]
+            .iter()
+            .take(127)
+            .take_while(|&&c| c == channel[i])
+            .count();
+        if run >= 4 {
+            out.extend([128 + run as u8, channel[i]]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < channel.len() && i - start < 128 {
+            if i + 3 < channel.len() && channel[i..i + 4].iter().all(|&c| c == channel[i]) {
+                break;
+            }
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend(&channel[start..i]);
+    }
+}
+
+pub fn rgbe_header(w: usize, h: usize) -> String {
+    format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", h, w)
+}
+
+/// Radiance RGBE, scanlines are run-length encoded when the width allows it
+pub fn encode_rgbe(w: usize, h: usize, pixels: &[Rgb]) -> Vec<u8> {
+    let mut buf = rgbe_header(w, h).into_bytes();
+    for row in pixels.chunks(w.max(1)) {
+        let row = row.iter().map(|&rgb| rgbe(rgb)).collect::<Vec<_>>();
+        if !(8..0x8000).contains(&w) {
+            buf.extend(row.iter().flatten());
+            continue;
+        }
+        buf.extend([2, 2, (w >> 8) as u8, w as u8]);
+        for k in 0..4 {
+            let channel = row.iter().map(|p| p[k]).collect::<Vec<_>>();
+            rle(&channel, &mut buf);
+        }
+    }
+    buf
+}
+
+/// # Returns
+/// whether `data` after the header holds exactly `h` scanlines of `w` pixels
+/// as written by `encode_rgbe`
+pub fn rgbe_complete(w: usize, h: usize, data: &[u8]) -> bool {
+    if !(8..0x8000).contains(&w) {
+        return data.len() == w * h * 4;
+    }
+    let mut i = 0;
+    for _ in 0..h {
+        if data.get(i..i + 4) != Some(&[2, 2, (w >> 8) as u8, w as u8]) {
+            return false;
+        }
+        i += 4;
+        for _ in 0..4 {
+            let mut n = 0;
+            while n < w {
+                let Some(&count) = data.get(i) else {
+                    return false;
+                };
+                // a run is followed by one value, a literal by its bytes
+                let (pixels, bytes) = if count > 128 {
+                    (count as usize - 128, 1)
+                } else {
+                    (count as usize, count as usize)
+                };
+                if pixels == 0 {
+                    return false;
+                }
+                n += pixels;
+                i += 1 + bytes;
+            }
+            if n != w {
+                return false;
+            }
+        }
+    }
+    i == data.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_test() {
+        for c in [0.001, 0.5, 1., 1.5, 200.] {
+            let [r, ..] = from_rgbe(rgbe([c, c / 2., 0.]));
+            assert!((r - c).abs() / c < 1. / 128.);
+        }
+        assert_eq!(rgbe([0.; 3]), [0; 4]);
+        assert_eq!(rgbe([1., 0.5, 0.]), [128, 64, 0, 129]);
+        assert_eq!(rgbe([3., 0., 0.]), [192, 0, 0, 130]);
+        let pixels = [[4., 0., 0.], [0., 0., 1.]];
+        let pfm = encode_pfm(1, 2, &pixels);
+        assert!(pfm.starts_with(b"PF\n1 2\n-1.0\n"));
+        assert_eq!(pfm[12..16], 0f32.to_le_bytes());
+        assert_eq!(pfm[20..24], 1f32.to_le_bytes());
+        assert_eq!(pfm[24..28], 4f32.to_le_bytes());
+        let row = [[2., 2., 2.]; 10];
+        let hdr = encode_rgbe(10, 1, &row);
+        let data = &hdr[hdr.len() - 12..];
+        // r, g, b and e are each one run of 10
+        assert_eq!(data, [2, 2, 0, 10, 138, 128, 138, 128, 138, 128, 138, 130]);
+        let header = rgbe_header(10, 1).len();
+        assert!(rgbe_complete(10, 1, &hdr[header..]));
+        assert!(!rgbe_complete(10, 1, &hdr[header..hdr.len() - 1]));
+        assert!(!rgbe_complete(10, 2, &hdr[header..]));
+    }
+}
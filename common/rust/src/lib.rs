@@ -0,0 +1,8 @@
+//! # Common
+//! Modules shared by the Rust renderers of simple3d and ppmdemo
+
+pub mod color;
+pub mod filter;
+pub mod hdr;
+pub mod noise;
+pub mod shutter;
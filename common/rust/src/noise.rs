@@ -0,0 +1,305 @@
+//! # Procedural Noise
+//! Deterministic lattice noise in 2 to 4 dimensions, the same seed gives the same field on
+//! every platform. Value, gradient and Worley noise can tile with a period in lattice cells,
+//! simplex noise cannot and loops in time by walking a circle through 4D instead.
+//! Every basis returns values in about `[-1, 1]`.
+
+use std::array;
+use std::f64::consts::TAU;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Basis {
+    /// random values at the lattice points
+    Value,
+    /// Perlin noise, random gradients at the lattice points
+    Gradient,
+    Simplex,
+    /// distance to the nearest of one random point per cell
+    Worley,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Noise {
+    seed: u64,
+    /// lattice cells after which the field repeats on every axis
+    period: Option<i64>,
+}
+
+/// splitmix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+/// Uniform in `[0, 1)` from the high bits
+fn unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Quintic interpolation weight with zero first and second derivatives at the ends
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn dot<const N: usize>(a: [f64; N], b: [f64; N]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Scales keeping simplex noise within `[-1, 1]`, measured over dense samples
+const SIMPLEX_SCALE: [f64; 5] = [0., 0., 90., 100., 100.];
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, period: None }
+    }
+
+    /// Repeats value, gradient and Worley noise every `period` lattice cells
+    pub fn with_period(mut self, period: usize) -> Self {
+        self.period = Some(period.max(1) as i64);
+        self
+    }
+
+    fn hash<const N: usize>(&self, cell: [i64; N], period: Option<i64>) -> u64 {
+        cell.iter().fold(mix(self.seed), |h, &c| {
+            let c = period.map_or(c, |p| c.rem_euclid(p));
+            mix(h ^ c as u64)
+        })
+    }
+
+    /// Random unit vector
+    fn gradient_at<const N: usize>(&self, cell: [i64; N], period: Option<i64>) -> [f64; N] {
+        let h = self.hash(cell, period);
+        let g: [f64; N] = array::from_fn(|i| unit(mix(h + i as u64)) * 2. - 1.);
+        let len = dot(g, g).sqrt();
+        if len < 1e-9 {
+            array::from_fn(|i| if i == 0 { 1. } else { 0. })
+        } else {
+            g.map(|c| c / len)
+        }
+    }
+
+    /// Interpolates `corner` of the `2^N` corners of the cell around `p` with `fade`
+    fn lattice<const N: usize>(
+        &self,
+        p: [f64; N],
+        corner: impl Fn([i64; N], [f64; N]) -> f64,
+    ) -> f64 {
+        let cell = p.map(|c| c.floor());
+        let f: [f64; N] = array::from_fn(|i| p[i] - cell[i]);
+        let u = f.map(fade);
+        let mut sum = 0.;
+        for bits in 0..1usize << N {
+            let mut weight = 1.;
+            let mut offset = [0; N];
+            let mut d = f;
+            for i in 0..N {
+                if bits >> i & 1 == 1 {
+                    weight *= u[i];
+                    offset[i] = 1;
+                    d[i] -= 1.;
+                } else {
+                    weight *= 1. - u[i];
+                }
+            }
+            if weight != 0. {
+                let c = array::from_fn(|i| cell[i] as i64 + offset[i]);
+                sum += weight * corner(c, d);
+            }
+        }
+        sum
+    }
+
+    fn value_with<const N: usize>(&self, p: [f64; N], period: Option<i64>) -> f64 {
+        self.lattice(p, |c, _| unit(self.hash(c, period)) * 2. - 1.)
+    }
+
+    fn gradient_with<const N: usize>(&self, p: [f64; N], period: Option<i64>) -> f64 {
+        // unit gradients reach at most sqrt(N) / 2
+        let scale = 2. / (N as f64).sqrt();
+        scale * self.lattice(p, |c, d| dot(self.gradient_at(c, period), d))
+    }
+
+    fn worley_with<const N: usize>(&self, p: [f64; N], period: Option<i64>) -> [f64; 2] {
+        let cell = p.map(|c| c.floor() as i64);
+        let mut f = [f64::INFINITY; 2];
+        for k in 0..3usize.pow(N as u32) {
+            let c: [i64; N] =
+                array::from_fn(|i| cell[i] + (k / 3usize.pow(i as u32) % 3) as i64 - 1);
+            let h = self.hash(c, period);
+            let d2 = (0..N)
+                .map(|i| {
+                    let feature = c[i] as f64 + unit(mix(h + i as u64));
+                    (feature - p[i]).powi(2)
+                })
+                .sum::<f64>();
+            if d2 < f[0] {
+                f = [d2, f[0]];
+            } else if d2 < f[1] {
+                f[1] = d2;
+            }
+        }
+        f.map(f64::sqrt)
+    }
+
+    /// Random values at the lattice points, smoothly interpolated
+    pub fn value<const N: usize>(&self, p: [f64; N]) -> f64 {
+        self.value_with(p, self.period)
+    }
+
+    /// Perlin noise, zero at the lattice points
+    pub fn gradient<const N: usize>(&self, p: [f64; N]) -> f64 {
+        self.gradient_with(p, self.period)
+    }
+
+    /// Simplex noise on the skewed lattice, ignores the period
+    pub fn simplex<const N: usize>(&self, p: [f64; N]) -> f64 {
+        let n = N as f64;
+        let skew = ((n + 1.).sqrt() - 1.) / n;
+        let unskew = (1. - 1. / (n + 1.).sqrt()) / n;
+        let s = p.iter().sum::<f64>() * skew;
+        let cell: [i64; N] = array::from_fn(|i| (p[i] + s).floor() as i64);
+        let t = cell.iter().sum::<i64>() as f64 * unskew;
+        let x0: [f64; N] = array::from_fn(|i| p[i] - (cell[i] as f64 - t));
+        // the simplex corners step along the axes from the largest offset down
+        let mut order: [usize; N] = array::from_fn(|i| i);
+        order.sort_by(|&a, &b| x0[b].total_cmp(&x0[a]));
+        let mut vertex = [0; N];
+        let mut sum = 0.;
+        for k in 0..=N {
+            if k > 0 {
+                vertex[order[k - 1]] = 1;
+            }
+            let x: [f64; N] = array::from_fn(|i| x0[i] - vertex[i] as f64 + k as f64 * unskew);
+            let falloff = 0.5 - dot(x, x);
+            if falloff > 0. {
+                let c = array::from_fn(|i| cell[i] + vertex[i]);
+                sum += falloff.powi(4) * dot(self.gradient_at(c, None), x);
+            }
+        }
+        SIMPLEX_SCALE[N] * sum
+    }
+
+    /// # Returns
+    /// distances to the nearest and second nearest feature points
+    pub fn worley<const N: usize>(&self, p: [f64; N]) -> [f64; 2] {
+        self.worley_with(p, self.period)
+    }
+
+    fn basis_with<const N: usize>(&self, basis: Basis, p: [f64; N], period: Option<i64>) -> f64 {
+        match basis {
+            Basis::Value => self.value_with(p, period),
+            Basis::Gradient => self.gradient_with(p, period),
+            Basis::Simplex => self.simplex(p),
+            // nearest distances rarely exceed 1
+            Basis::Worley => (self.worley_with(p, period)[0] * 2. - 1.).min(1.),
+        }
+    }
+
+    pub fn sample<const N: usize>(&self, basis: Basis, p: [f64; N]) -> f64 {
+        self.basis_with(basis, p, self.period)
+    }
+
+    /// Fractal Brownian motion, `octaves` layers of doubling frequency with amplitudes
+    /// shrinking by `gain`, normalized to the range of one layer. The period doubles with
+    /// the frequency so the sum tiles like the first octave.
+    pub fn fbm<const N: usize>(&self, basis: Basis, p: [f64; N], octaves: usize, gain: f64) -> f64 {
+        let mut sum = 0.;
+        let mut norm = 0.;
+        let mut amplitude = 1.;
+        for k in 0..octaves.max(1) {
+            let frequency = (1u64 << k) as f64;
+            let period = self.period.map(|p| p << k);
+            sum += amplitude * self.basis_with(basis, p.map(|c| c * frequency), period);
+            norm += amplitude;
+            amplitude *= gain;
+        }
+        sum / norm
+    }
+
+    /// Domain warping, `fbm` at `p` displaced by `strength` times an `fbm` vector field
+    pub fn warp<const N: usize>(
+        &self,
+        basis: Basis,
+        p: [f64; N],
+        octaves: usize,
+        gain: f64,
+        strength: f64,
+    ) -> f64 {
+        // shifted copies of the field make the components independent
+        let q: [f64; N] = array::from_fn(|i| {
+            let shifted: [f64; N] = array::from_fn(|k| p[k] + 17. * (i + 1) as f64);
+            self.fbm(basis, shifted, octaves, gain)
+        });
+        let warped: [f64; N] = array::from_fn(|i| p[i] + strength * q[i]);
+        self.fbm(basis, warped, octaves, gain)
+    }
+
+    /// `fbm` at `p` that repeats when `t` advances by `1`, by walking a circle of `radius`
+    /// through two extra dimensions, a larger radius changes faster
+    pub fn looped(
+        &self,
+        basis: Basis,
+        [x, y]: [f64; 2],
+        t: f64,
+        radius: f64,
+        octaves: usize,
+        gain: f64,
+    ) -> f64 {
+        let a = TAU * t;
+        self.fbm(
+            basis,
+            [x, y, radius * a.cos(), radius * a.sin()],
+            octaves,
+            gain,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_test() {
+        let noise = Noise::new(7);
+        let p = [0.3, 1.7, -2.2];
+        assert_eq!(noise.gradient(p), Noise::new(7).gradient(p));
+        assert_ne!(noise.gradient(p), Noise::new(8).gradient(p));
+        assert_eq!(noise.gradient([3., -2.]), 0.);
+        // every basis stays in range and varies
+        for basis in [Basis::Value, Basis::Gradient, Basis::Simplex, Basis::Worley] {
+            let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+            for i in 0..2000 {
+                let i = i as f64;
+                let v2 = noise.sample(basis, [i * 0.37, i * 0.11]);
+                let v3 = noise.sample(basis, [i * 0.37, i * 0.11, i * 0.23]);
+                let v4 = noise.sample(basis, [i * 0.37, i * 0.11, i * 0.23, i * 0.07]);
+                for v in [v2, v3, v4] {
+                    min = min.min(v);
+                    max = max.max(v);
+                }
+            }
+            assert!(-1. <= min && max <= 1., "{:?} {} {}", basis, min, max);
+            assert!(max - min > 0.8, "{:?} {} {}", basis, min, max);
+        }
+        let [f1, f2] = noise.worley([0.5, 0.5]);
+        assert!(0. <= f1 && f1 <= f2);
+        // periodic fields and loops repeat exactly
+        let tiled = noise.with_period(4);
+        for basis in [Basis::Value, Basis::Gradient, Basis::Worley] {
+            let a = tiled.fbm(basis, [0.3, 1.1], 3, 0.5);
+            let b = tiled.fbm(basis, [4.3, -2.9], 3, 0.5);
+            assert!((a - b).abs() < 1e-12);
+            assert!(
+                (tiled.warp(basis, [0.3, 1.1], 2, 0.5, 1.)
+                    - tiled.warp(basis, [0.3, 5.1], 2, 0.5, 1.))
+                .abs()
+                    < 1e-12
+            );
+        }
+        let a = noise.looped(Basis::Simplex, [0.4, 0.9], 0.25, 1., 3, 0.5);
+        let b = noise.looped(Basis::Simplex, [0.4, 0.9], 1.25, 1., 3, 0.5);
+        assert!((a - b).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,53 @@
+/// # Motion Blur Shutter
+/// the shutter stays open for `angle / 360` of a frame interval,
+/// averaging `samples` sub-frames spread evenly over that time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shutter {
+    pub samples: usize,
+    /// degrees, `360` blurs over the whole frame interval
+    pub angle: f64,
+}
+
+impl Default for Shutter {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            angle: 180.,
+        }
+    }
+}
+
+impl Shutter {
+    pub fn new(samples: usize, angle: f64) -> Self {
+        assert!(samples != 0);
+        Self { samples, angle }
+    }
+
+    /// # Returns
+    /// sub-frame times in seconds after the start of a frame, `[0]` without blur
+    pub fn offsets(&self, fps: usize) -> Vec<f64> {
+        if self.samples <= 1 {
+            return vec![0.];
+        }
+        let open = self.angle / 360. / fps as f64;
+        (0..self.samples)
+            .map(|k| (k as f64 + 0.5) / self.samples as f64 * open)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shutter_test() {
+        let shutter = Shutter::new(4, 180.);
+        let offsets = shutter.offsets(10);
+        for (t, expected) in offsets.iter().zip([0.00625, 0.01875, 0.03125, 0.04375]) {
+            assert!((t - expected).abs() < 1e-12);
+        }
+        assert_eq!(Shutter::new(2, 360.).offsets(1), [0.25, 0.75]);
+        assert_eq!(Shutter::default().offsets(10), [0.]);
+    }
+}
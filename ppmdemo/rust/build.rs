@@ -0,0 +1,42 @@
+//! Generates the swizzle accessors of `vector::{Vec2, Vec3, Vec4}`,
+//! every 2 to 4 letter combination of `xyzw` and of `rgba`.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let mut code = String::new();
+    for (ty, n) in [("Vec2", 2), ("Vec3", 3), ("Vec4", 4)] {
+        writeln!(code, "impl {} {{", ty).unwrap();
+        for len in 2..=4 {
+            for i in 0..(n as usize).pow(len) {
+                let components = (0..len)
+                    .map(|k| i / (n as usize).pow(len - 1 - k) % n as usize)
+                    .collect::<Vec<_>>();
+                let fields = components
+                    .iter()
+                    .map(|&c| format!("self.{}", &"xyzw"[c..c + 1]))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                for set in ["xyzw", "rgba"] {
+                    let name = components
+                        .iter()
+                        .map(|&c| &set[c..c + 1])
+                        .collect::<String>();
+                    writeln!(
+                        code,
+                        "    #[inline]\n    pub fn {}(self) -> Vec{} {{\n        Vec{}::new({})\n    }}",
+                        name, len, len, fields
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        writeln!(code, "}}").unwrap();
+    }
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("swizzle.rs");
+    fs::write(out, code).unwrap();
+    println!("cargo::rerun-if-changed=build.rs");
+}
@@ -0,0 +1,3 @@
+// the swirl of shader.rs as a twigl one-liner. FC is bottom-up like gl_FragCoord while the
+// native demo passes rows top-down, so `glsl` renders the `shader` demo upside down
+vec2 p=(FC.xy*2.-r)/r.y,l,i,v=p*(l+=4.-4.*abs(.7-dot(p,p)));for(;i.y++<8.;o+=(sin(v.xyyx)+1.)*abs(v.x-v.y))v+=cos(v.yx*i.y+i+t)/i.y+.7;o=tanh(5.*exp(l.x-4.-p.y*vec4(-1,1,2,0))/o);
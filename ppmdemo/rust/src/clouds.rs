@@ -0,0 +1,30 @@
+use crate::frame::{Frames, Options};
+use crate::vector::{Vec2, Vec3, smoothstep};
+use common::noise::{Basis, Noise};
+
+/// # Clouds
+/// domain-warped simplex fBm drifting over a sky, `p` in units of the height,
+/// the whole animation loops when `t` advances by `1`
+fn clouds(noise: &Noise, p: Vec2, t: f64) -> [f64; 3] {
+    let p = p * 3.;
+    let radius = 0.6;
+    let fbm = |p: Vec2| noise.looped(Basis::Simplex, p.to_array(), t, radius, 5, 0.5);
+    let q = Vec2::new(fbm(p), fbm(p + Vec2::new(5.2, 1.3)));
+    let n = fbm(p + q * 1.5);
+    let cover = smoothstep(-0.25, 0.45, n);
+    let sky = Vec3::new(0.25, 0.45, 0.8).mix(Vec3::new(0.6, 0.75, 0.95), p.y / 3.);
+    // thicker cloud is darker underneath
+    let cloud = Vec3::splat(1.).mix(Vec3::new(0.7, 0.72, 0.8), smoothstep(0.3, 1., n));
+    sky.mix(cloud, cover).to_array()
+}
+
+pub fn generate(options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let noise = Noise::new(7);
+    let frames = Frames::new("clouds", w, h, fps).with_options(options);
+    frames.render(|[x, y], t| clouds(&noise, Vec2::new(x, y) / h as f64, t));
+}
@@ -0,0 +1,213 @@
+//! # Color Pipeline
+//! Shaders compute linear-light RGB, which is tone mapped into `[0, 1]`,
+//! optionally sRGB encoded and dithered when quantized to bytes.
+
+use common::color::srgb_encode;
+use std::sync::OnceLock;
+
+pub type Rgb = common::color::Rgb;
+
+/// # Tone Mappers
+/// compress linear values of any brightness into `[0, 1]`
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ToneMap {
+    /// cuts off everything above `1`
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(self, rgb: Rgb) -> Rgb {
+        rgb.map(|c| {
+            let c = c.max(0.);
+            match self {
+                ToneMap::Clamp => c,
+                ToneMap::Reinhard => c / (1. + c),
+                ToneMap::Aces => c * (2.51 * c + 0.03) / (c * (2.43 * c + 0.59) + 0.14),
+            }
+            .min(1.)
+        })
+    }
+}
+
+/// # Dithering
+/// a threshold per pixel added before rounding down,
+/// spreading the quantization error into a fine pattern instead of bands
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Dither {
+    /// truncates like `as u8`
+    #[default]
+    None,
+    /// 8x8 Bayer matrix
+    Ordered,
+    /// 16x16 tile generated with void-and-cluster
+    BlueNoise,
+}
+
+/// Rank of a pixel in the 8x8 Bayer matrix, `0..64`
+fn bayer(x: usize, y: usize) -> usize {
+    let mut v = 0;
+    for bit in 0..3 {
+        let xb = (x >> bit) & 1;
+        let yb = (y >> bit) & 1;
+        v = v << 2 | (xb ^ yb) << 1 | yb;
+    }
+    v
+}
+
+const BLUE: usize = 16;
+
+/// # Void and Cluster
+/// ranks the pixels of a tileable `BLUE * BLUE` texture so that every
+/// prefix of the ranking is spread evenly, without low frequency clumps
+fn void_and_cluster() -> Vec<usize> {
+    let n = BLUE * BLUE;
+    let sigma = 1.5f32;
+    let kernel = (0..n)
+        .map(|i| {
+            let wrap = |d: usize| d.min(BLUE - d) as f32;
+            let (dx, dy) = (wrap(i % BLUE), wrap(i / BLUE));
+            (-(dx * dx + dy * dy) / (2. * sigma * sigma)).exp()
+        })
+        .collect::<Vec<_>>();
+    let mut energy = vec![0f32; n];
+    let mut ones = vec![false; n];
+    let toggle = |energy: &mut [f32], ones: &mut [bool], i: usize| {
+        ones[i] = !ones[i];
+        let sign = if ones[i] { 1. } else { -1. };
+        let (x0, y0) = (i % BLUE, i / BLUE);
+        for (j, e) in energy.iter_mut().enumerate() {
+            let dx = (j % BLUE + BLUE - x0) % BLUE;
+            let dy = (j / BLUE + BLUE - y0) % BLUE;
+            *e += sign * kernel[dy * BLUE + dx];
+        }
+    };
+    // tightest cluster among ones, largest void among zeros
+    let extreme = |energy: &[f32], ones: &[bool], one: bool| {
+        let candidates = (0..n).filter(|&i| ones[i] == one);
+        if one {
+            candidates.max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        } else {
+            candidates.min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        }
+        .unwrap()
+    };
+    // initial pattern, a tenth of the pixels from a fixed LCG
+    let mut seed = 1u32;
+    while ones.iter().filter(|&&o| o).count() < n / 10 {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        let i = (seed >> 8) as usize % n;
+        if !ones[i] {
+            toggle(&mut energy, &mut ones, i);
+        }
+    }
+    // move ones from clusters to voids until stable
+    loop {
+        let cluster = extreme(&energy, &ones, true);
+        toggle(&mut energy, &mut ones, cluster);
+        let void = extreme(&energy, &ones, false);
+        toggle(&mut energy, &mut ones, void);
+        if void == cluster {
+            break;
+        }
+    }
+    let initial = (energy.clone(), ones.clone());
+    let count = ones.iter().filter(|&&o| o).count();
+    let mut rank = vec![0; n];
+    // remove the tightest clusters first, they get the lowest ranks
+    for r in (0..count).rev() {
+        let cluster = extreme(&energy, &ones, true);
+        toggle(&mut energy, &mut ones, cluster);
+        rank[cluster] = r;
+    }
+    // then fill the largest voids
+    let (mut energy, mut ones) = initial;
+    for r in count..n {
+        let void = extreme(&energy, &ones, false);
+        toggle(&mut energy, &mut ones, void);
+        rank[void] = r;
+    }
+    rank
+}
+
+fn blue_noise() -> &'static [usize] {
+    static RANKS: OnceLock<Vec<usize>> = OnceLock::new();
+    RANKS.get_or_init(void_and_cluster)
+}
+
+impl Dither {
+    /// # Returns
+    /// threshold in `[0, 1)` for the pixel at `x`, `y`
+    pub fn threshold(self, x: usize, y: usize) -> f32 {
+        match self {
+            Dither::None => 0.,
+            Dither::Ordered => (bayer(x % 8, y % 8) as f32 + 0.5) / 64.,
+            Dither::BlueNoise => {
+                let rank = blue_noise()[y % BLUE * BLUE + x % BLUE];
+                (rank as f32 + 0.5) / (BLUE * BLUE) as f32
+            }
+        }
+    }
+}
+
+/// # Quantization
+/// the default passes shader output through unchanged, as display-encoded values
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Encoding {
+    pub tone_map: ToneMap,
+    /// treat the shader output as linear light and sRGB encode it
+    pub srgb: bool,
+    pub dither: Dither,
+}
+
+impl Encoding {
+    pub fn quantize(&self, rgb: Rgb, x: usize, y: usize) -> [u8; 3] {
+        let d = self.dither.threshold(x, y);
+        self.tone_map.apply(rgb).map(|c| {
+            let c = if self.srgb { srgb_encode(c) } else { c };
+            (c * 255. + d).min(255.) as u8
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_test() {
+        for tone_map in [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::Aces] {
+            let ramp = [0., 0.1, 0.5, 1., 4., 100.].map(|c| tone_map.apply([c; 3])[0]);
+            assert!(ramp.windows(2).all(|w| w[0] <= w[1] && w[1] <= 1.));
+        }
+        assert_eq!(ToneMap::Reinhard.apply([1., 3., -1.]), [0.5, 0.75, 0.]);
+        for dither in [Dither::Ordered, Dither::BlueNoise] {
+            let size = if dither == Dither::Ordered { 8 } else { BLUE };
+            let mut ts = (0..size * size)
+                .map(|i| dither.threshold(i % size, i / size))
+                .collect::<Vec<_>>();
+            ts.sort_by(f32::total_cmp);
+            for (i, t) in ts.iter().enumerate() {
+                assert_eq!(*t, (i as f32 + 0.5) / (size * size) as f32);
+            }
+            // a flat field between two levels keeps its mean
+            let encoding = Encoding {
+                dither,
+                ..Default::default()
+            };
+            let c = 100.25 / 255.;
+            let sum = (0..size * size)
+                .map(|i| encoding.quantize([c; 3], i % size, i / size)[0] as f32)
+                .sum::<f32>();
+            assert!((sum / (size * size) as f32 - 100.25).abs() < 0.02);
+        }
+        assert_eq!(
+            Encoding::default().quantize([0.5, 1.5, -1.], 0, 0),
+            [127, 255, 0]
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use crate::color::{Encoding, Rgb};
+use common::color::{srgb_decode, srgb_encode};
+use common::filter::{self, Filter};
+use common::hdr;
+use common::shutter::Shutter;
+use std::env;
+use std::fs;
+use std::io::BufWriter;
+use std::io::Write as IoWrite;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// # Sample Patterns
+/// positions inside a pixel, `[0, 0]` is the top-left corner
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Samples {
+    /// one sample at the pixel corner
+    #[default]
+    One,
+    /// `n * n` samples on a regular grid
+    Grid(usize),
+    /// 4 samples on a grid rotated by about 26.6 degrees, no two share a row or column
+    RotatedGrid,
+}
+
+impl Samples {
+    pub fn offsets(self) -> Vec<[f64; 2]> {
+        match self {
+            Samples::One => vec![[0., 0.]],
+            Samples::Grid(n) => {
+                let n = n.max(1);
+                (0..n * n)
+                    .map(|i| {
+                        let x = (i % n) as f64 + 0.5;
+                        let y = (i / n) as f64 + 0.5;
+                        [x / n as f64, y / n as f64]
+                    })
+                    .collect()
+            }
+            Samples::RotatedGrid => vec![
+                [0.125, 0.625],
+                [0.375, 0.125],
+                [0.625, 0.875],
+                [0.875, 0.375],
+            ],
+        }
+    }
+}
+
+/// # File Formats
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// quantized with the frame's `Encoding`
+    #[default]
+    Ppm,
+    /// Portable FloatMap, unquantized
+    Pfm,
+    /// Radiance RGBE, unquantized
+    Rgbe,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ppm => "ppm",
+            Format::Pfm => "pfm",
+            Format::Rgbe => "hdr",
+        }
+    }
+}
+
+/// # Render Options
+/// the settings every demo takes from the command line, see `Frames::with_options`
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub samples: Samples,
+    pub shutter: Shutter,
+    pub encoding: Encoding,
+    pub format: Format,
+    pub filters: Vec<Filter>,
+    pub resume: bool,
+}
+
+/// # Frame Renderer
+/// evaluates a shader for every pixel of every frame and writes
+/// `output/{dir}/frame-{i}.ppm` or the HDR `format`, frames are rendered on one thread per core.
+/// Each file is written under a temporary name and renamed when complete,
+/// so an interrupted render never leaves a truncated frame under its final name.
+pub struct Frames {
+    pub w: usize,
+    pub h: usize,
+    pub fps: usize,
+    /// number of frames
+    pub count: usize,
+    pub dir: String,
+    pub samples: Samples,
+    pub shutter: Shutter,
+    pub encoding: Encoding,
+    pub format: Format,
+    /// post-processing of every frame before it is encoded
+    pub filters: Vec<Filter>,
+    /// skips frames whose files are already complete
+    pub resume: bool,
+}
+
+impl Frames {
+    /// One second of frames
+    pub fn new(dir: &str, w: usize, h: usize, fps: usize) -> Self {
+        Self {
+            w,
+            h,
+            fps,
+            count: fps,
+            dir: dir.to_string(),
+            samples: Samples::default(),
+            shutter: Shutter::default(),
+            encoding: Encoding::default(),
+            format: Format::default(),
+            filters: Vec::new(),
+            resume: false,
+        }
+    }
+
+    pub fn with_samples(mut self, samples: Samples) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_shutter(mut self, shutter: Shutter) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_filters(mut self, filters: Vec<Filter>) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    pub fn with_options(self, options: Options) -> Self {
+        self.with_samples(options.samples)
+            .with_shutter(options.shutter)
+            .with_encoding(options.encoding)
+            .with_format(options.format)
+            .with_filters(options.filters)
+            .with_resume(options.resume)
+    }
+
+    /// # Params
+    /// `shader` maps a fragment coordinate and the time in seconds to a color,
+    /// every pixel averages all sample positions at all sub-frame times after `t`
+    ///
+    /// # Returns
+    /// the unclamped f32 framebuffer before the filters, rows from the top
+    pub fn shade(&self, shader: &impl Fn([f64; 2], f64) -> [f64; 3], t: f64) -> Vec<Rgb> {
+        let offsets = self.samples.offsets();
+        let times = self.shutter.offsets(self.fps);
+        let n = (offsets.len() * times.len()) as f64;
+        let mut buf = Vec::with_capacity(self.w * self.h);
+        for y in 0..self.h {
+            for x in 0..self.w {
+                let mut c = [0.; 3];
+                for dt in &times {
+                    for [dx, dy] in &offsets {
+                        let s = shader([x as f64 + dx, y as f64 + dy], t + dt);
+                        for i in 0..3 {
+                            c[i] += s[i];
+                        }
+                    }
+                }
+                buf.push(c.map(|c| (c / n) as f32));
+            }
+        }
+        buf
+    }
+
+    /// Runs the filters in linear light, decoding and re-encoding
+    /// the display-encoded output of shaders without `srgb`
+    fn filter(&self, pixels: &mut [Rgb]) {
+        if self.filters.is_empty() {
+            return;
+        }
+        let encoded = !self.encoding.srgb;
+        if encoded {
+            pixels.iter_mut().for_each(|c| *c = c.map(srgb_decode));
+        }
+        filter::apply(&self.filters, self.w, self.h, pixels);
+        if encoded {
+            pixels.iter_mut().for_each(|c| *c = c.map(srgb_encode));
+        }
+    }
+
+    /// # Returns
+    /// the unclamped f32 framebuffer after the filters, rows from the top
+    pub fn frame_hdr(&self, shader: &impl Fn([f64; 2], f64) -> [f64; 3], t: f64) -> Vec<Rgb> {
+        let mut pixels = self.shade(shader, t);
+        self.filter(&mut pixels);
+        pixels
+    }
+
+    /// rgb bytes of `pixels` quantized with `encoding`
+    fn quantize(&self, pixels: &[Rgb]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.w * self.h * 3);
+        for (i, &rgb) in pixels.iter().enumerate() {
+            buf.extend(self.encoding.quantize(rgb, i % self.w, i / self.w));
+        }
+        buf
+    }
+
+    /// # Returns
+    /// rgb bytes of the frame quantized with `encoding`, rows from the top
+    pub fn frame(&self, shader: &impl Fn([f64; 2], f64) -> [f64; 3], t: f64) -> Vec<u8> {
+        self.quantize(&self.frame_hdr(shader, t))
+    }
+
+    fn header(&self) -> String {
+        let Self { w, h, .. } = *self;
+        match self.format {
+            Format::Ppm => {
+                let rgb_max = 0xFF;
+                format!("P6\n{} {}\n{}\n", w, h, rgb_max)
+            }
+            Format::Pfm => hdr::pfm_header(w, h),
+            Format::Rgbe => hdr::rgbe_header(w, h),
+        }
+    }
+
+    /// # Returns
+    /// the whole file in `format` of a frame shaded as `pixels`, after the filters
+    pub fn encode_pixels(&self, mut pixels: Vec<Rgb>) -> Vec<u8> {
+        let Self { w, h, .. } = *self;
+        self.filter(&mut pixels);
+        match self.format {
+            Format::Ppm => {
+                let mut buf = self.header().into_bytes();
+                buf.extend(self.quantize(&pixels));
+                buf
+            }
+            Format::Pfm => hdr::encode_pfm(w, h, &pixels),
+            Format::Rgbe => hdr::encode_rgbe(w, h, &pixels),
+        }
+    }
+
+    /// # Returns
+    /// the whole file of the frame in `format`
+    pub fn encode(&self, shader: &impl Fn([f64; 2], f64) -> [f64; 3], t: f64) -> Vec<u8> {
+        self.encode_pixels(self.shade(shader, t))
+    }
+
+    /// # Returns
+    /// whether `file` has the header of this size and format and all of its pixel data
+    pub fn is_complete(&self, file: &[u8]) -> bool {
+        let header = self.header();
+        let Some(data) = file.strip_prefix(header.as_bytes()) else {
+            return false;
+        };
+        let Self { w, h, .. } = *self;
+        match self.format {
+            Format::Ppm => data.len() == w * h * 3,
+            Format::Pfm => data.len() == w * h * 12,
+            Format::Rgbe => hdr::rgbe_complete(w, h, data),
+        }
+    }
+
+    /// Creates `output/{dir}`
+    fn output_dir(&self) -> String {
+        let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let output_dir = format!("{}/output/{}", root, self.dir);
+        if !fs::exists(&output_dir).unwrap() {
+            fs::create_dir_all(&output_dir).unwrap();
+        }
+        output_dir
+    }
+
+    fn output_path(&self, output_dir: &str, i: usize) -> String {
+        format!("{}/frame-{:02}.{}", output_dir, i, self.format.extension())
+    }
+
+    /// Also removes the temporary file an interrupted render may have left for frame `i`
+    ///
+    /// # Returns
+    /// whether frame `i` still has to be written, always without `resume`
+    fn pending(&self, output_dir: &str, i: usize) -> bool {
+        let output_path = self.output_path(output_dir, i);
+        let _ = fs::remove_file(format!("{}.tmp", output_path));
+        if !self.resume {
+            return true;
+        }
+        match fs::read(&output_path) {
+            Ok(file) if self.is_complete(&file) => {
+                println!("Skipped {}", output_path);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Writes a temporary file next to the frame and renames it over the frame,
+    /// the temporary file is removed when either fails
+    fn write(&self, output_dir: &str, i: usize, buf: &[u8]) {
+        let output_path = self.output_path(output_dir, i);
+        let temp_path = format!("{}.tmp", output_path);
+        let written = (|| {
+            let mut file = BufWriter::new(fs::File::create(&temp_path)?);
+            IoWrite::write_all(&mut file, buf)?;
+            file.into_inner()?.sync_all()?;
+            fs::rename(&temp_path, &output_path)
+        })();
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp_path);
+            panic!("{}: {}", output_path, e);
+        }
+        println!("Generated {}", output_path);
+    }
+
+    pub fn render(&self, shader: impl Fn([f64; 2], f64) -> [f64; 3] + Sync) {
+        self.render_frames(|t| self.shade(&shader, t));
+    }
+
+    /// Like `render`, `frame` shades the whole frame at a time in seconds
+    pub fn render_frames(&self, frame: impl Fn(f64) -> Vec<Rgb> + Sync) {
+        let fps = self.fps;
+        let output_dir = self.output_dir();
+        let next = AtomicUsize::new(0);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        thread::scope(|scope| {
+            for _ in 0..threads.min(self.count) {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= self.count {
+                            break;
+                        }
+                        if !self.pending(&output_dir, i) {
+                            continue;
+                        }
+                        let buf = self.encode_pixels(frame(i as f64 / fps as f64));
+                        self.write(&output_dir, i, &buf);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Like `render_frames` on the calling thread in order, for frames depending on earlier ones.
+    /// Resuming still shades every frame up to the last pending one, but only writes pending frames.
+    pub fn render_sequential(&self, mut frame: impl FnMut(f64) -> Vec<Rgb>) {
+        let output_dir = self.output_dir();
+        let pending = (0..self.count)
+            .map(|i| self.pending(&output_dir, i))
+            .collect::<Vec<_>>();
+        let end = pending.iter().rposition(|&p| p).map_or(0, |i| i + 1);
+        for (i, &pending) in pending.iter().enumerate().take(end) {
+            let pixels = frame(i as f64 / self.fps as f64);
+            if pending {
+                self.write(&output_dir, i, &self.encode_pixels(pixels));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_test() {
+        let edge = |[x, _]: [f64; 2], _| if x < 1.5 { [1.; 3] } else { [0.; 3] };
+        let frames = Frames::new("test", 3, 1, 1);
+        assert_eq!(
+            frames.frame(&edge, 0.),
+            [255, 255, 255, 255, 255, 255, 0, 0, 0]
+        );
+        let frames = frames.with_samples(Samples::Grid(2));
+        assert_eq!(
+            frames.frame(&edge, 0.),
+            [255, 255, 255, 127, 127, 127, 0, 0, 0]
+        );
+        let frames = frames.with_samples(Samples::RotatedGrid);
+        assert_eq!(
+            frames.frame(&edge, 0.),
+            [255, 255, 255, 127, 127, 127, 0, 0, 0]
+        );
+        let moving = |_, t: f64| if t < 0.5 { [1.; 3] } else { [0.; 3] };
+        let frames = Frames::new("test", 1, 1, 1).with_shutter(Shutter::new(4, 360.));
+        assert_eq!(frames.frame(&moving, 0.), [127, 127, 127]);
+        // filters blend in linear light, also for display-encoded output
+        let filters = vec![Filter::BoxBlur { radius: 1 }];
+        let frames = Frames::new("test", 3, 1, 1).with_filters(filters);
+        let step = |[x, _]: [f64; 2], _| if x < 0.5 { [1.; 3] } else { [0.; 3] };
+        let [a, b] = [2. / 3., 1. / 3.].map(|c| (srgb_encode(c) * 255.) as u8);
+        assert_eq!(frames.frame(&step, 0.), [a, a, a, b, b, b, 0, 0, 0]);
+        for samples in [Samples::Grid(3), Samples::RotatedGrid] {
+            let offsets = samples.offsets();
+            let mean = offsets
+                .iter()
+                .fold([0.; 2], |m, o| [m[0] + o[0], m[1] + o[1]])
+                .map(|c| c / offsets.len() as f64);
+            assert!(mean.iter().all(|m| (m - 0.5).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn resume_test() {
+        let gradient = |[x, y]: [f64; 2], _| [x / 16., y / 4., 2.];
+        for format in [Format::Ppm, Format::Pfm, Format::Rgbe] {
+            let frames = Frames::new("test", 16, 4, 1).with_format(format);
+            let file = frames.encode(&gradient, 0.);
+            assert!(frames.is_complete(&file), "{:?}", format);
+            assert!(!frames.is_complete(&file[..file.len() - 1]), "{:?}", format);
+            let mut longer = file.clone();
+            longer.push(0);
+            assert!(!frames.is_complete(&longer), "{:?}", format);
+            // a frame of another size is not resumed
+            let other = Frames::new("test", 16, 3, 1).with_format(format);
+            assert!(!other.is_complete(&file), "{:?}", format);
+        }
+        // a truncated frame and a stale temporary file in a render of black frames
+        let black = |_, _| [0.; 3];
+        let white = |_, _| [1.; 3];
+        let frames = Frames::new("test-resume", 2, 2, 3).with_resume(true);
+        let output_dir = frames.output_dir();
+        let path = |i| frames.output_path(&output_dir, i);
+        let damage = || {
+            let file = fs::read(path(1)).unwrap();
+            fs::write(path(1), &file[..file.len() - 1]).unwrap();
+            fs::write(format!("{}.tmp", path(1)), b"P6").unwrap();
+        };
+        frames.render(black);
+        let complete = fs::read(path(0)).unwrap();
+        damage();
+        // resuming with another shader only replaces the truncated frame
+        frames.render(white);
+        let read = |i| fs::read(path(i)).unwrap();
+        assert_eq!((read(0), read(2)), (complete.clone(), complete.clone()));
+        assert_eq!(read(1), frames.encode(&white, 0.));
+        assert!(!fs::exists(format!("{}.tmp", path(1))).unwrap());
+        // frames depending on earlier ones are shaded up to the last pending one
+        fs::write(path(1), &complete).unwrap();
+        damage();
+        let mut times = Vec::new();
+        frames.render_sequential(|t| {
+            times.push(t);
+            frames.shade(&white, t)
+        });
+        assert_eq!(times.len(), 2);
+        assert_eq!((read(0), read(2)), (complete.clone(), complete));
+        assert_eq!(read(1), frames.encode(&white, 0.));
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
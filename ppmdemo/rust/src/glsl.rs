@@ -0,0 +1,1203 @@
+//! # GLSL Subset
+//! Fragment shaders in the style of twigl, either a bare list of statements
+//! or `void main() { ... }` with `precision`, `uniform` and `out` declarations ignored.
+//! ```glsl
+//! vec2 p = (FC.xy * 2. - r) / r.y;
+//! for (float i = 0.; i < 3.; i++) o.rgb += .1 / length(p - vec2(i - 1., 0));
+//! ```
+//! Uniforms:
+//! * `FC`: vec4 fragment coordinate, `y` points up
+//! * `r`: vec2 resolution
+//! * `t`: float time in seconds
+//! * `o`: vec4 output color, starts at zero
+//!
+//! Types are `bool`, `int`, `float`, `vec2`, `vec3`, `vec4` and `mat2`, ints convert to floats implicitly.
+//! Statements are declarations, expressions, blocks, `if`, `for`, `while`, `break`, `continue` and `return`.
+//! There are no user functions, arrays or structs.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl ShaderError {
+    fn new(line: usize, col: usize, msg: impl Into<String>) -> Self {
+        Self {
+            line,
+            col,
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Every value is stored in four components, scalars in the first one,
+/// bools as `0` or `1` and `mat2` column by column
+pub type Value = [f64; 4];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    Bool,
+    Int,
+    Float,
+    Vec(usize),
+    Mat2,
+}
+
+impl Type {
+    pub fn size(self) -> usize {
+        match self {
+            Type::Bool | Type::Int | Type::Float => 1,
+            Type::Vec(n) => n,
+            Type::Mat2 => 4,
+        }
+    }
+
+    fn numeric(self) -> bool {
+        self != Type::Bool
+    }
+
+    fn scalar(self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => Type::Bool,
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "vec2" => Type::Vec(2),
+            "vec3" => Type::Vec(3),
+            "vec4" => Type::Vec(4),
+            "mat2" => Type::Mat2,
+            _ => return None,
+        })
+    }
+
+    /// `Int` where `Float` is expected is allowed
+    fn accepts(self, other: Type) -> bool {
+        self == other || (self == Type::Float && other == Type::Int)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Vec(n) => write!(f, "vec{}", n),
+            Type::Mat2 => write!(f, "mat2"),
+        }
+    }
+}
+
+/// Slots of the uniforms
+pub const FC: usize = 0;
+pub const R: usize = 1;
+pub const T: usize = 2;
+pub const O: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// `mat2 * vec2`
+    MatVec,
+    /// `vec2 * mat2`
+    VecMat,
+    /// `mat2 * mat2`
+    MatMat,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Builtin {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Exp2,
+    Log,
+    Log2,
+    Sqrt,
+    InverseSqrt,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Fract,
+    Round,
+    Radians,
+    Degrees,
+    Pow,
+    Mod,
+    Min,
+    Max,
+    Clamp,
+    Mix,
+    Step,
+    Smoothstep,
+    Length,
+    Distance,
+    Dot,
+    Cross,
+    Normalize,
+    Reflect,
+}
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Self> {
+        use Builtin::*;
+        Some(match name {
+            "sin" => Sin,
+            "cos" => Cos,
+            "tan" => Tan,
+            "asin" => Asin,
+            "acos" => Acos,
+            "atan" => Atan,
+            "sinh" => Sinh,
+            "cosh" => Cosh,
+            "tanh" => Tanh,
+            "exp" => Exp,
+            "exp2" => Exp2,
+            "log" => Log,
+            "log2" => Log2,
+            "sqrt" => Sqrt,
+            "inversesqrt" => InverseSqrt,
+            "abs" => Abs,
+            "sign" => Sign,
+            "floor" => Floor,
+            "ceil" => Ceil,
+            "fract" => Fract,
+            "round" => Round,
+            "radians" => Radians,
+            "degrees" => Degrees,
+            "pow" => Pow,
+            "mod" => Mod,
+            "min" => Min,
+            "max" => Max,
+            "clamp" => Clamp,
+            "mix" => Mix,
+            "step" => Step,
+            "smoothstep" => Smoothstep,
+            "length" => Length,
+            "distance" => Distance,
+            "dot" => Dot,
+            "cross" => Cross,
+            "normalize" => Normalize,
+            "reflect" => Reflect,
+            _ => return None,
+        })
+    }
+
+    /// Number of arguments, `atan` takes one or two
+    fn arity(self) -> &'static [usize] {
+        use Builtin::*;
+        match self {
+            Atan => &[1, 2],
+            Pow | Mod | Min | Max | Step | Distance | Dot | Cross | Reflect => &[2],
+            Clamp | Mix | Smoothstep => &[3],
+            _ => &[1],
+        }
+    }
+}
+
+/// A variable, optionally through a swizzle without repeated components
+#[derive(Clone, Debug, PartialEq)]
+pub struct LValue {
+    pub var: usize,
+    pub swizzle: Option<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    Literal(Value),
+    Var(usize),
+    Swizzle(Box<Expr>, Vec<usize>),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    /// `&&` and `||` only evaluate the right side when needed
+    Logic(BinOp, Box<Expr>, Box<Expr>),
+    /// evaluates both, the value is the right one
+    Comma(Box<Expr>, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(Builtin, Vec<Expr>),
+    /// one scalar fills a vector or the diagonal of a matrix, otherwise components are concatenated
+    Construct(Type, Vec<Expr>),
+    /// `None` is a plain `=`, otherwise `lhs = lhs op rhs`
+    Assign(LValue, Option<BinOp>, Box<Expr>),
+    /// adds `delta`, a prefix returns the new value and a postfix the old one
+    Step(LValue, f64, bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub ty: Type,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stmt {
+    Expr(Expr),
+    Block(Vec<Stmt>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    /// `for` and `while`, the init statements run once before
+    Loop {
+        init: Vec<Stmt>,
+        cond: Option<Expr>,
+        step: Option<Expr>,
+        body: Box<Stmt>,
+    },
+    Break,
+    Continue,
+    Return,
+}
+
+/// # Parsed Shader
+/// variables are resolved to slots, the first ones are the uniforms `FC`, `r`, `t` and `o`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    pub body: Vec<Stmt>,
+    /// type of every slot
+    pub vars: Vec<Type>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    /// the flag is set for float literals
+    Num(f64, bool),
+    Ident(String),
+    Punct(&'static str),
+    Eof,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    tok: Tok,
+    line: usize,
+    col: usize,
+}
+
+/// Longest first
+const PUNCTS: [&str; 32] = [
+    "++", "--", "+=", "-=", "*=", "/=", "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/",
+    "<", ">", "=", "!", "?", ":", ";", ",", ".", "(", ")", "{", "}", "[", "]", "%",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, ShaderError> {
+    let mut tokens = Vec::new();
+    for (i, text) in src.lines().enumerate() {
+        let line = i + 1;
+        if text.trim_start().starts_with('#') {
+            continue;
+        }
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            let col = j + 1;
+            let rest = &chars[j..];
+            if c.is_whitespace() {
+                j += 1;
+            } else if rest.starts_with(&['/', '/']) {
+                break;
+            } else if c.is_ascii_digit()
+                || (c == '.' && rest.get(1).is_some_and(char::is_ascii_digit))
+            {
+                let start = j;
+                let mut float = false;
+                while j < chars.len() && chars[j].is_ascii_digit() {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j] == '.' {
+                    float = true;
+                    j += 1;
+                    while j < chars.len() && chars[j].is_ascii_digit() {
+                        j += 1;
+                    }
+                }
+                if j < chars.len() && (chars[j] == 'e' || chars[j] == 'E') {
+                    let mut k = j + 1;
+                    if k < chars.len() && (chars[k] == '+' || chars[k] == '-') {
+                        k += 1;
+                    }
+                    if k < chars.len() && chars[k].is_ascii_digit() {
+                        float = true;
+                        j = k;
+                        while j < chars.len() && chars[j].is_ascii_digit() {
+                            j += 1;
+                        }
+                    }
+                }
+                let s = chars[start..j].iter().collect::<String>();
+                if j < chars.len() && (chars[j] == 'f' || chars[j] == 'F') {
+                    float = true;
+                    j += 1;
+                }
+                let n = s
+                    .parse()
+                    .map_err(|_| ShaderError::new(line, col, format!("invalid number `{}`", s)))?;
+                tokens.push(Token {
+                    tok: Tok::Num(n, float),
+                    line,
+                    col,
+                });
+            } else if c.is_alphabetic() || c == '_' {
+                let start = j;
+                while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+                    j += 1;
+                }
+                tokens.push(Token {
+                    tok: Tok::Ident(chars[start..j].iter().collect()),
+                    line,
+                    col,
+                });
+            } else {
+                let Some(p) = PUNCTS.iter().find(|p| {
+                    let p = p.chars().collect::<Vec<_>>();
+                    rest.starts_with(&p)
+                }) else {
+                    return Err(ShaderError::new(line, col, format!("unexpected `{}`", c)));
+                };
+                j += p.len();
+                tokens.push(Token {
+                    tok: Tok::Punct(p),
+                    line,
+                    col,
+                });
+            }
+        }
+    }
+    let (line, col) = tokens.last().map_or((1, 1), |t| (t.line, t.col + 1));
+    tokens.push(Token {
+        tok: Tok::Eof,
+        line,
+        col,
+    });
+    Ok(tokens)
+}
+
+/// Removes `/* */` comments, keeping new lines so positions stay right
+fn strip_block_comments(src: &str) -> Result<String, ShaderError> {
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(start) = rest.find("/*") {
+        out += &rest[..start];
+        let Some(end) = rest[start + 2..].find("*/") else {
+            let line = out.lines().count().max(1);
+            let col = out.len() - out.rfind('\n').map_or(0, |i| i + 1) + 1;
+            return Err(ShaderError::new(line, col, "unterminated comment"));
+        };
+        let comment = &rest[start..start + 2 + end + 2];
+        out.extend(comment.chars().map(|c| if c == '\n' { '\n' } else { ' ' }));
+        rest = &rest[start + 2 + end + 2..];
+    }
+    out += rest;
+    Ok(out)
+}
+
+/// Component indices of a swizzle, all from one of `xyzw`, `rgba` or `stpq`
+fn swizzle(s: &str, size: usize) -> Option<Vec<usize>> {
+    let sets = ["xyzw", "rgba", "stpq"];
+    let set = sets.iter().find(|set| s.chars().all(|c| set.contains(c)))?;
+    let components = s.chars().map(|c| set.find(c).unwrap()).collect::<Vec<_>>();
+    (!components.is_empty() && components.len() <= 4 && components.iter().all(|&i| i < size))
+        .then_some(components)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    scopes: Vec<Vec<(String, usize)>>,
+    vars: Vec<Type>,
+    /// loops around the current statement
+    loops: usize,
+}
+
+type Parsed<T> = Result<T, ShaderError>;
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn next(&mut self) -> Token {
+        let t = self.tokens[self.pos].clone();
+        if t.tok != Tok::Eof {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn error(&self, t: &Token, msg: impl Into<String>) -> ShaderError {
+        ShaderError::new(t.line, t.col, msg)
+    }
+
+    fn is(&self, p: &str) -> bool {
+        self.peek().tok == Tok::Punct(PUNCTS.iter().find(|q| **q == p).unwrap())
+    }
+
+    fn eat(&mut self, p: &str) -> bool {
+        let found = self.is(p);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, p: &str) -> Parsed<()> {
+        if self.eat(p) {
+            Ok(())
+        } else {
+            let t = self.peek().clone();
+            Err(self.error(&t, format!("expected `{}`", p)))
+        }
+    }
+
+    fn ident(&mut self) -> Parsed<String> {
+        let t = self.next();
+        match t.tok {
+            Tok::Ident(name) => Ok(name),
+            _ => Err(self.error(&t, "expected a name")),
+        }
+    }
+
+    fn declare(&mut self, name: String, ty: Type) -> usize {
+        self.vars.push(ty);
+        let slot = self.vars.len() - 1;
+        self.scopes.last_mut().unwrap().push((name, slot));
+        slot
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(n, _)| n == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    /// A type name at the current position, `const` is skipped
+    fn peek_type(&mut self) -> Option<Type> {
+        let mut pos = self.pos;
+        if self.tokens[pos].tok == Tok::Ident("const".into()) {
+            pos += 1;
+        }
+        let Tok::Ident(name) = &self.tokens[pos].tok else {
+            return None;
+        };
+        let ty = Type::from_name(name)?;
+        // a constructor call starts an expression statement
+        if self.tokens[pos + 1].tok == Tok::Punct("(") {
+            return None;
+        }
+        self.pos = pos + 1;
+        Some(ty)
+    }
+
+    fn block(&mut self) -> Parsed<Vec<Stmt>> {
+        self.scopes.push(Vec::new());
+        let mut body = Vec::new();
+        while !self.eat("}") {
+            if self.peek().tok == Tok::Eof {
+                let t = self.peek().clone();
+                return Err(self.error(&t, "expected `}`"));
+            }
+            body.extend(self.statement()?);
+        }
+        self.scopes.pop();
+        Ok(body)
+    }
+
+    /// A declaration of several variables becomes several statements
+    fn declaration(&mut self, ty: Type) -> Parsed<Vec<Stmt>> {
+        let mut stmts = Vec::new();
+        loop {
+            let t = self.peek().clone();
+            let name = self.ident()?;
+            let init = if self.eat("=") {
+                let e = self.assignment()?;
+                if !ty.accepts(e.ty) {
+                    return Err(self.error(&t, format!("cannot initialize {} with {}", ty, e.ty)));
+                }
+                Some(e)
+            } else {
+                None
+            };
+            // the name is visible from the next declarator on
+            let var = self.declare(name, ty);
+            let value = init.unwrap_or(Expr {
+                kind: ExprKind::Literal([0.; 4]),
+                ty,
+            });
+            stmts.push(Stmt::Expr(Expr {
+                kind: ExprKind::Assign(LValue { var, swizzle: None }, None, Box::new(value)),
+                ty,
+            }));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        Ok(stmts)
+    }
+
+    fn condition(&mut self) -> Parsed<Expr> {
+        let t = self.peek().clone();
+        let e = self.expression()?;
+        if e.ty != Type::Bool {
+            return Err(self.error(&t, format!("expected a bool condition, found {}", e.ty)));
+        }
+        Ok(e)
+    }
+
+    /// Body of a loop or branch, in its own scope
+    fn scoped(&mut self) -> Parsed<Box<Stmt>> {
+        self.scopes.push(Vec::new());
+        let stmts = self.statement()?;
+        self.scopes.pop();
+        Ok(Box::new(match <[Stmt; 1]>::try_from(stmts) {
+            Ok([stmt]) => stmt,
+            Err(stmts) => Stmt::Block(stmts),
+        }))
+    }
+
+    /// Body of a loop, where `break` and `continue` are allowed
+    fn loop_body(&mut self) -> Parsed<Box<Stmt>> {
+        self.loops += 1;
+        let body = self.scoped()?;
+        self.loops -= 1;
+        Ok(body)
+    }
+
+    fn statement(&mut self) -> Parsed<Vec<Stmt>> {
+        let t = self.peek().clone();
+        if self.eat(";") {
+            return Ok(Vec::new());
+        }
+        if self.eat("{") {
+            return Ok(vec![Stmt::Block(self.block()?)]);
+        }
+        if let Some(ty) = self.peek_type() {
+            let stmts = self.declaration(ty)?;
+            self.expect(";")?;
+            return Ok(stmts);
+        }
+        let keyword = match &t.tok {
+            Tok::Ident(name) => name.as_str(),
+            _ => "",
+        };
+        let stmt = match keyword {
+            "if" => {
+                self.next();
+                self.expect("(")?;
+                let cond = self.condition()?;
+                self.expect(")")?;
+                let then = self.scoped()?;
+                let otherwise = if self.peek().tok == Tok::Ident("else".into()) {
+                    self.next();
+                    Some(self.scoped()?)
+                } else {
+                    None
+                };
+                Stmt::If(cond, then, otherwise)
+            }
+            "for" => {
+                self.next();
+                self.expect("(")?;
+                self.scopes.push(Vec::new());
+                let init = if self.eat(";") {
+                    Vec::new()
+                } else if let Some(ty) = self.peek_type() {
+                    let stmts = self.declaration(ty)?;
+                    self.expect(";")?;
+                    stmts
+                } else {
+                    let e = self.expression()?;
+                    self.expect(";")?;
+                    vec![Stmt::Expr(e)]
+                };
+                let cond = if self.is(";") {
+                    None
+                } else {
+                    Some(self.condition()?)
+                };
+                self.expect(";")?;
+                let step = if self.is(")") {
+                    None
+                } else {
+                    Some(self.expression()?)
+                };
+                self.expect(")")?;
+                let body = self.loop_body()?;
+                self.scopes.pop();
+                Stmt::Loop {
+                    init,
+                    cond,
+                    step,
+                    body,
+                }
+            }
+            "while" => {
+                self.next();
+                self.expect("(")?;
+                let cond = self.condition()?;
+                self.expect(")")?;
+                Stmt::Loop {
+                    init: Vec::new(),
+                    cond: Some(cond),
+                    step: None,
+                    body: self.loop_body()?,
+                }
+            }
+            "break" | "continue" if self.loops == 0 => {
+                return Err(self.error(&t, format!("`{}` outside a loop", keyword)));
+            }
+            "break" | "continue" | "return" => {
+                self.next();
+                self.expect(";")?;
+                match keyword {
+                    "break" => Stmt::Break,
+                    "continue" => Stmt::Continue,
+                    _ => Stmt::Return,
+                }
+            }
+            _ => {
+                let e = self.expression()?;
+                self.expect(";")?;
+                Stmt::Expr(e)
+            }
+        };
+        Ok(vec![stmt])
+    }
+
+    /// Comma separated expressions, the last one is the value
+    fn expression(&mut self) -> Parsed<Expr> {
+        let mut e = self.assignment()?;
+        while self.eat(",") {
+            let rhs = self.assignment()?;
+            e = Expr {
+                ty: rhs.ty,
+                kind: ExprKind::Comma(Box::new(e), Box::new(rhs)),
+            };
+        }
+        Ok(e)
+    }
+
+    fn lvalue(&self, t: &Token, e: &Expr) -> Parsed<LValue> {
+        let lvalue = match &e.kind {
+            ExprKind::Var(var) => LValue {
+                var: *var,
+                swizzle: None,
+            },
+            ExprKind::Swizzle(base, components) => match base.kind {
+                ExprKind::Var(var) => {
+                    let mut sorted = components.clone();
+                    sorted.sort();
+                    sorted.dedup();
+                    if sorted.len() != components.len() {
+                        return Err(self.error(t, "repeated component in assignment"));
+                    }
+                    LValue {
+                        var,
+                        swizzle: Some(components.clone()),
+                    }
+                }
+                _ => return Err(self.error(t, "cannot assign to this expression")),
+            },
+            _ => return Err(self.error(t, "cannot assign to this expression")),
+        };
+        if lvalue.var < O {
+            return Err(self.error(t, "cannot assign to a uniform"));
+        }
+        Ok(lvalue)
+    }
+
+    fn assignment(&mut self) -> Parsed<Expr> {
+        let t = self.peek().clone();
+        let lhs = self.ternary()?;
+        let op = [
+            ("=", None),
+            ("+=", Some(BinOp::Add)),
+            ("-=", Some(BinOp::Sub)),
+            ("*=", Some(BinOp::Mul)),
+            ("/=", Some(BinOp::Div)),
+        ]
+        .into_iter()
+        .find(|(p, _)| self.is(p));
+        let Some((p, op)) = op else {
+            return Ok(lhs);
+        };
+        let t_op = self.next();
+        let lvalue = self.lvalue(&t, &lhs)?;
+        let rhs = self.assignment()?;
+        let (op, ty) = match op {
+            None => (None, rhs.ty),
+            Some(op) => {
+                let (op, ty) = self.arith(&t_op, op, lhs.ty, rhs.ty)?;
+                (Some(op), ty)
+            }
+        };
+        if !lhs.ty.accepts(ty) {
+            return Err(self.error(&t_op, format!("cannot `{}` {} to {}", p, rhs.ty, lhs.ty)));
+        }
+        Ok(Expr {
+            kind: ExprKind::Assign(lvalue, op, Box::new(rhs)),
+            ty: lhs.ty,
+        })
+    }
+
+    fn ternary(&mut self) -> Parsed<Expr> {
+        let t = self.peek().clone();
+        let cond = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        if cond.ty != Type::Bool {
+            return Err(self.error(&t, format!("expected a bool condition, found {}", cond.ty)));
+        }
+        let a = self.assignment()?;
+        self.expect(":")?;
+        let t_b = self.peek().clone();
+        let b = self.assignment()?;
+        let ty = if a.ty.accepts(b.ty) {
+            a.ty
+        } else if b.ty.accepts(a.ty) {
+            b.ty
+        } else {
+            return Err(self.error(&t_b, format!("branches have types {} and {}", a.ty, b.ty)));
+        };
+        Ok(Expr {
+            kind: ExprKind::Ternary(Box::new(cond), Box::new(a), Box::new(b)),
+            ty,
+        })
+    }
+
+    /// # Returns
+    /// the operation, matrix products get their own, and the result type
+    fn arith(&self, t: &Token, op: BinOp, a: Type, b: Type) -> Parsed<(BinOp, Type)> {
+        use Type::*;
+        let ty = match (a, b) {
+            (Int, Int) => Int,
+            _ if a.scalar() && b.scalar() => Float,
+            (Mat2, Vec(2)) if op == BinOp::Mul => return Ok((BinOp::MatVec, Vec(2))),
+            (Vec(2), Mat2) if op == BinOp::Mul => return Ok((BinOp::VecMat, Vec(2))),
+            (Mat2, Mat2) if op == BinOp::Mul => return Ok((BinOp::MatMat, Mat2)),
+            _ if a == b && a != Bool => a,
+            (Vec(_) | Mat2, _) if b.scalar() => a,
+            (_, Vec(_) | Mat2) if a.scalar() => b,
+            _ => return Err(self.error(t, format!("cannot combine {} and {}", a, b))),
+        };
+        Ok((op, ty))
+    }
+
+    fn binary(&mut self, level: usize) -> Parsed<Expr> {
+        const LEVELS: [&[(&str, BinOp)]; 6] = [
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+            &[
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+                ("<=", BinOp::Le),
+                (">=", BinOp::Ge),
+            ],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul), ("/", BinOp::Div)],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(&(_, op)) = LEVELS[level].iter().find(|(p, _)| self.is(p)) {
+            let t = self.next();
+            let rhs = self.binary(level + 1)?;
+            let (a, b) = (lhs.ty, rhs.ty);
+            let mismatch = || format!("cannot combine {} and {}", a, b);
+            lhs = match op {
+                BinOp::And | BinOp::Or => {
+                    if a != Type::Bool || b != Type::Bool {
+                        return Err(self.error(&t, mismatch()));
+                    }
+                    Expr {
+                        kind: ExprKind::Logic(op, Box::new(lhs), Box::new(rhs)),
+                        ty: Type::Bool,
+                    }
+                }
+                BinOp::Eq | BinOp::Ne => {
+                    if !a.accepts(b) && !b.accepts(a) {
+                        return Err(self.error(&t, mismatch()));
+                    }
+                    Expr {
+                        kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+                        ty: Type::Bool,
+                    }
+                }
+                BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge => {
+                    if !a.scalar() || !b.scalar() {
+                        return Err(self.error(&t, mismatch()));
+                    }
+                    Expr {
+                        kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+                        ty: Type::Bool,
+                    }
+                }
+                _ => {
+                    let (op, ty) = self.arith(&t, op, a, b)?;
+                    Expr {
+                        kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+                        ty,
+                    }
+                }
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn step(&self, t: &Token, e: &Expr, delta: f64, prefix: bool) -> Parsed<Expr> {
+        let lvalue = self.lvalue(t, e)?;
+        if !e.ty.numeric() {
+            return Err(self.error(t, format!("cannot increment {}", e.ty)));
+        }
+        Ok(Expr {
+            kind: ExprKind::Step(lvalue, delta, prefix),
+            ty: e.ty,
+        })
+    }
+
+    fn unary(&mut self) -> Parsed<Expr> {
+        let t = self.peek().clone();
+        if self.eat("-") {
+            let e = self.unary()?;
+            if !e.ty.numeric() {
+                return Err(self.error(&t, format!("cannot negate {}", e.ty)));
+            }
+            let ty = e.ty;
+            return Ok(Expr {
+                kind: ExprKind::Unary(UnOp::Neg, Box::new(e)),
+                ty,
+            });
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("!") {
+            let e = self.unary()?;
+            if e.ty != Type::Bool {
+                return Err(self.error(&t, format!("cannot apply `!` to {}", e.ty)));
+            }
+            return Ok(Expr {
+                kind: ExprKind::Unary(UnOp::Not, Box::new(e)),
+                ty: Type::Bool,
+            });
+        }
+        for (p, delta) in [("++", 1.), ("--", -1.)] {
+            if self.eat(p) {
+                let t = self.peek().clone();
+                let e = self.unary()?;
+                return self.step(&t, &e, delta, true);
+            }
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Parsed<Expr> {
+        let start = self.peek().clone();
+        let mut e = self.primary()?;
+        loop {
+            let t = self.peek().clone();
+            if self.eat(".") {
+                let name = self.ident()?;
+                let Type::Vec(n) = e.ty else {
+                    return Err(self.error(&t, format!("cannot swizzle {}", e.ty)));
+                };
+                let Some(components) = swizzle(&name, n) else {
+                    return Err(self.error(&t, format!("invalid swizzle `{}` of vec{}", name, n)));
+                };
+                let ty = match components.len() {
+                    1 => Type::Float,
+                    n => Type::Vec(n),
+                };
+                e = Expr {
+                    kind: ExprKind::Swizzle(Box::new(e), components),
+                    ty,
+                };
+            } else if self.eat("++") {
+                e = self.step(&start, &e, 1., false)?;
+            } else if self.eat("--") {
+                e = self.step(&start, &e, -1., false)?;
+            } else {
+                return Ok(e);
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Parsed<Vec<(Token, Expr)>> {
+        self.expect("(")?;
+        let mut args = Vec::new();
+        if self.eat(")") {
+            return Ok(args);
+        }
+        loop {
+            let t = self.peek().clone();
+            args.push((t, self.assignment()?));
+            if self.eat(")") {
+                return Ok(args);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn construct(&self, t: &Token, ty: Type, args: Vec<(Token, Expr)>) -> Parsed<Expr> {
+        if args.is_empty() {
+            return Err(self.error(t, format!("{} needs arguments", ty)));
+        }
+        if let Some((t, arg)) = args.iter().find(|(_, arg)| arg.ty == Type::Bool)
+            && (ty != Type::Bool || args.len() > 1)
+        {
+            return Err(self.error(t, format!("cannot convert {} to {}", arg.ty, ty)));
+        }
+        let sizes = args
+            .iter()
+            .map(|(_, arg)| arg.ty.size())
+            .collect::<Vec<_>>();
+        let total = sizes.iter().sum::<usize>();
+        let single = args.len() == 1;
+        if !single && total - sizes.last().unwrap() >= ty.size() {
+            return Err(self.error(t, format!("too many arguments for {}", ty)));
+        }
+        if !single && total < ty.size() {
+            return Err(self.error(t, format!("not enough arguments for {}", ty)));
+        }
+        if single && args[0].1.ty.size() < ty.size() && !args[0].1.ty.scalar() {
+            return Err(self.error(t, format!("not enough arguments for {}", ty)));
+        }
+        Ok(Expr {
+            kind: ExprKind::Construct(ty, args.into_iter().map(|(_, arg)| arg).collect()),
+            ty,
+        })
+    }
+
+    fn call(&self, t: &Token, f: Builtin, name: &str, args: Vec<(Token, Expr)>) -> Parsed<Expr> {
+        if !f.arity().contains(&args.len()) {
+            return Err(self.error(t, format!("wrong number of arguments for `{}`", name)));
+        }
+        if let Some((t, arg)) = args
+            .iter()
+            .find(|(_, arg)| !matches!(arg.ty, Type::Float | Type::Int | Type::Vec(_)))
+        {
+            return Err(self.error(t, format!("`{}` does not take {}", name, arg.ty)));
+        }
+        // the vector type of the arguments, scalars are broadcast
+        let mut vector = None;
+        for (t, arg) in &args {
+            if let Type::Vec(n) = arg.ty {
+                match vector {
+                    Some(m) if m != n => {
+                        return Err(self.error(t, format!("mixed vector sizes in `{}`", name)));
+                    }
+                    _ => vector = Some(n),
+                }
+            }
+        }
+        let gen_type = vector.map_or(Type::Float, Type::Vec);
+        let ty = match f {
+            Builtin::Length | Builtin::Distance | Builtin::Dot => Type::Float,
+            Builtin::Cross => {
+                if args.iter().any(|(_, arg)| arg.ty != Type::Vec(3)) {
+                    return Err(self.error(t, "`cross` takes two vec3"));
+                }
+                Type::Vec(3)
+            }
+            _ => gen_type,
+        };
+        Ok(Expr {
+            kind: ExprKind::Call(f, args.into_iter().map(|(_, arg)| arg).collect()),
+            ty,
+        })
+    }
+
+    fn primary(&mut self) -> Parsed<Expr> {
+        let t = self.next();
+        match t.tok.clone() {
+            Tok::Num(n, float) => Ok(Expr {
+                kind: ExprKind::Literal([n, 0., 0., 0.]),
+                ty: if float { Type::Float } else { Type::Int },
+            }),
+            Tok::Punct("(") => {
+                let e = self.expression()?;
+                self.expect(")")?;
+                Ok(e)
+            }
+            Tok::Ident(name) if name == "true" || name == "false" => Ok(Expr {
+                kind: ExprKind::Literal([(name == "true") as u8 as f64, 0., 0., 0.]),
+                ty: Type::Bool,
+            }),
+            Tok::Ident(name) if self.is("(") => {
+                let args = self.arguments()?;
+                if let Some(ty) = Type::from_name(&name) {
+                    self.construct(&t, ty, args)
+                } else if let Some(f) = Builtin::from_name(&name) {
+                    self.call(&t, f, &name, args)
+                } else {
+                    Err(self.error(&t, format!("unknown function `{}`", name)))
+                }
+            }
+            Tok::Ident(name) => match self.lookup(&name) {
+                Some(var) => Ok(Expr {
+                    kind: ExprKind::Var(var),
+                    ty: self.vars[var],
+                }),
+                None => Err(self.error(&t, format!("unknown variable `{}`", name))),
+            },
+            Tok::Eof => Err(self.error(&t, "unexpected end of shader")),
+            Tok::Punct(p) => Err(self.error(&t, format!("unexpected `{}`", p))),
+        }
+    }
+
+    /// Skips `precision`, `uniform`, `in` and `out` declarations before `void main()`
+    fn main(&mut self) -> Parsed<Vec<Stmt>> {
+        loop {
+            let t = self.next();
+            match &t.tok {
+                Tok::Ident(name)
+                    if ["precision", "uniform", "in", "out"].contains(&name.as_str()) =>
+                {
+                    while !self.eat(";") {
+                        if self.next().tok == Tok::Eof {
+                            return Err(self.error(&t, "expected `;`"));
+                        }
+                    }
+                }
+                Tok::Ident(name) if name == "void" => {
+                    let t = self.peek().clone();
+                    if self.ident()? != "main" {
+                        return Err(self.error(&t, "only `main` can be defined"));
+                    }
+                    self.expect("(")?;
+                    self.expect(")")?;
+                    self.expect("{")?;
+                    let body = self.block()?;
+                    let t = self.peek().clone();
+                    if t.tok != Tok::Eof {
+                        return Err(self.error(&t, "expected the end after `main`"));
+                    }
+                    return Ok(body);
+                }
+                _ => return Err(self.error(&t, "expected `void main()`")),
+            }
+        }
+    }
+}
+
+impl Program {
+    pub fn parse(src: &str) -> Result<Self, ShaderError> {
+        let tokens = tokenize(&strip_block_comments(src)?)?;
+        let has_main = tokens.windows(2).any(|w| {
+            w[0].tok == Tok::Ident("void".into()) && w[1].tok == Tok::Ident("main".into())
+        });
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            scopes: vec![Vec::new()],
+            vars: Vec::new(),
+            loops: 0,
+        };
+        for (name, ty) in [
+            ("FC", Type::Vec(4)),
+            ("r", Type::Vec(2)),
+            ("t", Type::Float),
+            ("o", Type::Vec(4)),
+        ] {
+            parser.declare(name.to_string(), ty);
+        }
+        let body = if has_main {
+            parser.main()?
+        } else {
+            let mut body = Vec::new();
+            while parser.peek().tok != Tok::Eof {
+                body.extend(parser.statement()?);
+            }
+            body
+        };
+        Ok(Self {
+            body,
+            vars: parser.vars,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let p =
+            Program::parse("vec2 p = FC.xy / r, q;\nfloat a = 1; q.yx += p * mat2(1);").unwrap();
+        assert_eq!(p.vars[4..], [Type::Vec(2), Type::Vec(2), Type::Float]);
+        assert_eq!(p.body.len(), 4);
+        let main = "#version 300 es\nprecision highp float;\nuniform float t;\nout vec4 o;\n\
+            void main() { /* a\ncomment */ o = vec4(1); }";
+        assert!(Program::parse(main).is_ok());
+        let err = |src: &str| Program::parse(src).unwrap_err().to_string();
+        assert_eq!(
+            err("float a = 1.;\na = vec2(1);"),
+            "2:3: cannot `=` vec2 to float"
+        );
+        assert_eq!(
+            err("o.xx = vec2(0);"),
+            "1:1: repeated component in assignment"
+        );
+        assert_eq!(err("t = 1.;"), "1:1: cannot assign to a uniform");
+        assert_eq!(err("o = foo(1.);"), "1:5: unknown function `foo`");
+        assert_eq!(err("o.xg = r;"), "1:2: invalid swizzle `xg` of vec4");
+        assert_eq!(
+            err("vec3 v = vec3(FC, 1.);"),
+            "1:10: too many arguments for vec3"
+        );
+        assert_eq!(
+            err("if (1.) o = vec4(1);"),
+            "1:5: expected a bool condition, found float"
+        );
+        assert_eq!(err("{ float a; } a = 1.;"), "1:14: unknown variable `a`");
+        assert_eq!(
+            err("for (;;) {}\nif (t > 1.) break;"),
+            "2:13: `break` outside a loop"
+        );
+        assert_eq!(err("continue;"), "1:1: `continue` outside a loop");
+    }
+}
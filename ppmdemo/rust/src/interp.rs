@@ -0,0 +1,393 @@
+//! # Shader Interpreter
+//! Walks the syntax tree of a `glsl::Program` once per sample,
+//! variables live in slots of four components.
+
+use crate::frame::{Frames, Options};
+use crate::glsl::{
+    BinOp, Builtin, Expr, ExprKind, FC, LValue, O, Program, R, Stmt, T, Type, UnOp, Value,
+};
+
+/// Component `i` of a value, scalars are broadcast
+#[inline]
+fn component(v: &Value, size: usize, i: usize) -> f64 {
+    if size == 1 { v[0] } else { v[i] }
+}
+
+fn bool_value(b: bool) -> Value {
+    [b as u8 as f64, 0., 0., 0.]
+}
+
+/// # Params
+/// `sizes` are the component counts of `args`
+pub fn call(f: Builtin, args: &[Value], sizes: &[usize]) -> Value {
+    let n = sizes.iter().copied().max().unwrap_or(1);
+    let dot = |a: &Value, b: &Value| (0..n).map(|i| a[i] * b[i]).sum::<f64>();
+    let mut out = [0.; 4];
+    match f {
+        Builtin::Length => out[0] = dot(&args[0], &args[0]).sqrt(),
+        Builtin::Distance => {
+            let d = std::array::from_fn(|i| args[0][i] - args[1][i]);
+            out[0] = dot(&d, &d).sqrt();
+        }
+        Builtin::Dot => out[0] = dot(&args[0], &args[1]),
+        Builtin::Cross => {
+            let [a, b] = [args[0], args[1]];
+            out = [
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+                0.,
+            ];
+        }
+        Builtin::Normalize => {
+            let l = dot(&args[0], &args[0]).sqrt();
+            for i in 0..n {
+                out[i] = args[0][i] / l;
+            }
+        }
+        Builtin::Reflect => {
+            let [v, normal] = [args[0], args[1]];
+            let d = dot(&normal, &v);
+            for i in 0..n {
+                out[i] = v[i] - 2. * d * normal[i];
+            }
+        }
+        _ => {
+            for (i, out) in out.iter_mut().enumerate().take(n) {
+                let a = |k: usize| component(&args[k], sizes[k], i);
+                *out = match f {
+                    Builtin::Sin => a(0).sin(),
+                    Builtin::Cos => a(0).cos(),
+                    Builtin::Tan => a(0).tan(),
+                    Builtin::Asin => a(0).asin(),
+                    Builtin::Acos => a(0).acos(),
+                    Builtin::Atan if args.len() == 2 => a(0).atan2(a(1)),
+                    Builtin::Atan => a(0).atan(),
+                    Builtin::Sinh => a(0).sinh(),
+                    Builtin::Cosh => a(0).cosh(),
+                    Builtin::Tanh => a(0).tanh(),
+                    Builtin::Exp => a(0).exp(),
+                    Builtin::Exp2 => a(0).exp2(),
+                    Builtin::Log => a(0).ln(),
+                    Builtin::Log2 => a(0).log2(),
+                    Builtin::Sqrt => a(0).sqrt(),
+                    Builtin::InverseSqrt => 1. / a(0).sqrt(),
+                    Builtin::Abs => a(0).abs(),
+                    Builtin::Sign if a(0) == 0. => 0.,
+                    Builtin::Sign => a(0).signum(),
+                    Builtin::Floor => a(0).floor(),
+                    Builtin::Ceil => a(0).ceil(),
+                    Builtin::Fract => a(0) - a(0).floor(),
+                    Builtin::Round => a(0).round(),
+                    Builtin::Radians => a(0).to_radians(),
+                    Builtin::Degrees => a(0).to_degrees(),
+                    Builtin::Pow => a(0).powf(a(1)),
+                    Builtin::Mod => a(0) - a(1) * (a(0) / a(1)).floor(),
+                    Builtin::Min => a(0).min(a(1)),
+                    Builtin::Max => a(0).max(a(1)),
+                    Builtin::Clamp => a(0).max(a(1)).min(a(2)),
+                    Builtin::Mix => a(0) * (1. - a(2)) + a(1) * a(2),
+                    Builtin::Step if a(1) < a(0) => 0.,
+                    Builtin::Step => 1.,
+                    Builtin::Smoothstep => {
+                        let t = ((a(2) - a(0)) / (a(1) - a(0))).clamp(0., 1.);
+                        t * t * (3. - 2. * t)
+                    }
+                    _ => unreachable!(),
+                };
+            }
+        }
+    }
+    out
+}
+
+/// Arithmetic, comparisons and matrix products of typed operands
+pub fn binary(op: BinOp, a: Value, ta: Type, b: Value, tb: Type, ty: Type) -> Value {
+    let (sa, sb) = (ta.size(), tb.size());
+    let mut out = [0.; 4];
+    match op {
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+            for (i, out) in out.iter_mut().enumerate().take(ty.size()) {
+                let (x, y) = (component(&a, sa, i), component(&b, sb, i));
+                *out = match op {
+                    BinOp::Add => x + y,
+                    BinOp::Sub => x - y,
+                    BinOp::Mul => x * y,
+                    _ if ty == Type::Int => (x / y).trunc(),
+                    _ => x / y,
+                };
+            }
+        }
+        BinOp::MatVec => {
+            out[0] = a[0] * b[0] + a[2] * b[1];
+            out[1] = a[1] * b[0] + a[3] * b[1];
+        }
+        BinOp::VecMat => {
+            out[0] = a[0] * b[0] + a[1] * b[1];
+            out[1] = a[0] * b[2] + a[1] * b[3];
+        }
+        BinOp::MatMat => {
+            out = [
+                a[0] * b[0] + a[2] * b[1],
+                a[1] * b[0] + a[3] * b[1],
+                a[0] * b[2] + a[2] * b[3],
+                a[1] * b[2] + a[3] * b[3],
+            ];
+        }
+        BinOp::Lt => out = bool_value(a[0] < b[0]),
+        BinOp::Gt => out = bool_value(a[0] > b[0]),
+        BinOp::Le => out = bool_value(a[0] <= b[0]),
+        BinOp::Ge => out = bool_value(a[0] >= b[0]),
+        BinOp::Eq | BinOp::Ne => {
+            let eq = (0..sa.max(sb)).all(|i| a[i] == b[i]);
+            out = bool_value(eq == (op == BinOp::Eq));
+        }
+        BinOp::And => out = bool_value(a[0] != 0. && b[0] != 0.),
+        BinOp::Or => out = bool_value(a[0] != 0. || b[0] != 0.),
+    }
+    out
+}
+
+/// Conversions and constructors
+pub fn construct(ty: Type, args: &[Value], sizes: &[usize]) -> Value {
+    let mut out = [0.; 4];
+    match ty {
+        Type::Bool => out = bool_value(args[0][0] != 0.),
+        Type::Int => out[0] = args[0][0].trunc(),
+        Type::Float => out[0] = args[0][0],
+        Type::Mat2 if sizes == [1] => out = [args[0][0], 0., 0., args[0][0]],
+        _ if sizes == [1] => out[..ty.size()].fill(args[0][0]),
+        _ => {
+            let components = args
+                .iter()
+                .zip(sizes)
+                .flat_map(|(v, &n)| v[..n].iter().copied());
+            for (out, c) in out.iter_mut().take(ty.size()).zip(components) {
+                *out = c;
+            }
+        }
+    }
+    out
+}
+
+/// How a loop body or statement list ended
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return,
+}
+
+struct Interpreter {
+    env: Vec<Value>,
+}
+
+impl Interpreter {
+    fn read(&self, lvalue: &LValue) -> Value {
+        let v = self.env[lvalue.var];
+        match &lvalue.swizzle {
+            None => v,
+            Some(components) => {
+                let mut out = [0.; 4];
+                for (out, &c) in out.iter_mut().zip(components) {
+                    *out = v[c];
+                }
+                out
+            }
+        }
+    }
+
+    fn write(&mut self, lvalue: &LValue, v: Value) {
+        match &lvalue.swizzle {
+            None => self.env[lvalue.var] = v,
+            Some(components) => {
+                for (i, &c) in components.iter().enumerate() {
+                    self.env[lvalue.var][c] = v[i];
+                }
+            }
+        }
+    }
+
+    fn eval(&mut self, e: &Expr) -> Value {
+        match &e.kind {
+            ExprKind::Literal(v) => *v,
+            ExprKind::Var(var) => self.env[*var],
+            ExprKind::Swizzle(base, components) => {
+                let v = self.eval(base);
+                let mut out = [0.; 4];
+                for (out, &c) in out.iter_mut().zip(components) {
+                    *out = v[c];
+                }
+                out
+            }
+            ExprKind::Unary(UnOp::Neg, a) => self.eval(a).map(|c| -c),
+            ExprKind::Unary(UnOp::Not, a) => bool_value(self.eval(a)[0] == 0.),
+            ExprKind::Binary(op, a, b) => {
+                let (va, vb) = (self.eval(a), self.eval(b));
+                binary(*op, va, a.ty, vb, b.ty, e.ty)
+            }
+            ExprKind::Logic(op, a, b) => {
+                let a = self.eval(a)[0] != 0.;
+                let short = if *op == BinOp::And { !a } else { a };
+                bool_value(if short { a } else { self.eval(b)[0] != 0. })
+            }
+            ExprKind::Comma(a, b) => {
+                self.eval(a);
+                self.eval(b)
+            }
+            ExprKind::Ternary(cond, a, b) => {
+                if self.eval(cond)[0] != 0. {
+                    self.eval(a)
+                } else {
+                    self.eval(b)
+                }
+            }
+            ExprKind::Call(f, args) => {
+                let values = args.iter().map(|a| self.eval(a)).collect::<Vec<_>>();
+                let sizes = args.iter().map(|a| a.ty.size()).collect::<Vec<_>>();
+                call(*f, &values, &sizes)
+            }
+            ExprKind::Construct(ty, args) => {
+                let values = args.iter().map(|a| self.eval(a)).collect::<Vec<_>>();
+                let sizes = args.iter().map(|a| a.ty.size()).collect::<Vec<_>>();
+                construct(*ty, &values, &sizes)
+            }
+            ExprKind::Assign(lvalue, op, rhs) => {
+                let v = self.eval(rhs);
+                let v = match op {
+                    None => v,
+                    Some(op) => binary(*op, self.read(lvalue), e.ty, v, rhs.ty, e.ty),
+                };
+                self.write(lvalue, v);
+                v
+            }
+            ExprKind::Step(lvalue, delta, prefix) => {
+                let old = self.read(lvalue);
+                let new = old.map(|c| c + delta);
+                self.write(lvalue, new);
+                if *prefix { new } else { old }
+            }
+        }
+    }
+
+    fn exec_all(&mut self, stmts: &[Stmt]) -> Flow {
+        for stmt in stmts {
+            match self.exec(stmt) {
+                Flow::Next => {}
+                flow => return flow,
+            }
+        }
+        Flow::Next
+    }
+
+    fn exec(&mut self, stmt: &Stmt) -> Flow {
+        match stmt {
+            Stmt::Expr(e) => {
+                self.eval(e);
+                Flow::Next
+            }
+            Stmt::Block(stmts) => self.exec_all(stmts),
+            Stmt::If(cond, then, otherwise) => {
+                if self.eval(cond)[0] != 0. {
+                    self.exec(then)
+                } else if let Some(otherwise) = otherwise {
+                    self.exec(otherwise)
+                } else {
+                    Flow::Next
+                }
+            }
+            Stmt::Loop {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                if let Flow::Return = self.exec_all(init) {
+                    return Flow::Return;
+                }
+                while cond.as_ref().is_none_or(|c| self.eval(c)[0] != 0.) {
+                    match self.exec(body) {
+                        Flow::Break => break,
+                        Flow::Return => return Flow::Return,
+                        Flow::Next | Flow::Continue => {}
+                    }
+                    if let Some(step) = step {
+                        self.eval(step);
+                    }
+                }
+                Flow::Next
+            }
+            Stmt::Break => Flow::Break,
+            Stmt::Continue => Flow::Continue,
+            Stmt::Return => Flow::Return,
+        }
+    }
+}
+
+/// # Params
+/// `fc` is the sample position with `y` pointing down as in the frame renderer,
+/// `r` the resolution and `t` the time in seconds
+///
+/// # Returns
+/// `o.rgb` after running the shader
+pub fn run(program: &Program, [x, y]: [f64; 2], r: [f64; 2], t: f64) -> [f64; 3] {
+    let mut env = vec![[0.; 4]; program.vars.len()];
+    env[FC] = [x, r[1] - y, 0., 1.];
+    env[R] = [r[0], r[1], 0., 0.];
+    env[T] = [t, 0., 0., 0.];
+    let mut interpreter = Interpreter { env };
+    interpreter.exec_all(&program.body);
+    let [r, g, b, _] = interpreter.env[O];
+    [r, g, b]
+}
+
+pub fn generate(program: &Program, name: &str, options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let r = [w as f64, h as f64];
+    let frames = Frames::new(name, w, h, fps).with_options(options);
+    frames.render(|fc, t| run(program, fc, r, t));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shader;
+    use crate::vector::Vec2;
+
+    #[test]
+    fn run_test() {
+        let shade = |src: &str| run(&Program::parse(src).unwrap(), [3., 1.], [4., 2.], 0.5);
+        assert_eq!(shade("o.rg = FC.xy / r; o.b = t;"), [0.75, 0.5, 0.5]);
+        assert_eq!(
+            shade("o.xyz = vec3(vec2(1, 2) * mat2(1, 2, 3, 4), 7 / 2);"),
+            [5., 11., 3.]
+        );
+        let loops = "float s = 0.;\n\
+            for (int i = 0; i < 10; i++) { if (i == 2) continue; if (i > 4) break; s += float(i); }\n\
+            int k = 0; while (true) { if (++k >= 3) break; }\n\
+            o.r = s; o.g = float(k); o.b = k == 3 && s > 7. ? mix(1., 3., .5) : -1.;";
+        assert_eq!(shade(loops), [8., 3., 2.]);
+        assert_eq!(
+            shade("vec2 i; for (; i.y++ < 8.;) o.x += i.y; o.y = i.y;"),
+            [36., 9., 0.]
+        );
+        assert_eq!(shade("o = vec4(1); return; o = vec4(2);"), [1.; 3]);
+        let v = shade("o.rgb = vec3(length(vec2(3, 4)), smoothstep(0., 2., 1.), mod(-1., 3.));");
+        assert_eq!(v, [5., 0.5, 2.]);
+        let root = env!("CARGO_MANIFEST_DIR");
+        let src = std::fs::read_to_string(format!("{}/shaders/swirl.glsl", root)).unwrap();
+        let swirl = Program::parse(&src).unwrap();
+        let r = [32., 18.];
+        for (x, y) in [(3., 4.), (16., 9.), (30., 1.)] {
+            let native = shader::swirl(Vec2::new(x, r[1] - y), Vec2::new(r[0], r[1]), 0.3);
+            let interpreted = run(&swirl, [x, y], r, 0.3);
+            for (a, b) in [native.x, native.y, native.z].iter().zip(interpreted) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+}
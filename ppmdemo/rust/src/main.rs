@@ -1,7 +1,206 @@
-pub mod board;
+pub mod clouds;
+pub mod color;
+pub mod frame;
+pub mod glsl;
+pub mod interp;
+pub mod multipass;
+pub mod pattern;
+pub mod raymarch;
+pub mod reaction;
+pub mod sdf;
 pub mod shader;
+pub mod trails;
+pub mod vector;
+pub mod vm;
+
+use crate::color::{Dither, ToneMap};
+use crate::frame::{Format, Options, Samples};
+use crate::glsl::Program;
+use crate::pattern::{Pattern, Shape};
+use crate::vm::Bytecode;
+use common::filter::Filter;
+use std::fs;
+use std::path::Path;
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "usage: {} [shader|board|clouds|raymarch|reaction|trails|glsl FILE|bench FILE] [--aa N|rgss] [--blur N [--shutter DEGREES]] \\\n  [--tone clamp|reinhard|aces] [--srgb] [--dither bayer|blue] [--hdr pfm|rgbe] \\\n  [--filter NAME[:ARG,...]]... [--resume]\n       {} pattern checker|stripes|rings|grid|hex|dots|linear|radial [--colors RRGGBB RRGGBB] \\\n  [--scale PIXELS] [--rotate DEGREES] [--scroll VX VY] [--center X Y] [--fill SHARE] [options above]",
+        program, program
+    );
+    std::process::exit(2);
+}
+
+/// `name` or `name:a,b,...`, see `Filter::parse`
+fn parse_filter(spec: &str) -> Result<Filter, String> {
+    let (name, args) = spec.split_once(':').unwrap_or((spec, ""));
+    let args = args
+        .split(',')
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            a.parse()
+                .map_err(|_| format!("expected a number, found `{}`", a))
+        })
+        .collect::<Result<Vec<f64>, _>>()?;
+    Filter::parse(name, &args)
+}
+
+/// `RRGGBB` in hex
+fn hex_color(s: &str) -> Option<[f64; 3]> {
+    if s.len() != 6 {
+        return None;
+    }
+    let n = u32::from_str_radix(s, 16).ok()?;
+    Some([16, 8, 0].map(|shift| (n >> shift & 0xFF) as f64 / 255.))
+}
 
 fn main() {
-    // board::generate();
-    shader::generate();
+    let args = std::env::args().collect::<Vec<_>>();
+    let mut demo = "shader";
+    let mut path = None;
+    let mut options = Options::default();
+    // pattern settings apply on top of the shape's defaults, or the board's
+    let mut shape = Shape::Checker;
+    let mut colors = None;
+    let mut scale = None;
+    let mut rotation = None;
+    let mut velocity = None;
+    let mut center = None;
+    let mut fill = None;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "shader" | "board" | "clouds" | "raymarch" | "reaction" | "trails" => demo = arg,
+            "pattern" => {
+                demo = arg;
+                let name = iter.next().and_then(|s| Shape::from_name(s));
+                shape = name.unwrap_or_else(|| usage(&args[0]));
+            }
+            "--colors" => {
+                let mut color = || iter.next().and_then(|s| hex_color(s));
+                match (color(), color()) {
+                    (Some(a), Some(b)) => colors = Some([a, b]),
+                    _ => usage(&args[0]),
+                }
+            }
+            "--scale" => match iter.next().and_then(|s| s.parse().ok()) {
+                Some(s) if s > 0. => scale = Some(s),
+                _ => usage(&args[0]),
+            },
+            "--rotate" => match iter.next().and_then(|s| s.parse().ok()) {
+                Some(degrees) => rotation = Some(degrees),
+                None => usage(&args[0]),
+            },
+            "--scroll" | "--center" => {
+                let mut number = || iter.next().and_then(|s| s.parse().ok());
+                let v = match (number(), number()) {
+                    (Some(x), Some(y)) => [x, y],
+                    _ => usage(&args[0]),
+                };
+                if arg == "--scroll" {
+                    velocity = Some(v);
+                } else {
+                    center = Some(v);
+                }
+            }
+            "--fill" => match iter.next().and_then(|s| s.parse().ok()) {
+                Some(f) if (0. ..=1.).contains(&f) => fill = Some(f),
+                _ => usage(&args[0]),
+            },
+            "glsl" | "bench" => {
+                demo = arg;
+                path = Some(iter.next().unwrap_or_else(|| usage(&args[0])));
+            }
+            "--aa" => {
+                options.samples = match iter.next().map(String::as_str) {
+                    Some("rgss") => Samples::RotatedGrid,
+                    Some(n) => match n.parse() {
+                        Ok(1) => Samples::One,
+                        Ok(n) if n > 1 => Samples::Grid(n),
+                        _ => usage(&args[0]),
+                    },
+                    None => usage(&args[0]),
+                };
+            }
+            "--blur" => match iter.next().and_then(|s| s.parse().ok()) {
+                Some(n) if n > 0 => options.shutter.samples = n,
+                _ => usage(&args[0]),
+            },
+            "--shutter" => match iter.next().and_then(|s| s.parse().ok()) {
+                Some(angle) if 0. < angle && angle <= 360. => options.shutter.angle = angle,
+                _ => usage(&args[0]),
+            },
+            "--tone" => {
+                options.encoding.tone_map = match iter.next().map(String::as_str) {
+                    Some("clamp") => ToneMap::Clamp,
+                    Some("reinhard") => ToneMap::Reinhard,
+                    Some("aces") => ToneMap::Aces,
+                    _ => usage(&args[0]),
+                };
+            }
+            "--srgb" => options.encoding.srgb = true,
+            "--dither" => {
+                options.encoding.dither = match iter.next().map(String::as_str) {
+                    Some("bayer") => Dither::Ordered,
+                    Some("blue") => Dither::BlueNoise,
+                    _ => usage(&args[0]),
+                };
+            }
+            "--hdr" => {
+                options.format = match iter.next().map(String::as_str) {
+                    Some("pfm") => Format::Pfm,
+                    Some("rgbe") => Format::Rgbe,
+                    _ => usage(&args[0]),
+                };
+            }
+            "--filter" => {
+                let spec = iter.next().unwrap_or_else(|| usage(&args[0]));
+                options.filters.push(parse_filter(spec).unwrap_or_else(|e| {
+                    eprintln!("--filter {}: {}", spec, e);
+                    std::process::exit(2);
+                }));
+            }
+            "--resume" => options.resume = true,
+            _ => usage(&args[0]),
+        }
+    }
+    let mut pattern = match demo {
+        "board" => Pattern::board(),
+        _ => Pattern::new(shape),
+    };
+    pattern.colors = colors.unwrap_or(pattern.colors);
+    pattern.scale = scale.unwrap_or(pattern.scale);
+    pattern.rotation = rotation.unwrap_or(pattern.rotation);
+    pattern.velocity = velocity.unwrap_or(pattern.velocity);
+    pattern.center = center.unwrap_or(pattern.center);
+    pattern.fill = fill.unwrap_or(pattern.fill);
+    match demo {
+        "board" => pattern::generate(pattern, "board", options),
+        "pattern" => {
+            let name = pattern.shape.name();
+            pattern::generate(pattern, name, options);
+        }
+        "clouds" => clouds::generate(options),
+        "raymarch" => raymarch::generate(options),
+        "reaction" => reaction::generate(options),
+        "trails" => trails::generate(options),
+        "glsl" | "bench" => {
+            let path = Path::new(path.unwrap());
+            let src = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("{}: {}", path.display(), e);
+                std::process::exit(1);
+            });
+            let program = Program::parse(&src).unwrap_or_else(|e| {
+                eprintln!("{}:{}", path.display(), e);
+                std::process::exit(1);
+            });
+            if demo == "bench" {
+                vm::bench(&program);
+                return;
+            }
+            let name = path.file_stem().unwrap().to_string_lossy();
+            let bytecode = Bytecode::compile(&program);
+            vm::generate(&bytecode, &name, options);
+        }
+        _ => shader::generate(options),
+    }
 }
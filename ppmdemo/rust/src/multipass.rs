@@ -0,0 +1,200 @@
+//! # Multi-Pass Rendering
+//! Every frame runs a list of passes in order, each shading a named buffer of the frame size.
+//! A pass samples the buffers it lists as inputs: passes before it give their output of this
+//! frame, itself and later passes their output of the previous frame, black before the first.
+//! The last pass is the image. Reading previous frames is feedback, which makes every frame
+//! depend on the one before, so those frames are rendered in order on one thread.
+
+use crate::color::Rgb;
+use crate::frame::Frames;
+
+type Shader<'a> = Box<dyn Fn(&Inputs, [f64; 2], f64) -> [f64; 3] + Sync + 'a>;
+
+pub struct Pass<'a> {
+    pub name: String,
+    /// names of the buffers `shader` samples
+    pub inputs: Vec<String>,
+    /// times the pass runs per frame, each step reads the output of the step before
+    pub steps: usize,
+    shader: Shader<'a>,
+}
+
+impl<'a> Pass<'a> {
+    /// # Params
+    /// `shader` maps the inputs, a fragment coordinate and the time in seconds to a color
+    pub fn new(
+        name: &str,
+        shader: impl Fn(&Inputs, [f64; 2], f64) -> [f64; 3] + Sync + 'a,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            inputs: Vec::new(),
+            steps: 1,
+            shader: Box::new(shader),
+        }
+    }
+
+    pub fn with_inputs(mut self, names: &[&str]) -> Self {
+        self.inputs = names.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        assert!(steps != 0);
+        self.steps = steps;
+        self
+    }
+}
+
+/// The buffers a pass samples, texel `(x, y)` holds the color at the fragment coordinate `[x, y]`
+pub struct Inputs<'a> {
+    w: usize,
+    h: usize,
+    buffers: Vec<(&'a str, &'a [Rgb])>,
+}
+
+impl Inputs<'_> {
+    fn buffer(&self, name: &str) -> &[Rgb] {
+        match self.buffers.iter().find(|(n, _)| *n == name) {
+            Some((_, buffer)) => buffer,
+            None => panic!("buffer `{}` is not an input of this pass", name),
+        }
+    }
+
+    /// The texel nearest to `[x, y]` inside the buffer, like `texelFetch`
+    pub fn fetch(&self, name: &str, [x, y]: [i64; 2]) -> [f64; 3] {
+        let x = x.clamp(0, self.w as i64 - 1) as usize;
+        let y = y.clamp(0, self.h as i64 - 1) as usize;
+        self.buffer(name)[y * self.w + x].map(|c| c as f64)
+    }
+
+    /// Bilinear between the texels around a fragment coordinate, the edges repeat
+    pub fn sample(&self, name: &str, [x, y]: [f64; 2]) -> [f64; 3] {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let mut c = [0.; 3];
+        for (dx, dy, weight) in [
+            (0, 0, (1. - fx) * (1. - fy)),
+            (1, 0, fx * (1. - fy)),
+            (0, 1, (1. - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            if weight == 0. {
+                continue;
+            }
+            let texel = self.fetch(name, [x0 + dx, y0 + dy]);
+            for i in 0..3 {
+                c[i] += weight * texel[i];
+            }
+        }
+        c
+    }
+}
+
+/// # Returns
+/// whether a pass reads itself or a later pass
+pub fn feedback(passes: &[Pass]) -> bool {
+    passes.iter().enumerate().any(|(k, pass)| {
+        pass.inputs
+            .iter()
+            .any(|name| passes[k..].iter().any(|p| p.name == *name))
+    })
+}
+
+fn validate(passes: &[Pass]) {
+    assert!(!passes.is_empty(), "no passes");
+    for (k, pass) in passes.iter().enumerate() {
+        assert!(
+            passes[..k].iter().all(|p| p.name != pass.name),
+            "duplicate pass `{}`",
+            pass.name
+        );
+        for name in &pass.inputs {
+            assert!(
+                passes.iter().any(|p| p.name == *name),
+                "pass `{}` reads unknown buffer `{}`",
+                pass.name,
+                name
+            );
+        }
+    }
+}
+
+/// Runs every pass once for the frame at time `t`, `buffers` hold the previous frame
+/// and are replaced by this one
+fn run(frames: &Frames, passes: &[Pass], buffers: &mut [Vec<Rgb>], t: f64) {
+    for (k, pass) in passes.iter().enumerate() {
+        for _ in 0..pass.steps {
+            let inputs = Inputs {
+                w: frames.w,
+                h: frames.h,
+                buffers: pass
+                    .inputs
+                    .iter()
+                    .map(|name| {
+                        let i = passes.iter().position(|p| p.name == *name).unwrap();
+                        (name.as_str(), buffers[i].as_slice())
+                    })
+                    .collect(),
+            };
+            let out = frames.shade(&|fc, t| (pass.shader)(&inputs, fc, t), t);
+            buffers[k] = out;
+        }
+    }
+}
+
+/// Writes the image of every frame like `Frames::render`,
+/// one frame after another when the passes use feedback
+pub fn render(frames: &Frames, passes: &[Pass]) {
+    validate(passes);
+    let black = || vec![vec![[0.; 3]; frames.w * frames.h]; passes.len()];
+    if feedback(passes) {
+        let mut buffers = black();
+        frames.render_sequential(|t| {
+            run(frames, passes, &mut buffers, t);
+            buffers.last().unwrap().clone()
+        });
+    } else {
+        frames.render_frames(|t| {
+            let mut buffers = black();
+            run(frames, passes, &mut buffers, t);
+            buffers.pop().unwrap()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multipass_test() {
+        let frames = Frames::new("test", 4, 2, 1);
+        // a counter that adds one every step of every frame, and an image reading it
+        let passes = [
+            Pass::new("count", |input, [x, y], _| {
+                let [n, _, _] = input.fetch("count", [x as i64, y as i64]);
+                [n + 1., x, y]
+            })
+            .with_inputs(&["count"])
+            .with_steps(2),
+            Pass::new("image", |input, fc, _| input.sample("count", fc)).with_inputs(&["count"]),
+        ];
+        assert!(feedback(&passes));
+        assert!(!feedback(&passes[1..]));
+        validate(&passes);
+        let mut buffers = vec![vec![[0.; 3]; 8]; 2];
+        run(&frames, &passes, &mut buffers, 0.);
+        run(&frames, &passes, &mut buffers, 1.);
+        assert_eq!(buffers[1][5], [4., 1., 1.]);
+        let inputs = Inputs {
+            w: 4,
+            h: 2,
+            buffers: vec![("count", &buffers[0])],
+        };
+        // bilinear between texels and clamped beyond the edges
+        assert_eq!(inputs.sample("count", [1.5, 0.]), [4., 1.5, 0.]);
+        assert_eq!(inputs.sample("count", [-3., 7.]), [4., 0., 1.]);
+    }
+}
@@ -0,0 +1,224 @@
+//! # Patterns
+//! Test patterns and textures in cells of `scale` pixels, rotated about `center` and
+//! scrolling at `velocity` pixels per second. `fill` is the share of a cell covered by
+//! stripes, grid lines, dots and rings.
+
+use crate::frame::{Frames, Options};
+use crate::vector::{Vec2, Vec3, fract};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Checker,
+    /// vertical before rotation
+    Stripes,
+    /// concentric about `center`
+    Rings,
+    Grid,
+    /// three-colored hexagons of `scale` pixels from the center to a corner
+    Hex,
+    Dots,
+    /// a linear ramp repeating every cell
+    Linear,
+    /// a ramp repeating every cell outwards from `center`
+    Radial,
+}
+
+impl Shape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "checker" => Shape::Checker,
+            "stripes" => Shape::Stripes,
+            "rings" => Shape::Rings,
+            "grid" => Shape::Grid,
+            "hex" => Shape::Hex,
+            "dots" => Shape::Dots,
+            "linear" => Shape::Linear,
+            "radial" => Shape::Radial,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Checker => "checker",
+            Shape::Stripes => "stripes",
+            Shape::Rings => "rings",
+            Shape::Grid => "grid",
+            Shape::Hex => "hex",
+            Shape::Dots => "dots",
+            Shape::Linear => "linear",
+            Shape::Radial => "radial",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pattern {
+    pub shape: Shape,
+    /// background and foreground, or the ends of a ramp
+    pub colors: [[f64; 3]; 2],
+    /// cell size in pixels
+    pub scale: f64,
+    /// degrees, counterclockwise on screen
+    pub rotation: f64,
+    /// pixels per second
+    pub velocity: [f64; 2],
+    pub center: [f64; 2],
+    pub fill: f64,
+}
+
+impl Pattern {
+    /// Black and white cells of 60 pixels, standing still
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            colors: [[0.; 3], [1.; 3]],
+            scale: 60.,
+            rotation: 0.,
+            velocity: [0.; 2],
+            center: [0.; 2],
+            fill: 0.5,
+        }
+    }
+
+    /// The red and black checkerboard scrolling up-left by a cell per second
+    pub fn board() -> Self {
+        Self::new(Shape::Checker)
+            .with_colors([[0.; 3], [1., 0., 0.]])
+            .with_velocity([-60., -60.])
+    }
+
+    pub fn with_colors(mut self, colors: [[f64; 3]; 2]) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: [f64; 2]) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_center(mut self, center: [f64; 2]) -> Self {
+        self.center = center;
+        self
+    }
+
+    pub fn with_fill(mut self, fill: f64) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Blend of the two colors, `0` is the background
+    fn mix(&self, k: f64) -> [f64; 3] {
+        let [a, b] = self.colors.map(Vec3::from);
+        a.mix(b, k).to_array()
+    }
+
+    /// # Params
+    /// `fc` the fragment coordinate from the top left, `t` the time in seconds
+    pub fn color(&self, [x, y]: [f64; 2], t: f64) -> [f64; 3] {
+        let [vx, vy] = self.velocity;
+        let [cx, cy] = self.center;
+        let mut p = Vec2::new(x - cx - vx * t, y - cy - vy * t);
+        if self.rotation != 0. {
+            // y points down, so turning the pattern counterclockwise is a clockwise turn of p
+            let (s, c) = self.rotation.to_radians().sin_cos();
+            p = Vec2::new(c * p.x - s * p.y, s * p.x + c * p.y);
+        }
+        let u = p / self.scale;
+        let on = |b: bool| b as u8 as f64;
+        let k = match self.shape {
+            Shape::Checker => on((u.x.floor() + u.y.floor()).rem_euclid(2.) == 1.),
+            Shape::Stripes => on(fract(u.x) < self.fill),
+            Shape::Rings => on(fract(u.length()) < self.fill),
+            Shape::Grid => {
+                let half = self.fill / 2.;
+                let line = |c: f64| (fract(c + half)) < self.fill;
+                on(line(u.x) || line(u.y))
+            }
+            Shape::Hex => hex(u) as f64 / 2.,
+            Shape::Dots => on((u.fract() - 0.5).length() < self.fill / 2.),
+            Shape::Linear => fract(u.x),
+            Shape::Radial => fract(u.length()),
+        };
+        self.mix(k)
+    }
+}
+
+/// # Returns
+/// one of 3 colors so that neighboring hexagons of unit circumradius differ
+fn hex(p: Vec2) -> usize {
+    // fractional axial coordinates of pointy-top hexagons, rounded in cube coordinates
+    let q = 3f64.sqrt() / 3. * p.x - p.y / 3.;
+    let r = 2. / 3. * p.y;
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq - rr).rem_euclid(3.) as usize
+}
+
+pub fn generate(pattern: Pattern, name: &str, options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let frames = Frames::new(name, w, h, fps).with_options(options);
+    frames.render(|fc, t| pattern.color(fc, t));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_test() {
+        let board = Pattern::board();
+        assert_eq!(board.color([59., 0.], 0.), [0.; 3]);
+        assert_eq!(board.color([60., 0.], 0.), [1., 0., 0.]);
+        // a second later every cell moved one cell up-left
+        assert_eq!(board.color([0., 0.], 1.), board.color([60., 60.], 0.));
+        let white = [1.; 3];
+        let stripes = Pattern::new(Shape::Stripes).with_scale(10.).with_fill(0.3);
+        assert_eq!(stripes.color([2., 0.], 0.), white);
+        assert_eq!(stripes.color([4., 0.], 0.), [0.; 3]);
+        // rotated a quarter turn the stripes run across
+        let across = stripes.with_rotation(90.);
+        assert_eq!(across.color([4., 2.], 0.), [0.; 3]);
+        assert_eq!(across.color([4., -2.], 0.), white);
+        let rings = Pattern::new(Shape::Rings)
+            .with_center([50., 50.])
+            .with_fill(0.5);
+        assert_eq!(rings.color([50., 110.], 0.), white);
+        assert_eq!(rings.color([50., 140.], 0.), [0.; 3]);
+        let dots = Pattern::new(Shape::Dots);
+        assert_eq!(dots.color([30., 30.], 0.), white);
+        assert_eq!(dots.color([1., 1.], 0.), [0.; 3]);
+        let grid = Pattern::new(Shape::Grid).with_fill(0.1);
+        assert_eq!(grid.color([61., 30.], 0.), white);
+        assert_eq!(grid.color([30., 30.], 0.), [0.; 3]);
+        assert_eq!(Pattern::new(Shape::Linear).color([15., 0.], 0.), [0.25; 3]);
+        // neighbors across each edge of a hexagon get different colors
+        let center = hex(Vec2::default());
+        for k in 0..6 {
+            let a = (k as f64 * 60.).to_radians();
+            assert_ne!(hex(Vec2::new(a.cos(), a.sin()) * 3f64.sqrt()), center);
+        }
+        assert_eq!(Shape::from_name(Shape::Hex.name()), Some(Shape::Hex));
+    }
+}
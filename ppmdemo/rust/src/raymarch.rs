@@ -0,0 +1,165 @@
+//! # Raymarcher
+//! Sphere traces a scene of signed distance fields, normals are the gradient of the field,
+//! shadows are softened by how closely the shadow ray passes the geometry and
+//! ambient occlusion samples the field along the normal. The animation loops every second.
+
+use crate::frame::{Frames, Options};
+use crate::sdf;
+use crate::vector::{Vec2, Vec3, mix, smoothstep};
+use std::f64::consts::TAU;
+
+const STEPS: usize = 160;
+const FAR: f64 = 40.;
+const EPSILON: f64 = 1e-4;
+
+const FLOOR: usize = 0;
+const BLOB: usize = 1;
+const TORUS: usize = 2;
+const PILLARS: usize = 3;
+
+/// # Returns
+/// the distance to the nearest surface and its material
+fn scene(p: Vec3, t: f64) -> (f64, usize) {
+    let a = TAU * t;
+    let floor = sdf::plane(p, Vec3::new(0., 1., 0.), 0.);
+    // a sphere bouncing through a box with a hole carved through it
+    let center = Vec3::new(0., 1. + 0.6 * a.sin().abs(), 0.);
+    let cube = sdf::smooth_subtraction(
+        sdf::cuboid(p - Vec3::new(0., 0.8, 0.), Vec3::splat(0.6)),
+        sdf::capsule(p, Vec3::new(-2., 0.8, 0.), Vec3::new(2., 0.8, 0.), 0.35),
+        0.1,
+    );
+    let blob = sdf::smooth_union(cube, sdf::sphere(p - center, 0.5), 0.4);
+    // a torus standing up and twisting around its axis over the loop
+    let q = p - Vec3::new(2.4, 1.2, 0.);
+    let q = Vec3::new(q.x, q.z, q.y);
+    let torus = sdf::torus(sdf::twist(q, 1.5 * a.sin()), 0.8, 0.25);
+    // a row of pillars behind, the repetition cut to seven by a slab
+    let cell = sdf::repeat(p - Vec3::new(0., 0., -6.), Vec3::new(2.5, 0., 0.));
+    let pillar = sdf::cuboid(cell - Vec3::new(0., 1.5, 0.), Vec3::new(0.3, 1.5, 0.3));
+    let pillars = sdf::intersection(pillar, p.x.abs() - 8.75);
+    [
+        (floor, FLOOR),
+        (blob, BLOB),
+        (torus, TORUS),
+        (pillars, PILLARS),
+    ]
+    .into_iter()
+    .fold((f64::INFINITY, FLOOR), |a, b| if b.0 < a.0 { b } else { a })
+}
+
+fn albedo(p: Vec3, material: usize) -> Vec3 {
+    match material {
+        FLOOR => {
+            let checker = (p.x.floor() + p.z.floor()).rem_euclid(2.);
+            Vec3::splat(0.25 + 0.35 * checker)
+        }
+        BLOB => Vec3::new(0.9, 0.35, 0.2),
+        TORUS => Vec3::new(0.2, 0.5, 0.9),
+        _ => Vec3::splat(0.7),
+    }
+}
+
+/// # Returns
+/// the distance along `rd` to the first surface and its material
+fn march(ro: Vec3, rd: Vec3, t: f64) -> Option<(f64, usize)> {
+    let mut d = 0.;
+    for _ in 0..STEPS {
+        let (h, material) = scene(ro + rd * d, t);
+        if h < EPSILON * d.max(1.) {
+            return Some((d, material));
+        }
+        // the twist bends the field, step a little less than its distance
+        d += 0.8 * h;
+        if d > FAR {
+            break;
+        }
+    }
+    None
+}
+
+/// Gradient of the field from four samples on a tetrahedron
+fn normal(p: Vec3, t: f64) -> Vec3 {
+    let e = 5e-4;
+    [
+        Vec3::new(1., -1., -1.),
+        Vec3::new(-1., -1., 1.),
+        Vec3::new(-1., 1., -1.),
+        Vec3::new(1., 1., 1.),
+    ]
+    .into_iter()
+    .fold(Vec3::default(), |n, k| n + k * scene(p + k * e, t).0)
+    .normalize()
+}
+
+/// `1` when nothing blocks `rd` from `ro`, darker the closer a blocker passes
+fn soft_shadow(ro: Vec3, rd: Vec3, t: f64, k: f64) -> f64 {
+    let mut shade: f64 = 1.;
+    let mut d = 0.02;
+    while d < 12. {
+        let h = scene(ro + rd * d, t).0;
+        if h < EPSILON {
+            return 0.;
+        }
+        shade = shade.min(k * h / d);
+        d += h.clamp(0.02, 0.5);
+    }
+    shade
+}
+
+/// `1` when open, darker where the field along the normal is closer than the distance travelled
+fn occlusion(p: Vec3, n: Vec3, t: f64) -> f64 {
+    let mut occ = 0.;
+    let mut weight = 1.;
+    for i in 1..=5 {
+        let d = 0.04 * i as f64;
+        occ += (d - scene(p + n * d, t).0) * weight;
+        weight *= 0.7;
+    }
+    (1. - 3. * occ).clamp(0., 1.)
+}
+
+/// # Params
+/// `fc` the fragment coordinate from the top left, `r` the resolution, `t` the time in seconds
+///
+/// # Returns
+/// linear radiance, above `1` in the highlights
+pub fn raymarch(fc: Vec2, r: Vec2, t: f64) -> Vec3 {
+    let uv = (fc * 2. - r) / r.y * Vec2::new(1., -1.);
+    // the camera sways around the scene
+    let a = 0.6 + 0.5 * (TAU * t).sin();
+    let ro = Vec3::new(7. * a.sin(), 3., 7. * a.cos());
+    let forward = (Vec3::new(1.2, 0.9, -1.) - ro).normalize();
+    let right = forward.cross(Vec3::new(0., 1., 0.)).normalize();
+    let up = right.cross(forward);
+    let rd = (forward * 1.8 + right * uv.x + up * uv.y).normalize();
+    let sky = Vec3::new(0.5, 0.7, 1.).mix(Vec3::new(0.9, 0.9, 1.), 1. - rd.y.max(0.));
+    let Some((d, material)) = march(ro, rd, t) else {
+        return sky;
+    };
+    let p = ro + rd * d;
+    let n = normal(p, t);
+    let sun = Vec3::new(0.6, 0.7, 0.4).normalize();
+    let shadow = soft_shadow(p + n * 2e-3, sun, t, 8.);
+    let ao = occlusion(p, n, t);
+    let diffuse = n.dot(sun).max(0.) * shadow;
+    let half = (sun - rd).normalize();
+    let specular = n.dot(half).max(0.).powf(48.) * shadow * (material != FLOOR) as u8 as f64;
+    let ambient = mix(0.3, 0.6, 0.5 + 0.5 * n.y) * ao;
+    let color = albedo(p, material)
+        * (Vec3::new(1.3, 1.2, 1.) * diffuse + Vec3::new(0.35, 0.45, 0.6) * ambient)
+        + specular * 2.;
+    // fade into the sky with distance
+    color.mix(sky, smoothstep(15., FAR, d))
+}
+
+pub fn generate(options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let r = Vec2::new(w as f64, h as f64);
+    let frames = Frames::new("raymarch", w, h, fps).with_options(options);
+    frames.render(|[x, y], t| raymarch(Vec2::new(x, y), r, t).to_array());
+}
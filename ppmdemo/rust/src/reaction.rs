@@ -0,0 +1,63 @@
+use crate::frame::{Frames, Options};
+use crate::multipass::{self, Inputs, Pass};
+use crate::vector::{Vec3, smoothstep};
+use common::noise::Noise;
+
+/// Gray-Scott rates of the "coral" regime
+const FEED: f64 = 0.0545;
+const KILL: f64 = 0.062;
+/// simulation steps per frame
+const STEPS: usize = 12;
+
+/// # Reaction-Diffusion
+/// one Gray-Scott step, the buffer holds the concentrations `u` and `v` and
+/// whether it was seeded, unseeded texels start from blobs of simplex noise
+fn react(input: &Inputs, noise: &Noise, [x, y]: [f64; 2]) -> [f64; 3] {
+    let p = [x.floor() as i64, y.floor() as i64];
+    let [u, v, seeded] = input.fetch("sim", p);
+    if seeded == 0. {
+        let spot = noise.simplex([x / 24., y / 24.]) > 0.55;
+        return if spot { [0.5, 0.25, 1.] } else { [1., 0., 1.] };
+    }
+    // 3x3 Laplacian, the diagonals weigh a quarter of the sides
+    let mut laplacian = [-u, -v];
+    for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+        if (dx, dy) == (0, 0) {
+            continue;
+        }
+        let weight = if dx == 0 || dy == 0 { 0.2 } else { 0.05 };
+        let [nu, nv, _] = input.fetch("sim", [p[0] + dx, p[1] + dy]);
+        laplacian[0] += weight * nu;
+        laplacian[1] += weight * nv;
+    }
+    let uvv = u * v * v;
+    let du = laplacian[0] - uvv + FEED * (1. - u);
+    let dv = 0.5 * laplacian[1] + uvv - (FEED + KILL) * v;
+    [u + du, v + dv, 1.]
+}
+
+/// Colors the concentration of `v` and shades its slope like a relief lit from the top left
+fn image(input: &Inputs, [x, y]: [f64; 2]) -> [f64; 3] {
+    let v = |dx: f64, dy: f64| input.sample("sim", [x + dx, y + dy])[1];
+    let slope = (v(-1., -1.) - v(1., 1.)) * 4.;
+    let k = smoothstep(0.1, 0.35, v(0., 0.));
+    let color = Vec3::new(0.02, 0.03, 0.08).mix(Vec3::new(0.95, 0.55, 0.2), k);
+    (color * (1. + slope).max(0.)).to_array()
+}
+
+pub fn generate(options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let noise = Noise::new(11);
+    let frames = Frames::new("reaction", w, h, fps).with_options(options);
+    let passes = [
+        Pass::new("sim", |input, fc, _| react(input, &noise, fc))
+            .with_inputs(&["sim"])
+            .with_steps(STEPS),
+        Pass::new("image", |input, fc, _| image(input, fc)).with_inputs(&["sim"]),
+    ];
+    multipass::render(&frames, &passes);
+}
@@ -0,0 +1,114 @@
+//! # Signed Distance Fields
+//! Distances from `p` to primitives centered at the origin, negative inside,
+//! and operators that combine distances or transform `p` before evaluating a field.
+//! Smooth combinations blend within `k` with the polynomial smooth minimum.
+
+use crate::vector::{Vec2, Vec3, mix};
+
+pub fn sphere(p: Vec3, radius: f64) -> f64 {
+    p.length() - radius
+}
+
+/// `half` is the distance from the center to the faces on each axis
+pub fn cuboid(p: Vec3, half: Vec3) -> f64 {
+    let q = p.abs() - half;
+    q.max(0.).length() + q.x.max(q.y).max(q.z).min(0.)
+}
+
+/// Lying in the xz plane, `radius` to the center of the tube of `thickness`
+pub fn torus(p: Vec3, radius: f64, thickness: f64) -> f64 {
+    Vec2::new(p.xz().length() - radius, p.y).length() - thickness
+}
+
+/// The segment from `a` to `b` with rounded caps
+pub fn capsule(p: Vec3, a: Vec3, b: Vec3, radius: f64) -> f64 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = (pa.dot(ba) / ba.dot(ba)).clamp(0., 1.);
+    (pa - ba * h).length() - radius
+}
+
+/// `normal` must be normalized, the plane is `height` along it from the origin
+pub fn plane(p: Vec3, normal: Vec3, height: f64) -> f64 {
+    p.dot(normal) - height
+}
+
+pub fn union(d1: f64, d2: f64) -> f64 {
+    d1.min(d2)
+}
+
+/// `d1` with `d2` carved out
+pub fn subtraction(d1: f64, d2: f64) -> f64 {
+    d1.max(-d2)
+}
+
+pub fn intersection(d1: f64, d2: f64) -> f64 {
+    d1.max(d2)
+}
+
+pub fn smooth_union(d1: f64, d2: f64, k: f64) -> f64 {
+    let h = (0.5 + 0.5 * (d2 - d1) / k).clamp(0., 1.);
+    mix(d2, d1, h) - k * h * (1. - h)
+}
+
+pub fn smooth_subtraction(d1: f64, d2: f64, k: f64) -> f64 {
+    let h = (0.5 - 0.5 * (d1 + d2) / k).clamp(0., 1.);
+    mix(d1, -d2, h) + k * h * (1. - h)
+}
+
+pub fn smooth_intersection(d1: f64, d2: f64, k: f64) -> f64 {
+    let h = (0.5 - 0.5 * (d2 - d1) / k).clamp(0., 1.);
+    mix(d2, d1, h) + k * h * (1. - h)
+}
+
+/// Folds space into cells of `period` centered at multiples of it,
+/// a zero component leaves that axis alone
+pub fn repeat(p: Vec3, period: Vec3) -> Vec3 {
+    let mut q = p;
+    for i in 0..3 {
+        if period[i] != 0. {
+            q[i] = p[i] - period[i] * (p[i] / period[i]).round();
+        }
+    }
+    q
+}
+
+/// Rotates the xz plane by `k` radians per unit of height,
+/// the result is no longer exact so step with a fraction of the distance
+pub fn twist(p: Vec3, k: f64) -> Vec3 {
+    let (s, c) = (k * p.y).sin_cos();
+    Vec3::new(c * p.x - s * p.z, p.y, s * p.x + c * p.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdf_test() {
+        let p = Vec3::new(3., 4., 0.);
+        assert_eq!(sphere(p, 1.), 4.);
+        assert_eq!(cuboid(p, Vec3::splat(1.)), (4f64 + 9.).sqrt());
+        assert_eq!(cuboid(Vec3::default(), Vec3::new(1., 2., 3.)), -1.);
+        assert_eq!(torus(Vec3::new(2., 0., 0.), 2., 0.5), -0.5);
+        let (a, b) = (Vec3::default(), Vec3::new(0., 2., 0.));
+        assert_eq!(capsule(Vec3::new(1., 1., 0.), a, b, 0.5), 0.5);
+        assert_eq!(capsule(Vec3::new(0., 5., 0.), a, b, 0.5), 2.5);
+        assert_eq!(plane(p, Vec3::new(0., 1., 0.), 1.), 3.);
+        assert_eq!(union(1., 2.), 1.);
+        assert_eq!(subtraction(1., -2.), 2.);
+        assert_eq!(intersection(1., 2.), 2.);
+        // the smooth versions only differ within `k`
+        assert_eq!(smooth_union(1., 3., 0.5), 1.);
+        assert_eq!(smooth_union(1., 1., 0.4), 0.9);
+        assert_eq!(smooth_intersection(1., 3., 0.5), 3.);
+        assert_eq!(smooth_subtraction(1., -3., 0.5), 3.);
+        assert!(smooth_subtraction(1., -1., 0.4) > 1.);
+        assert_eq!(
+            repeat(Vec3::new(5.5, -3.2, 7.), Vec3::new(2., 0., 4.)),
+            Vec3::new(-0.5, -3.2, -1.)
+        );
+        let q = twist(Vec3::new(1., std::f64::consts::FRAC_PI_2, 0.), 1.);
+        assert!((q - Vec3::new(0., std::f64::consts::FRAC_PI_2, 1.)).length() < 1e-12);
+    }
+}
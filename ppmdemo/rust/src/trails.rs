@@ -0,0 +1,64 @@
+use crate::frame::{Frames, Options};
+use crate::multipass::{self, Inputs, Pass};
+use crate::vector::{Vec2, Vec3};
+use std::f64::consts::TAU;
+
+/// share of the trail kept every frame
+const DECAY: f64 = 0.9;
+
+/// Centers and colors of three lights on Lissajous curves, `r` the resolution
+fn lights(r: Vec2, t: f64) -> [(Vec2, Vec3); 3] {
+    let a = TAU * t;
+    let at = |fx: f64, fy: f64, phase: f64| {
+        let p = Vec2::new((fx * a + phase).sin(), (fy * a).sin());
+        r / 2. + p * r.y * 0.4
+    };
+    [
+        (at(1., 2., 0.), Vec3::new(1., 0.3, 0.1)),
+        (at(2., 1., 1.), Vec3::new(0.2, 1., 0.4)),
+        (at(3., 2., 2.), Vec3::new(0.3, 0.4, 1.)),
+    ]
+}
+
+/// # Trails
+/// the previous frame faded by `DECAY`, with the glow of the lights added on top
+fn trail(input: &Inputs, fc: Vec2, r: Vec2, t: f64) -> [f64; 3] {
+    let previous = Vec3::from(input.sample("trail", fc.to_array())) * DECAY;
+    let glow = lights(r, t)
+        .into_iter()
+        .fold(Vec3::default(), |sum, (p, color)| {
+            sum + color * (-(fc - p).dot(fc - p) / 200.).exp()
+        });
+    (previous + glow).to_array()
+}
+
+/// The trails with white-hot cores at the current positions of the lights
+fn image(input: &Inputs, fc: Vec2, r: Vec2, t: f64) -> [f64; 3] {
+    let trail = Vec3::from(input.sample("trail", fc.to_array()));
+    let core = lights(r, t)
+        .into_iter()
+        .map(|(p, _)| (-(fc - p).dot(fc - p) / 20.).exp())
+        .sum::<f64>();
+    (trail + core).to_array()
+}
+
+pub fn generate(options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let r = Vec2::new(w as f64, h as f64);
+    let frames = Frames::new("trails", w, h, fps).with_options(options);
+    let passes = [
+        Pass::new("trail", |input, [x, y], t| {
+            trail(input, Vec2::new(x, y), r, t)
+        })
+        .with_inputs(&["trail"]),
+        Pass::new("image", |input, [x, y], t| {
+            image(input, Vec2::new(x, y), r, t)
+        })
+        .with_inputs(&["trail"]),
+    ];
+    multipass::render(&frames, &passes);
+}
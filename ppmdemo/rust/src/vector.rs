@@ -0,0 +1,355 @@
+//! # GLSL Vectors
+//! `Vec2`, `Vec3` and `Vec4` with component-wise operators against vectors and scalars,
+//! the usual builtins and every swizzle (`v.xyyx()`, `c.bgr()`) so shaders port line by line.
+//! Scalar versions of the builtins that `f64` lacks or defines differently are free functions.
+
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Rem, RemAssign, Sub,
+    SubAssign,
+};
+
+/// `x - y * floor(x / y)`, unlike `%` the sign follows `y`
+#[inline]
+pub fn modulo(x: f64, y: f64) -> f64 {
+    x - y * (x / y).floor()
+}
+
+/// `x - floor(x)`, unlike `f64::fract` always in `[0, 1)`
+#[inline]
+pub fn fract(x: f64) -> f64 {
+    x - x.floor()
+}
+
+#[inline]
+pub fn mix(x: f64, y: f64, a: f64) -> f64 {
+    x * (1. - a) + y * a
+}
+
+#[inline]
+pub fn step(edge: f64, x: f64) -> f64 {
+    if x < edge { 0. } else { 1. }
+}
+
+/// Hermite interpolation between `0` and `1` as `x` goes from `edge0` to `edge1`
+#[inline]
+pub fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+/// `max(min(x, max), min)`, does not panic when `min > max`
+#[inline]
+pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
+    x.max(min).min(max)
+}
+
+macro_rules! ops {
+    ($ty:ident, $($c:ident),+) => {
+        ops!(@op $ty, Add, add, AddAssign, add_assign, +, $($c),+);
+        ops!(@op $ty, Sub, sub, SubAssign, sub_assign, -, $($c),+);
+        ops!(@op $ty, Mul, mul, MulAssign, mul_assign, *, $($c),+);
+        ops!(@op $ty, Div, div, DivAssign, div_assign, /, $($c),+);
+        ops!(@op $ty, Rem, rem, RemAssign, rem_assign, %, $($c),+);
+
+        impl Neg for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self::Output {
+                Self { $($c: -self.$c),+ }
+            }
+        }
+    };
+    (@op $ty:ident, $op:ident, $f:ident, $assign:ident, $f_assign:ident, $sym:tt, $($c:ident),+) => {
+        impl $op<$ty> for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn $f(self, rhs: $ty) -> Self::Output {
+                Self { $($c: self.$c $sym rhs.$c),+ }
+            }
+        }
+
+        impl $op<f64> for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn $f(self, rhs: f64) -> Self::Output {
+                Self { $($c: self.$c $sym rhs),+ }
+            }
+        }
+
+        impl $op<$ty> for f64 {
+            type Output = $ty;
+
+            #[inline]
+            fn $f(self, rhs: $ty) -> Self::Output {
+                $ty { $($c: self $sym rhs.$c),+ }
+            }
+        }
+
+        impl $assign<$ty> for $ty {
+            #[inline]
+            fn $f_assign(&mut self, rhs: $ty) {
+                *self = *self $sym rhs;
+            }
+        }
+
+        impl $assign<f64> for $ty {
+            #[inline]
+            fn $f_assign(&mut self, rhs: f64) {
+                *self = *self $sym rhs;
+            }
+        }
+    };
+}
+
+/// Component-wise versions of one-argument `f64` methods
+macro_rules! maps {
+    ($($f:ident),+) => {
+        $(
+            #[inline]
+            pub fn $f(self) -> Self {
+                self.map(f64::$f)
+            }
+        )+
+    };
+}
+
+macro_rules! vector {
+    ($ty:ident, $n:literal, $($c:ident),+) => {
+        #[derive(Default, Clone, Copy, Debug, PartialEq)]
+        pub struct $ty {
+            $(pub $c: f64),+
+        }
+
+        impl $ty {
+            #[inline]
+            pub const fn new($($c: f64),+) -> Self {
+                Self { $($c),+ }
+            }
+
+            /// Every component set to `v`
+            #[inline]
+            pub const fn splat(v: f64) -> Self {
+                Self { $($c: v),+ }
+            }
+
+            #[inline]
+            pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+                Self { $($c: f(self.$c)),+ }
+            }
+
+            #[inline]
+            pub fn zip(self, rhs: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+                Self { $($c: f(self.$c, rhs.$c)),+ }
+            }
+
+            #[inline]
+            pub fn to_array(self) -> [f64; $n] {
+                [$(self.$c),+]
+            }
+
+            maps!(sin, cos, tan, asin, acos, atan, sinh, cosh, tanh, exp, exp2, ln, log2, sqrt, abs, floor, ceil, round);
+
+            #[inline]
+            pub fn sign(self) -> Self {
+                self.map(|x| if x == 0. { 0. } else { x.signum() })
+            }
+
+            #[inline]
+            pub fn fract(self) -> Self {
+                self.map(fract)
+            }
+
+            #[inline]
+            pub fn modulo(self, y: impl Into<Self>) -> Self {
+                self.zip(y.into(), modulo)
+            }
+
+            #[inline]
+            pub fn pow(self, y: impl Into<Self>) -> Self {
+                self.zip(y.into(), f64::powf)
+            }
+
+            #[inline]
+            pub fn min(self, y: impl Into<Self>) -> Self {
+                self.zip(y.into(), f64::min)
+            }
+
+            #[inline]
+            pub fn max(self, y: impl Into<Self>) -> Self {
+                self.zip(y.into(), f64::max)
+            }
+
+            #[inline]
+            pub fn clamp(self, min: impl Into<Self>, max: impl Into<Self>) -> Self {
+                self.max(min).min(max)
+            }
+
+            /// `self * (1 - a) + y * a`
+            #[inline]
+            pub fn mix(self, y: Self, a: impl Into<Self>) -> Self {
+                let a = a.into();
+                Self { $($c: mix(self.$c, y.$c, a.$c)),+ }
+            }
+
+            /// `0` where `self < edge`, else `1`
+            #[inline]
+            pub fn step(self, edge: impl Into<Self>) -> Self {
+                self.zip(edge.into(), |x, edge| step(edge, x))
+            }
+
+            /// `smoothstep(edge0, edge1, self)`
+            #[inline]
+            pub fn smoothstep(self, edge0: impl Into<Self>, edge1: impl Into<Self>) -> Self {
+                let (e0, e1) = (edge0.into(), edge1.into());
+                Self { $($c: smoothstep(e0.$c, e1.$c, self.$c)),+ }
+            }
+
+            #[inline]
+            pub fn dot(self, rhs: Self) -> f64 {
+                0. $(+ self.$c * rhs.$c)+
+            }
+
+            #[inline]
+            pub fn length(self) -> f64 {
+                self.dot(self).sqrt()
+            }
+
+            #[inline]
+            pub fn distance(self, rhs: Self) -> f64 {
+                (self - rhs).length()
+            }
+
+            #[inline]
+            pub fn normalize(self) -> Self {
+                self / self.length()
+            }
+
+            /// Reflects the incident `self` about the normal `n`, which should be normalized
+            #[inline]
+            pub fn reflect(self, n: Self) -> Self {
+                self - 2. * n.dot(self) * n
+            }
+        }
+
+        impl From<f64> for $ty {
+            #[inline]
+            fn from(v: f64) -> Self {
+                Self::splat(v)
+            }
+        }
+
+        impl From<[f64; $n]> for $ty {
+            #[inline]
+            fn from([$($c),+]: [f64; $n]) -> Self {
+                Self { $($c),+ }
+            }
+        }
+
+        impl From<$ty> for [f64; $n] {
+            #[inline]
+            fn from(v: $ty) -> Self {
+                v.to_array()
+            }
+        }
+
+        impl Index<usize> for $ty {
+            type Output = f64;
+
+            fn index(&self, i: usize) -> &f64 {
+                [$(&self.$c),+][i]
+            }
+        }
+
+        impl IndexMut<usize> for $ty {
+            fn index_mut(&mut self, i: usize) -> &mut f64 {
+                [$(&mut self.$c),+].into_iter().nth(i).unwrap()
+            }
+        }
+
+        ops!($ty, $($c),+);
+    };
+}
+
+vector!(Vec2, 2, x, y);
+vector!(Vec3, 3, x, y, z);
+vector!(Vec4, 4, x, y, z, w);
+
+include!(concat!(env!("OUT_DIR"), "/swizzle.rs"));
+
+impl Vec2 {
+    /// `vec3(self, z)`
+    #[inline]
+    pub fn extend(self, z: f64) -> Vec3 {
+        Vec3::new(self.x, self.y, z)
+    }
+}
+
+impl Vec3 {
+    #[inline]
+    pub fn cross(self, rhs: Self) -> Self {
+        Self::new(
+            self.y * rhs.z - self.z * rhs.y,
+            self.z * rhs.x - self.x * rhs.z,
+            self.x * rhs.y - self.y * rhs.x,
+        )
+    }
+
+    /// `vec4(self, w)`
+    #[inline]
+    pub fn extend(self, w: f64) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vector_test() {
+        let v = Vec4::new(1., 2., 3., 4.);
+        assert_eq!(v.wzyx(), Vec4::new(4., 3., 2., 1.));
+        assert_eq!(v.bgr(), Vec3::new(3., 2., 1.));
+        assert_eq!(v.xx(), Vec2::splat(1.));
+        assert_eq!(Vec2::new(1., 2.).yxxy(), Vec4::new(2., 1., 1., 2.));
+        assert_eq!(1. - v, Vec4::new(0., -1., -2., -3.));
+        assert_eq!(v - v * 2. / v, Vec4::new(-1., 0., 1., 2.));
+        let mut w = v;
+        w += 1.;
+        w *= Vec4::splat(2.);
+        w %= 3.;
+        assert_eq!(w, Vec4::new(1., 0., 2., 1.));
+        assert_eq!(v[2], 3.);
+        w[3] = 9.;
+        assert_eq!(w.w, 9.);
+        // GLSL mod and fract follow floor, not truncation
+        assert_eq!(Vec2::new(-1.5, 2.5).modulo(2.), Vec2::new(0.5, 0.5));
+        assert_eq!(Vec2::new(-0.25, 1.25).fract(), Vec2::new(0.75, 0.25));
+        assert_eq!(
+            Vec3::new(-1., 0.5, 2.).clamp(0., 1.),
+            Vec3::new(0., 0.5, 1.)
+        );
+        assert_eq!(
+            Vec2::new(0., 10.).mix(Vec2::new(1., 20.), 0.25),
+            Vec2::new(0.25, 12.5)
+        );
+        assert_eq!(
+            Vec3::new(-1., 0.5, 2.).smoothstep(0., 1.),
+            Vec3::new(0., 0.5, 1.)
+        );
+        assert_eq!(Vec3::new(3., 0., 4.).length(), 5.);
+        assert_eq!(Vec2::new(0., -3.).normalize(), Vec2::new(0., -1.));
+        assert_eq!(
+            Vec3::new(1., -1., 0.).reflect(Vec3::new(0., 1., 0.)),
+            Vec3::new(1., 1., 0.)
+        );
+        assert_eq!(
+            Vec3::new(1., 0., 0.).cross(Vec3::new(0., 1., 0.)),
+            Vec3::new(0., 0., 1.)
+        );
+        assert_eq!(Vec2::new(1., 2.).extend(3.).extend(4.), v);
+    }
+}
@@ -0,0 +1,974 @@
+//! # Shader Bytecode
+//! Compiles a `glsl::Program` to instructions over registers of four components.
+//! The first registers are the variables, constants get their own preloaded registers
+//! and subtrees of constants are folded at compile time with the interpreter's arithmetic.
+
+use crate::frame::{Frames, Options};
+use crate::glsl::{
+    BinOp, Builtin, Expr, ExprKind, FC, LValue, O, Program, R, Stmt, T, Type, UnOp, Value,
+};
+use crate::interp;
+use crate::shader;
+use crate::vector::Vec2;
+use std::time::Instant;
+
+type Reg = u32;
+
+/// Marks constant numbers until they are given registers
+const CONSTANT: Reg = 1 << 31;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Move {
+        dst: Reg,
+        src: Reg,
+    },
+    Swizzle {
+        dst: Reg,
+        src: Reg,
+        components: [u8; 4],
+        n: u8,
+    },
+    /// `dst[components[i]] = src[i]`
+    Scatter {
+        dst: Reg,
+        src: Reg,
+        components: [u8; 4],
+        n: u8,
+    },
+    Neg {
+        dst: Reg,
+        src: Reg,
+    },
+    Not {
+        dst: Reg,
+        src: Reg,
+    },
+    /// component-wise `+ - * /`, a size of `1` broadcasts the operand
+    Arith {
+        op: BinOp,
+        int: bool,
+        dst: Reg,
+        a: Reg,
+        b: Reg,
+        sa: u8,
+        sb: u8,
+        n: u8,
+    },
+    /// matrix products and comparisons
+    Binary {
+        op: BinOp,
+        dst: Reg,
+        a: Reg,
+        b: Reg,
+        ta: Type,
+        tb: Type,
+        ty: Type,
+    },
+    /// one-argument component-wise builtins
+    Map {
+        f: fn(f64) -> f64,
+        dst: Reg,
+        src: Reg,
+        n: u8,
+    },
+    /// two-argument component-wise builtins
+    Map2 {
+        f: fn(f64, f64) -> f64,
+        dst: Reg,
+        a: Reg,
+        b: Reg,
+        sa: u8,
+        sb: u8,
+        n: u8,
+    },
+    Call {
+        f: Builtin,
+        dst: Reg,
+        args: [Reg; 3],
+        sizes: [u8; 3],
+        argc: u8,
+    },
+    Construct {
+        ty: Type,
+        dst: Reg,
+        args: [Reg; 4],
+        sizes: [u8; 4],
+        argc: u8,
+    },
+    Jump {
+        target: u32,
+    },
+    JumpIfFalse {
+        cond: Reg,
+        target: u32,
+    },
+    JumpIfTrue {
+        cond: Reg,
+        target: u32,
+    },
+    Halt,
+}
+
+fn map(f: Builtin) -> Option<fn(f64) -> f64> {
+    Some(match f {
+        Builtin::Sin => f64::sin,
+        Builtin::Cos => f64::cos,
+        Builtin::Tan => f64::tan,
+        Builtin::Asin => f64::asin,
+        Builtin::Acos => f64::acos,
+        Builtin::Atan => f64::atan,
+        Builtin::Sinh => f64::sinh,
+        Builtin::Cosh => f64::cosh,
+        Builtin::Tanh => f64::tanh,
+        Builtin::Exp => f64::exp,
+        Builtin::Exp2 => f64::exp2,
+        Builtin::Log => f64::ln,
+        Builtin::Log2 => f64::log2,
+        Builtin::Sqrt => f64::sqrt,
+        Builtin::InverseSqrt => |x| 1. / x.sqrt(),
+        Builtin::Abs => f64::abs,
+        Builtin::Sign => |x| if x == 0. { 0. } else { x.signum() },
+        Builtin::Floor => f64::floor,
+        Builtin::Ceil => f64::ceil,
+        Builtin::Fract => |x| x - x.floor(),
+        Builtin::Round => f64::round,
+        Builtin::Radians => f64::to_radians,
+        Builtin::Degrees => f64::to_degrees,
+        _ => return None,
+    })
+}
+
+fn map2(f: Builtin) -> Option<fn(f64, f64) -> f64> {
+    Some(match f {
+        Builtin::Atan => f64::atan2,
+        Builtin::Pow => f64::powf,
+        Builtin::Mod => |x, y| x - y * (x / y).floor(),
+        Builtin::Min => f64::min,
+        Builtin::Max => f64::max,
+        Builtin::Step => |edge, x| if x < edge { 0. } else { 1. },
+        _ => return None,
+    })
+}
+
+/// # Compiled Shader
+pub struct Bytecode {
+    code: Vec<Op>,
+    /// initial registers, variables are zero and constants preloaded
+    registers: Vec<Value>,
+}
+
+/// Jumps to patch at the end of a loop
+struct LoopLabels {
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+struct Compiler {
+    code: Vec<Op>,
+    registers: Vec<Value>,
+    /// registers below are variables, temporaries start here
+    base: usize,
+    next: usize,
+    constants: Vec<Value>,
+    loops: Vec<LoopLabels>,
+    /// furthest position a forward jump lands on
+    label: usize,
+}
+
+/// Whether evaluating `e` may write a variable
+fn writes(e: &Expr) -> bool {
+    match &e.kind {
+        ExprKind::Literal(_) | ExprKind::Var(_) => false,
+        ExprKind::Assign(..) | ExprKind::Step(..) => true,
+        ExprKind::Swizzle(a, _) | ExprKind::Unary(_, a) => writes(a),
+        ExprKind::Binary(_, a, b) | ExprKind::Logic(_, a, b) | ExprKind::Comma(a, b) => {
+            writes(a) || writes(b)
+        }
+        ExprKind::Ternary(c, a, b) => writes(c) || writes(a) || writes(b),
+        ExprKind::Call(_, args) | ExprKind::Construct(_, args) => args.iter().any(writes),
+    }
+}
+
+/// # Returns
+/// the value of `e` if it only depends on literals
+fn fold(e: &Expr) -> Option<Value> {
+    let all = |args: &[Expr]| args.iter().map(fold).collect::<Option<Vec<_>>>();
+    let sizes = |args: &[Expr]| args.iter().map(|a| a.ty.size()).collect::<Vec<_>>();
+    match &e.kind {
+        ExprKind::Literal(v) => Some(*v),
+        ExprKind::Var(_) | ExprKind::Assign(..) | ExprKind::Step(..) => None,
+        ExprKind::Swizzle(a, components) => {
+            let v = fold(a)?;
+            let mut out = [0.; 4];
+            for (out, &c) in out.iter_mut().zip(components) {
+                *out = v[c];
+            }
+            Some(out)
+        }
+        ExprKind::Unary(UnOp::Neg, a) => Some(fold(a)?.map(|c| -c)),
+        ExprKind::Unary(UnOp::Not, a) => Some([(fold(a)?[0] == 0.) as u8 as f64, 0., 0., 0.]),
+        ExprKind::Binary(op, a, b) | ExprKind::Logic(op, a, b) => {
+            Some(interp::binary(*op, fold(a)?, a.ty, fold(b)?, b.ty, e.ty))
+        }
+        ExprKind::Comma(a, b) if !writes(a) => fold(b),
+        ExprKind::Comma(..) => None,
+        ExprKind::Ternary(c, a, b) => {
+            if fold(c)?[0] != 0. {
+                fold(a)
+            } else {
+                fold(b)
+            }
+        }
+        ExprKind::Call(f, args) => Some(interp::call(*f, &all(args)?, &sizes(args))),
+        ExprKind::Construct(ty, args) => Some(interp::construct(*ty, &all(args)?, &sizes(args))),
+    }
+}
+
+fn components(cs: &[usize]) -> ([u8; 4], u8) {
+    let mut out = [0; 4];
+    for (out, &c) in out.iter_mut().zip(cs) {
+        *out = c as u8;
+    }
+    (out, cs.len() as u8)
+}
+
+impl Compiler {
+    fn temp(&mut self) -> Reg {
+        let r = self.next;
+        self.next += 1;
+        if self.next > self.registers.len() {
+            self.registers.push([0.; 4]);
+        }
+        r as Reg
+    }
+
+    /// Constants are numbered apart and placed after the temporaries once compiled
+    fn constant(&mut self, v: Value) -> Reg {
+        let i = match self.constants.iter().position(|c| *c == v) {
+            Some(i) => i,
+            None => {
+                self.constants.push(v);
+                self.constants.len() - 1
+            }
+        };
+        CONSTANT | i as Reg
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        self.code.push(op);
+        self.code.len() - 1
+    }
+
+    fn patch(&mut self, at: usize, to: usize) {
+        match &mut self.code[at] {
+            Op::Jump { target }
+            | Op::JumpIfFalse { target, .. }
+            | Op::JumpIfTrue { target, .. } => *target = to as u32,
+            _ => unreachable!(),
+        }
+        self.label = self.label.max(to);
+    }
+
+    /// Copies variable registers that a later argument may overwrite
+    fn args(&mut self, args: &[&Expr]) -> Vec<Reg> {
+        let mut regs = Vec::with_capacity(args.len());
+        for (i, a) in args.iter().enumerate() {
+            let mut r = self.expr(a);
+            if (r as usize) < self.base && args[i + 1..].iter().any(|a| writes(a)) {
+                let dst = self.temp();
+                self.emit(Op::Move { dst, src: r });
+                r = dst;
+            }
+            regs.push(r);
+        }
+        regs
+    }
+
+    /// Writes `src` through `lvalue`, returns the register holding the stored value
+    fn store(&mut self, lvalue: &LValue, src: Reg) -> Reg {
+        let var = lvalue.var as Reg;
+        match &lvalue.swizzle {
+            None => {
+                // retarget the instruction that produced a fresh temporary,
+                // unless a jump skips it to the end of the code like a branch of `?:`
+                let fresh = src as usize >= self.base
+                    && src as usize == self.next - 1
+                    && self.label != self.code.len();
+                match self.code.last_mut() {
+                    Some(op) if fresh && dst_of(op) == Some(src) => set_dst(op, var),
+                    _ => {
+                        self.emit(Op::Move { dst: var, src });
+                    }
+                }
+                var
+            }
+            Some(cs) => {
+                let (components, n) = components(cs);
+                self.emit(Op::Scatter {
+                    dst: var,
+                    src,
+                    components,
+                    n,
+                });
+                src
+            }
+        }
+    }
+
+    fn load(&mut self, lvalue: &LValue) -> Reg {
+        match &lvalue.swizzle {
+            None => lvalue.var as Reg,
+            Some(cs) => {
+                let (components, n) = components(cs);
+                let dst = self.temp();
+                self.emit(Op::Swizzle {
+                    dst,
+                    src: lvalue.var as Reg,
+                    components,
+                    n,
+                });
+                dst
+            }
+        }
+    }
+
+    fn arith(&mut self, op: BinOp, ty: Type, a: Reg, ta: Type, b: Reg, tb: Type) -> Reg {
+        let dst = self.temp();
+        match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => self.emit(Op::Arith {
+                op,
+                int: ty == Type::Int,
+                dst,
+                a,
+                b,
+                sa: ta.size() as u8,
+                sb: tb.size() as u8,
+                n: ty.size() as u8,
+            }),
+            _ => self.emit(Op::Binary {
+                op,
+                dst,
+                a,
+                b,
+                ta,
+                tb,
+                ty,
+            }),
+        };
+        dst
+    }
+
+    /// # Returns
+    /// the register holding the value, variables are not copied
+    fn expr(&mut self, e: &Expr) -> Reg {
+        if let Some(v) = fold(e) {
+            return self.constant(v);
+        }
+        match &e.kind {
+            ExprKind::Literal(_) => unreachable!(),
+            ExprKind::Var(var) => *var as Reg,
+            ExprKind::Swizzle(a, cs) => {
+                let src = self.expr(a);
+                let (components, n) = components(cs);
+                let dst = self.temp();
+                self.emit(Op::Swizzle {
+                    dst,
+                    src,
+                    components,
+                    n,
+                });
+                dst
+            }
+            ExprKind::Unary(op, a) => {
+                let src = self.expr(a);
+                let dst = self.temp();
+                self.emit(match op {
+                    UnOp::Neg => Op::Neg { dst, src },
+                    UnOp::Not => Op::Not { dst, src },
+                });
+                dst
+            }
+            ExprKind::Binary(op, a, b) => {
+                let regs = self.args(&[a, b]);
+                self.arith(*op, e.ty, regs[0], a.ty, regs[1], b.ty)
+            }
+            ExprKind::Logic(op, a, b) => {
+                let dst = self.temp();
+                let ra = self.expr(a);
+                self.emit(Op::Move { dst, src: ra });
+                let skip = self.emit(match op {
+                    BinOp::And => Op::JumpIfFalse {
+                        cond: dst,
+                        target: 0,
+                    },
+                    _ => Op::JumpIfTrue {
+                        cond: dst,
+                        target: 0,
+                    },
+                });
+                let rb = self.expr(b);
+                self.emit(Op::Move { dst, src: rb });
+                let end = self.code.len();
+                self.patch(skip, end);
+                dst
+            }
+            ExprKind::Comma(a, b) => {
+                self.expr(a);
+                self.expr(b)
+            }
+            ExprKind::Ternary(c, a, b) => {
+                let dst = self.temp();
+                let cond = self.expr(c);
+                let otherwise = self.emit(Op::JumpIfFalse { cond, target: 0 });
+                let ra = self.expr(a);
+                self.emit(Op::Move { dst, src: ra });
+                let end = self.emit(Op::Jump { target: 0 });
+                let at = self.code.len();
+                self.patch(otherwise, at);
+                let rb = self.expr(b);
+                self.emit(Op::Move { dst, src: rb });
+                let at = self.code.len();
+                self.patch(end, at);
+                dst
+            }
+            ExprKind::Call(f, args) => {
+                let regs = self.args(&args.iter().collect::<Vec<_>>());
+                let sizes = args.iter().map(|a| a.ty.size() as u8).collect::<Vec<_>>();
+                let n = e.ty.size() as u8;
+                let dst = self.temp();
+                match (regs.len(), map(*f), map2(*f)) {
+                    (1, Some(f), _) => self.emit(Op::Map {
+                        f,
+                        dst,
+                        src: regs[0],
+                        n,
+                    }),
+                    (2, _, Some(f)) => self.emit(Op::Map2 {
+                        f,
+                        dst,
+                        a: regs[0],
+                        b: regs[1],
+                        sa: sizes[0],
+                        sb: sizes[1],
+                        n,
+                    }),
+                    _ => {
+                        let mut a = [0; 3];
+                        let mut s = [0; 3];
+                        a[..regs.len()].copy_from_slice(&regs);
+                        s[..regs.len()].copy_from_slice(&sizes);
+                        self.emit(Op::Call {
+                            f: *f,
+                            dst,
+                            args: a,
+                            sizes: s,
+                            argc: regs.len() as u8,
+                        })
+                    }
+                };
+                dst
+            }
+            ExprKind::Construct(ty, args) => {
+                let regs = self.args(&args.iter().collect::<Vec<_>>());
+                let mut a = [0; 4];
+                let mut s = [0; 4];
+                a[..regs.len()].copy_from_slice(&regs);
+                for (s, arg) in s.iter_mut().zip(args) {
+                    *s = arg.ty.size() as u8;
+                }
+                let dst = self.temp();
+                self.emit(Op::Construct {
+                    ty: *ty,
+                    dst,
+                    args: a,
+                    sizes: s,
+                    argc: regs.len() as u8,
+                });
+                dst
+            }
+            ExprKind::Assign(lvalue, op, rhs) => {
+                let src = self.expr(rhs);
+                let src = match op {
+                    None => src,
+                    Some(op) => {
+                        let current = self.load(lvalue);
+                        self.arith(*op, e.ty, current, e.ty, src, rhs.ty)
+                    }
+                };
+                self.store(lvalue, src)
+            }
+            ExprKind::Step(lvalue, delta, prefix) => {
+                let current = self.load(lvalue);
+                let old = if *prefix {
+                    current
+                } else {
+                    let old = self.temp();
+                    self.emit(Op::Move {
+                        dst: old,
+                        src: current,
+                    });
+                    old
+                };
+                let one = self.constant([*delta, 0., 0., 0.]);
+                let new = self.arith(BinOp::Add, e.ty, current, e.ty, one, Type::Float);
+                let new = self.store(lvalue, new);
+                if *prefix { new } else { old }
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        // temporaries only live within a statement
+        let next = self.next;
+        match stmt {
+            Stmt::Expr(e) => {
+                self.expr(e);
+            }
+            Stmt::Block(stmts) => {
+                for s in stmts {
+                    self.stmt(s);
+                }
+            }
+            Stmt::If(cond, then, otherwise) => {
+                if let Some(c) = fold(cond) {
+                    if c[0] != 0. {
+                        self.stmt(then);
+                    } else if let Some(otherwise) = otherwise {
+                        self.stmt(otherwise);
+                    }
+                } else {
+                    let cond = self.expr(cond);
+                    let skip = self.emit(Op::JumpIfFalse { cond, target: 0 });
+                    self.stmt(then);
+                    match otherwise {
+                        None => {
+                            let at = self.code.len();
+                            self.patch(skip, at);
+                        }
+                        Some(otherwise) => {
+                            let end = self.emit(Op::Jump { target: 0 });
+                            let at = self.code.len();
+                            self.patch(skip, at);
+                            self.stmt(otherwise);
+                            let at = self.code.len();
+                            self.patch(end, at);
+                        }
+                    }
+                }
+            }
+            Stmt::Loop {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                for s in init {
+                    self.stmt(s);
+                }
+                let top = self.code.len();
+                let exit = cond.as_ref().map(|c| {
+                    let cond = self.expr(c);
+                    self.emit(Op::JumpIfFalse { cond, target: 0 })
+                });
+                self.loops.push(LoopLabels {
+                    breaks: exit.into_iter().collect(),
+                    continues: Vec::new(),
+                });
+                self.stmt(body);
+                let labels = self.loops.pop().unwrap();
+                let at = self.code.len();
+                for c in labels.continues {
+                    self.patch(c, at);
+                }
+                if let Some(step) = step {
+                    self.expr(step);
+                }
+                self.emit(Op::Jump { target: top as u32 });
+                let at = self.code.len();
+                for b in labels.breaks {
+                    self.patch(b, at);
+                }
+            }
+            Stmt::Break => {
+                let at = self.emit(Op::Jump { target: 0 });
+                self.loops.last_mut().unwrap().breaks.push(at);
+            }
+            Stmt::Continue => {
+                let at = self.emit(Op::Jump { target: 0 });
+                self.loops.last_mut().unwrap().continues.push(at);
+            }
+            Stmt::Return => {
+                self.emit(Op::Halt);
+            }
+        }
+        self.next = next;
+    }
+}
+
+fn dst_of(op: &Op) -> Option<Reg> {
+    match *op {
+        Op::Move { dst, .. }
+        | Op::Swizzle { dst, .. }
+        | Op::Neg { dst, .. }
+        | Op::Not { dst, .. }
+        | Op::Arith { dst, .. }
+        | Op::Binary { dst, .. }
+        | Op::Map { dst, .. }
+        | Op::Map2 { dst, .. }
+        | Op::Call { dst, .. }
+        | Op::Construct { dst, .. } => Some(dst),
+        _ => None,
+    }
+}
+
+fn set_dst(op: &mut Op, reg: Reg) {
+    match op {
+        Op::Move { dst, .. }
+        | Op::Swizzle { dst, .. }
+        | Op::Neg { dst, .. }
+        | Op::Not { dst, .. }
+        | Op::Arith { dst, .. }
+        | Op::Binary { dst, .. }
+        | Op::Map { dst, .. }
+        | Op::Map2 { dst, .. }
+        | Op::Call { dst, .. }
+        | Op::Construct { dst, .. } => *dst = reg,
+        _ => unreachable!(),
+    }
+}
+
+/// Visits every register an instruction uses
+fn visit_registers(op: &mut Op, mut f: impl FnMut(&mut Reg)) {
+    match op {
+        Op::Move { dst, src }
+        | Op::Swizzle { dst, src, .. }
+        | Op::Scatter { dst, src, .. }
+        | Op::Neg { dst, src }
+        | Op::Not { dst, src }
+        | Op::Map { dst, src, .. } => {
+            f(dst);
+            f(src);
+        }
+        Op::Arith { dst, a, b, .. } | Op::Binary { dst, a, b, .. } | Op::Map2 { dst, a, b, .. } => {
+            f(dst);
+            f(a);
+            f(b);
+        }
+        Op::Call {
+            dst, args, argc, ..
+        } => {
+            f(dst);
+            args[..*argc as usize].iter_mut().for_each(f);
+        }
+        Op::Construct {
+            dst, args, argc, ..
+        } => {
+            f(dst);
+            args[..*argc as usize].iter_mut().for_each(f);
+        }
+        Op::JumpIfFalse { cond, .. } | Op::JumpIfTrue { cond, .. } => f(cond),
+        Op::Jump { .. } | Op::Halt => {}
+    }
+}
+
+/// Component `i`, a size of `1` broadcasts
+#[inline(always)]
+fn at(v: &Value, size: u8, i: usize) -> f64 {
+    if size == 1 { v[0] } else { v[i] }
+}
+
+impl Bytecode {
+    pub fn compile(program: &Program) -> Self {
+        let vars = program.vars.len();
+        let mut compiler = Compiler {
+            code: Vec::new(),
+            registers: vec![[0.; 4]; vars],
+            base: vars,
+            next: vars,
+            constants: Vec::new(),
+            loops: Vec::new(),
+            label: 0,
+        };
+        for stmt in &program.body {
+            compiler.stmt(stmt);
+        }
+        let Compiler {
+            mut code,
+            mut registers,
+            constants,
+            ..
+        } = compiler;
+        let first = registers.len() as Reg;
+        for op in &mut code {
+            visit_registers(op, |r| {
+                if *r & CONSTANT != 0 {
+                    *r = first + (*r & !CONSTANT);
+                }
+            });
+        }
+        registers.extend(constants);
+        Self { code, registers }
+    }
+
+    /// Number of instructions
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    /// Same contract as `interp::run`
+    pub fn run(&self, [x, y]: [f64; 2], r: [f64; 2], t: f64) -> [f64; 3] {
+        let mut regs = self.registers.clone();
+        regs[FC] = [x, r[1] - y, 0., 1.];
+        regs[R] = [r[0], r[1], 0., 0.];
+        regs[T] = [t, 0., 0., 0.];
+        let code = &self.code[..];
+        let mut pc = 0;
+        while pc < code.len() {
+            match code[pc] {
+                Op::Move { dst, src } => regs[dst as usize] = regs[src as usize],
+                Op::Swizzle {
+                    dst,
+                    src,
+                    components,
+                    n,
+                } => {
+                    let v = regs[src as usize];
+                    let mut out = [0.; 4];
+                    for i in 0..n as usize {
+                        out[i] = v[components[i] as usize];
+                    }
+                    regs[dst as usize] = out;
+                }
+                Op::Scatter {
+                    dst,
+                    src,
+                    components,
+                    n,
+                } => {
+                    let v = regs[src as usize];
+                    for i in 0..n as usize {
+                        regs[dst as usize][components[i] as usize] = v[i];
+                    }
+                }
+                Op::Neg { dst, src } => regs[dst as usize] = regs[src as usize].map(|c| -c),
+                Op::Not { dst, src } => {
+                    regs[dst as usize] = [(regs[src as usize][0] == 0.) as u8 as f64, 0., 0., 0.]
+                }
+                Op::Arith {
+                    op,
+                    int,
+                    dst,
+                    a,
+                    b,
+                    sa,
+                    sb,
+                    n,
+                } => {
+                    let (va, vb) = (regs[a as usize], regs[b as usize]);
+                    let mut out = [0.; 4];
+                    for (i, out) in out.iter_mut().enumerate().take(n as usize) {
+                        let (x, y) = (at(&va, sa, i), at(&vb, sb, i));
+                        *out = match op {
+                            BinOp::Add => x + y,
+                            BinOp::Sub => x - y,
+                            BinOp::Mul => x * y,
+                            _ if int => (x / y).trunc(),
+                            _ => x / y,
+                        };
+                    }
+                    regs[dst as usize] = out;
+                }
+                Op::Binary {
+                    op,
+                    dst,
+                    a,
+                    b,
+                    ta,
+                    tb,
+                    ty,
+                } => {
+                    let (va, vb) = (regs[a as usize], regs[b as usize]);
+                    regs[dst as usize] = interp::binary(op, va, ta, vb, tb, ty);
+                }
+                Op::Map { f, dst, src, n } => {
+                    let v = regs[src as usize];
+                    let mut out = [0.; 4];
+                    for i in 0..n as usize {
+                        out[i] = f(v[i]);
+                    }
+                    regs[dst as usize] = out;
+                }
+                Op::Map2 {
+                    f,
+                    dst,
+                    a,
+                    b,
+                    sa,
+                    sb,
+                    n,
+                } => {
+                    let (va, vb) = (regs[a as usize], regs[b as usize]);
+                    let mut out = [0.; 4];
+                    for (i, out) in out.iter_mut().enumerate().take(n as usize) {
+                        *out = f(at(&va, sa, i), at(&vb, sb, i));
+                    }
+                    regs[dst as usize] = out;
+                }
+                Op::Call {
+                    f,
+                    dst,
+                    args,
+                    sizes,
+                    argc,
+                } => {
+                    let n = argc as usize;
+                    let values = args.map(|r| regs[r as usize]);
+                    let sizes = sizes.map(|s| s as usize);
+                    regs[dst as usize] = interp::call(f, &values[..n], &sizes[..n]);
+                }
+                Op::Construct {
+                    ty,
+                    dst,
+                    args,
+                    sizes,
+                    argc,
+                } => {
+                    let n = argc as usize;
+                    let values = args.map(|r| regs[r as usize]);
+                    let sizes = sizes.map(|s| s as usize);
+                    regs[dst as usize] = interp::construct(ty, &values[..n], &sizes[..n]);
+                }
+                Op::Jump { target } => {
+                    pc = target as usize;
+                    continue;
+                }
+                Op::JumpIfFalse { cond, target } => {
+                    if regs[cond as usize][0] == 0. {
+                        pc = target as usize;
+                        continue;
+                    }
+                }
+                Op::JumpIfTrue { cond, target } => {
+                    if regs[cond as usize][0] != 0. {
+                        pc = target as usize;
+                        continue;
+                    }
+                }
+                Op::Halt => break,
+            }
+            pc += 1;
+        }
+        let [r, g, b, _] = regs[O];
+        [r, g, b]
+    }
+}
+
+pub fn generate(bytecode: &Bytecode, name: &str, options: Options) {
+    let scale = [16, 9];
+    let fps = 60;
+    let cell = fps;
+    let w = scale[0] * cell;
+    let h = scale[1] * cell;
+    let r = [w as f64, h as f64];
+    let frames = Frames::new(name, w, h, fps).with_options(options);
+    frames.render(|fc, t| bytecode.run(fc, r, t));
+}
+
+/// # Benchmark
+/// times one frame of the native swirl as the `shader` demo shades it, the interpreter and the
+/// bytecode on one thread, `program` should be the swirl for the results to be comparable.
+/// `FC` is bottom-up, so the bytecode is checked against the native frame mirrored vertically.
+pub fn bench(program: &Program) {
+    let [w, h] = [960, 540];
+    let r = [w as f64, h as f64];
+    let t = 0.5;
+    let bytecode = Bytecode::compile(program);
+    let native = |[x, y]: [f64; 2]| {
+        let o = shader::swirl(Vec2::new(x, y), Vec2::new(r[0], r[1]), t);
+        [o.x, o.y, o.z]
+    };
+    let time = |name: &str, shade: &dyn Fn([f64; 2]) -> [f64; 3]| {
+        let start = Instant::now();
+        let mut pixels = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                pixels.push(shade([x as f64, y as f64]));
+            }
+        }
+        let ms = start.elapsed().as_secs_f64() * 1000.;
+        println!("{:<12}{:>10.1} ms/frame", name, ms);
+        (ms, pixels)
+    };
+    let (native_ms, _) = time("native", &native);
+    let (tree, _) = time("interpreter", &|fc| interp::run(program, fc, r, t));
+    let (vm, pixels) = time("bytecode", &|fc| bytecode.run(fc, r, t));
+    let error = pixels
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (native([(i % w) as f64, r[1] - (i / w) as f64]), b))
+        .flat_map(|(a, b)| (0..3).map(move |i| (a[i] - b[i]).abs()))
+        .fold(0., f64::max);
+    println!("{} instructions", bytecode.len());
+    println!(
+        "bytecode is {:.1}x faster than the interpreter, {:.1}x slower than native",
+        tree / vm,
+        vm / native_ms
+    );
+    println!(
+        "FC is bottom-up, the bytecode frame is the native frame mirrored vertically, \
+         largest difference from the mirrored native {:e}",
+        error
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_test() {
+        let sources = [
+            "o.rg = FC.xy / r; o.b = t;",
+            "o.xyz = vec3(vec2(1, 2) * mat2(1, 2, 3, 4), 7 / 2);",
+            "float s = 0.;\n\
+             for (int i = 0; i < 10; i++) { if (i == 2) continue; if (i > 4) break; s += float(i); }\n\
+             int k = 0; while (true) { if (++k >= 3) break; }\n\
+             o.r = s; o.g = float(k); o.b = k == 3 && s > 7. ? mix(1., 3., .5) : -1.;",
+            "vec2 i; for (; i.y++ < 8.;) o.x += i.y; o.y = i.y;",
+            "o = vec4(1); return; o = vec4(2);",
+            "float a = 1.; o.x = a + (a = 2.); o.yz = vec2(a--, a); o.w = 0.;",
+            "vec2 p = FC.xy / r.y; o.rgb = vec3(length(p), atan(p.y, p.x), smoothstep(0., 1., p.x));",
+            // stores after branches, and side effects left of a comma
+            "bool b = t > 0.; float x = 1., y = 2.; float a = b ? x : y; o.r = a;",
+            "bool a = t < 0., b = true; bool c = true; c = a && b; o.r = c ? 1. : 0.;",
+            "float a; o.x = (a = 3., 2.); o.y = a;",
+        ];
+        for src in sources {
+            let program = Program::parse(src).unwrap();
+            let bytecode = Bytecode::compile(&program);
+            for fc in [[3., 1.], [0.5, 1.5]] {
+                let expected = interp::run(&program, fc, [4., 2.], 0.5);
+                assert_eq!(bytecode.run(fc, [4., 2.], 0.5), expected, "{}", src);
+            }
+        }
+        // folded to one constant and one move
+        let folded =
+            Bytecode::compile(&Program::parse("o = vec4(sin(1.) * 2., vec2(3).yx, -1);").unwrap());
+        assert_eq!(folded.len(), 1);
+        let root = env!("CARGO_MANIFEST_DIR");
+        let src = std::fs::read_to_string(format!("{}/shaders/swirl.glsl", root)).unwrap();
+        let swirl = Program::parse(&src).unwrap();
+        let bytecode = Bytecode::compile(&swirl);
+        for fc in [[3., 4.], [16., 9.], [30., 1.]] {
+            assert_eq!(
+                bytecode.run(fc, [32., 18.], 0.3),
+                interp::run(&swirl, fc, [32., 18.], 0.3)
+            );
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::quaternion::Quat;
+use crate::scene::{NodeId, Scene};
+use crate::transform::Transform;
+use crate::vector::{V3f64, Vector};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Easing {
+    /// holds the value until the next keyframe
+    Step,
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// control points `(x1, y1, x2, y2)` as in CSS `cubic-bezier`
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    fn bezier(p1: f64, p2: f64, t: f64) -> f64 {
+        let u = 1. - t;
+        3. * u * u * t * p1 + 3. * u * t * t * p2 + t * t * t
+    }
+
+    fn bezier_slope(p1: f64, p2: f64, t: f64) -> f64 {
+        let u = 1. - t;
+        3. * u * u * p1 + 6. * u * t * (p2 - p1) + 3. * t * t * (1. - p2)
+    }
+
+    /// # Params
+    /// `t` is the progress within the segment in `[0, 1]`
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        match *self {
+            Self::Step => 0.,
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1. - (1. - t).powi(3),
+            Self::EaseInOut => t * t * (3. - 2. * t),
+            Self::CubicBezier(x1, y1, x2, y2) => {
+                // solve x(s) = t with Newton, fall back to bisection
+                let mut s = t;
+                for _ in 0..8 {
+                    let dx = Self::bezier(x1, x2, s) - t;
+                    let slope = Self::bezier_slope(x1, x2, s);
+                    if dx.abs() < 1e-9 || slope.abs() < 1e-9 {
+                        break;
+                    }
+                    s = (s - dx / slope).clamp(0., 1.);
+                }
+                if (Self::bezier(x1, x2, s) - t).abs() > 1e-6 {
+                    let (mut lo, mut hi) = (0., 1.);
+                    for _ in 0..50 {
+                        s = (lo + hi) / 2.;
+                        if Self::bezier(x1, x2, s) < t {
+                            lo = s;
+                        } else {
+                            hi = s;
+                        }
+                    }
+                }
+                Self::bezier(y1, y2, s)
+            }
+        }
+    }
+}
+
+pub trait Interpolate: Copy {
+    fn interpolate(&self, rhs: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, rhs: &Self, t: f64) -> Self {
+        self + (rhs - self) * t
+    }
+}
+
+impl Interpolate for V3f64 {
+    fn interpolate(&self, rhs: &Self, t: f64) -> Self {
+        self.addv(&rhs.subv(self).muls(t))
+    }
+}
+
+impl Interpolate for Quat {
+    fn interpolate(&self, rhs: &Self, t: f64) -> Self {
+        self.slerp(rhs, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe<T> {
+    /// seconds
+    pub time: f64,
+    pub value: T,
+    /// easing of the segment starting at this keyframe
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f64, value: T, easing: Easing) -> Self {
+        Self {
+            time,
+            value,
+            easing,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps keyframes sorted by time, replacing one at the same time
+    pub fn key(mut self, time: f64, value: T, easing: Easing) -> Self {
+        let key = Keyframe::new(time, value, easing);
+        match self.keys.binary_search_by(|k| k.time.total_cmp(&time)) {
+            Ok(i) => self.keys[i] = key,
+            Err(i) => self.keys.insert(i, key),
+        }
+        self
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.keys.last().map_or(0., |k| k.time)
+    }
+
+    /// # Returns
+    /// `None` for an empty track, values outside the keyframes are held
+    pub fn sample(&self, time: f64) -> Option<T> {
+        let first = self.keys.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        let i = self.keys.partition_point(|k| k.time <= time);
+        let Some(next) = self.keys.get(i) else {
+            return self.keys.last().map(|k| k.value);
+        };
+        let prev = &self.keys[i - 1];
+        let t = (time - prev.time) / (next.time - prev.time);
+        Some(prev.value.interpolate(&next.value, prev.easing.apply(t)))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Animation {
+    pub translation: Track<V3f64>,
+    pub rotation: Track<Quat>,
+    pub scale: Track<V3f64>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.translation
+            .duration()
+            .max(self.rotation.duration())
+            .max(self.scale.duration())
+    }
+
+    /// # Returns
+    /// `translate * rotate * scale`, empty tracks contribute the identity
+    pub fn sample(&self, time: f64) -> Transform {
+        let t = self.translation.sample(time).unwrap_or([0.; 3]);
+        let r = self.rotation.sample(time).unwrap_or_default();
+        let s = self.scale.sample(time).unwrap_or([1.; 3]);
+        Transform::e().zoom(s).quat(&r).translate(t)
+    }
+}
+
+#[derive(Default)]
+pub struct Timeline {
+    channels: Vec<(NodeId, Animation)>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind(&mut self, id: NodeId, animation: Animation) {
+        self.channels.push((id, animation));
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.channels
+            .iter()
+            .map(|(_, a)| a.duration())
+            .fold(0., f64::max)
+    }
+
+    /// Overwrites the local transform of every bound node, call `Scene::update` afterwards
+    pub fn apply(&self, scene: &mut Scene, time: f64) {
+        for (id, animation) in self.channels.iter() {
+            scene.node_mut(*id).local = animation.sample(time);
+        }
+    }
+
+    /// # Returns
+    /// sample times of every frame at `fps`, covering `[0, duration)`
+    pub fn frames(&self, fps: usize) -> impl Iterator<Item = f64> + use<> {
+        assert!(fps != 0);
+        let n = (self.duration() * fps as f64).round() as usize;
+        (0..n.max(1)).map(move |i| i as f64 / fps as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_test() {
+        let track = Track::new()
+            .key(0., 0., Easing::Linear)
+            .key(1., 10., Easing::Step)
+            .key(2., 20., Easing::EaseInOut)
+            .key(3., 30., Easing::Linear);
+        assert_eq!(track.sample(-1.), Some(0.));
+        assert_eq!(track.sample(0.5), Some(5.));
+        assert_eq!(track.sample(1.5), Some(10.));
+        assert_eq!(track.sample(2.5), Some(25.));
+        assert_eq!(track.sample(4.), Some(30.));
+        let ease = Easing::CubicBezier(0.42, 0., 0.58, 1.);
+        assert!((ease.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!(ease.apply(0.25) < 0.25);
+        let q = Quat::identity().interpolate(&Quat::ry_rad(1.), 0.5);
+        assert!(q.dot(&Quat::ry_rad(0.5)) > 1. - 1e-9);
+    }
+}
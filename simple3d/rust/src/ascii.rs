@@ -0,0 +1,167 @@
+//! # ASCII Art
+//! Every character covers a block of pixels about twice as tall as wide,
+//! picked from a ramp by the block's luminance or, on strong edges, by the edge direction.
+
+use crate::canvas::Canvas;
+
+/// Sparse to dense
+pub const RAMP: &str = " .:-=+*#%@";
+
+pub struct Ascii {
+    pub cols: usize,
+    /// characters from the darkest to the brightest luminance
+    pub ramp: Vec<char>,
+    /// dark pixels get the dense characters, for text on a light background
+    pub invert: bool,
+    /// Sobel magnitude of the luminance above which `| / - \` trace the edge
+    pub edges: Option<f64>,
+}
+
+impl Default for Ascii {
+    fn default() -> Self {
+        Self {
+            cols: 80,
+            ramp: RAMP.chars().collect(),
+            invert: false,
+            edges: None,
+        }
+    }
+}
+
+/// Rec. 709 luma in `[0, 1]`
+fn luminance([r, g, b]: [u8; 3]) -> f64 {
+    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64) / 255.
+}
+
+impl Ascii {
+    pub fn new(cols: usize) -> Self {
+        Self {
+            cols,
+            ..Default::default()
+        }
+    }
+
+    /// Luminance per character cell, row-major
+    fn cells(&self, canvas: &Canvas) -> (usize, usize, Vec<f64>) {
+        let w = canvas.width();
+        let h = canvas.height();
+        if w == 0 || h == 0 {
+            return (0, 0, Vec::new());
+        }
+        let cols = self.cols.clamp(1, w);
+        let rows = ((h * cols) as f64 / w as f64 / 2.)
+            .round()
+            .clamp(1., h as f64) as usize;
+        let bounds = |i: usize, n: usize, len: usize| {
+            (i * len / n, ((i + 1) * len / n).max(i * len / n + 1))
+        };
+        let mut ls = Vec::with_capacity(cols * rows);
+        for j in 0..rows {
+            let (y0, y1) = bounds(j, rows, h);
+            for i in 0..cols {
+                let (x0, x1) = bounds(i, cols, w);
+                ls.push(luminance(canvas.average(x0, x1, y0, y1)));
+            }
+        }
+        (cols, rows, ls)
+    }
+
+    fn edge(
+        ls: &[f64],
+        cols: usize,
+        rows: usize,
+        i: usize,
+        j: usize,
+        threshold: f64,
+    ) -> Option<char> {
+        let l = |di: isize, dj: isize| {
+            let x = (i as isize + di).clamp(0, cols as isize - 1) as usize;
+            let y = (j as isize + dj).clamp(0, rows as isize - 1) as usize;
+            ls[y * cols + x]
+        };
+        let gx = l(1, -1) + 2. * l(1, 0) + l(1, 1) - l(-1, -1) - 2. * l(-1, 0) - l(-1, 1);
+        let gy = l(-1, 1) + 2. * l(0, 1) + l(1, 1) - l(-1, -1) - 2. * l(0, -1) - l(1, -1);
+        if gx.hypot(gy) <= threshold {
+            return None;
+        }
+        // the edge runs perpendicular to the gradient, y points down
+        let a = gy.atan2(gx).rem_euclid(std::f64::consts::PI) / std::f64::consts::FRAC_PI_8;
+        Some(match a {
+            a if !(1. ..7.).contains(&a) => '|',
+            a if a < 3. => '/',
+            a if a < 5. => '-',
+            _ => '\\',
+        })
+    }
+
+    /// # Returns
+    /// one line per character row, each ending with a new line
+    pub fn render(&self, canvas: &Canvas) -> String {
+        let (cols, rows, ls) = self.cells(canvas);
+        let n = self.ramp.len();
+        let mut s = String::with_capacity((cols + 1) * rows);
+        for j in 0..rows {
+            for i in 0..cols {
+                let edge = self
+                    .edges
+                    .and_then(|threshold| Self::edge(&ls, cols, rows, i, j, threshold));
+                let c = edge.unwrap_or_else(|| {
+                    let l = ls[j * cols + i];
+                    let l = if self.invert { 1. - l } else { l };
+                    self.ramp[((l * n as f64) as usize).min(n - 1)]
+                });
+                s.push(c);
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+/// Writes `text` to `output/{file_name}.txt`
+pub fn output_txt(text: &str, file_name: &str) {
+    use std::env;
+    use std::fs;
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let output_dir = root + "/output";
+    if !fs::exists(&output_dir).unwrap() {
+        fs::create_dir_all(&output_dir).unwrap();
+    }
+    let output_path = format!("{}/{}.txt", output_dir, file_name);
+    fs::write(&output_path, text).unwrap();
+    println!("Generated {}", output_path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_test() {
+        let mut canvas = Canvas::new(20, 4, [0x00; 3]);
+        for y in 0..4 {
+            for x in 0..20 {
+                let c = (x / 2 * 0xFF / 9) as u8;
+                *canvas.pixel_mut(x, y).unwrap() = [c; 3];
+            }
+        }
+        let ascii = Ascii::new(10);
+        assert_eq!(ascii.render(&canvas), " .:-=+*#%@\n");
+        let inverted = Ascii {
+            invert: true,
+            ..Ascii::new(10)
+        };
+        assert_eq!(inverted.render(&canvas), "@%#*+=-:. \n");
+        let mut canvas = Canvas::new(8, 8, [0x00; 3]);
+        for y in 0..8 {
+            for x in 4..8 {
+                *canvas.pixel_mut(x, y).unwrap() = [0xFF; 3];
+            }
+        }
+        let edges = Ascii {
+            edges: Some(0.5),
+            ..Ascii::new(4)
+        };
+        assert_eq!(edges.render(&canvas), " ||@\n ||@\n");
+    }
+}
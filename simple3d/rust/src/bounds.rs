@@ -0,0 +1,159 @@
+use crate::camera::Camera;
+use crate::vector::{V3f64, Vector};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: V3f64,
+    pub max: V3f64,
+}
+
+impl Aabb {
+    /// # Returns
+    /// an inverted box containing nothing when `vs` is empty
+    pub fn from_points(vs: &[V3f64]) -> Self {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for v in vs {
+            for i in 0..3 {
+                min[i] = min[i].min(v[i]);
+                max[i] = max[i].max(v[i]);
+            }
+        }
+        Self { min, max }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] > self.max[i])
+    }
+
+    pub fn center(&self) -> V3f64 {
+        self.min.addv(&self.max).divs(2.)
+    }
+
+    pub fn size(&self) -> V3f64 {
+        self.max.subv(&self.min)
+    }
+
+    pub fn union(&self, rhs: &Self) -> Self {
+        Self {
+            min: std::array::from_fn(|i| self.min[i].min(rhs.min[i])),
+            max: std::array::from_fn(|i| self.max[i].max(rhs.max[i])),
+        }
+    }
+
+    pub fn contains(&self, p: V3f64) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoundingSphere {
+    pub center: V3f64,
+    pub radius: f64,
+}
+
+impl BoundingSphere {
+    /// # Returns
+    /// sphere around the center of the `aabb` of `vs`, not the minimal one
+    pub fn from_points(vs: &[V3f64], aabb: &Aabb) -> Self {
+        let center = aabb.center();
+        let radius = vs
+            .iter()
+            .map(|v| v.subv(&center).magnitude())
+            .fold(0., f64::max);
+        Self { center, radius }
+    }
+}
+
+/// Points with `n · p + d >= 0` are inside
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub n: V3f64,
+    pub d: f64,
+}
+
+impl Plane {
+    pub fn new(n: V3f64, p: V3f64) -> Self {
+        let n = n.normalized();
+        Self { n, d: -n.dot(&p) }
+    }
+
+    #[inline]
+    pub fn distance(&self, p: V3f64) -> f64 {
+        self.n.dot(&p) + self.d
+    }
+}
+
+/// # View Frustum
+/// left, right, bottom, top and near planes in world space, there is no far plane
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub planes: [Plane; 5],
+}
+
+impl Frustum {
+    /// # Params
+    /// `w` and `h` give the aspect ratio the same way `project::screen` does
+    pub fn new(camera: &Camera, w: usize, h: usize, near: f64) -> Self {
+        let [r, u, f] = camera.basis();
+        let w = w as f64;
+        let h = h as f64;
+        let tx = w.max(h) / h / camera.focal();
+        let ty = h.max(w) / w / camera.focal();
+        let o = camera.position;
+        let world = |[x, y, z]: V3f64| r.muls(x).addv(&u.muls(y)).addv(&f.muls(z));
+        Self {
+            planes: [
+                Plane::new(world([1., 0., tx]), o),
+                Plane::new(world([-1., 0., tx]), o),
+                Plane::new(world([0., 1., ty]), o),
+                Plane::new(world([0., -1., ty]), o),
+                Plane::new(f, o.addv(&f.muls(near))),
+            ],
+        }
+    }
+
+    /// `false` only when the sphere is entirely outside
+    pub fn sphere(&self, s: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.distance(s.center) >= -s.radius)
+    }
+
+    /// `false` only when the box is entirely outside one plane
+    pub fn aabb(&self, b: &Aabb) -> bool {
+        !b.is_empty()
+            && self.planes.iter().all(|p| {
+                let v = std::array::from_fn(|i| if p.n[i] >= 0. { b.max[i] } else { b.min[i] });
+                p.distance(v) >= 0.
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frustum_test() {
+        let vs = [[-1., -1., 4.], [1., 2., 6.]];
+        let aabb = Aabb::from_points(&vs);
+        assert_eq!(aabb.center(), [0., 0.5, 5.]);
+        let sphere = BoundingSphere::from_points(&vs, &aabb);
+        let camera = Camera::default();
+        let frustum = Frustum::new(&camera, 16, 9, 1e-2);
+        assert!(frustum.aabb(&aabb));
+        assert!(frustum.sphere(&sphere));
+        let behind = Aabb::from_points(&[[-1., -1., -4.], [1., 1., -2.]]);
+        assert!(!frustum.aabb(&behind));
+        // visible horizontally at a 16:9 aspect, but not vertically
+        let side = Aabb::from_points(&[[1.5, 0., 1.], [1.6, 0.1, 1.]]);
+        assert!(frustum.aabb(&side));
+        let above = Aabb::from_points(&[[0., 1.5, 1.], [0.1, 1.6, 1.]]);
+        assert!(!frustum.aabb(&above));
+        let camera = Camera::new([0., 0., 10.], [0., 0., 0.]);
+        let frustum = Frustum::new(&camera, 16, 9, 1e-2);
+        assert!(frustum.aabb(&aabb));
+        assert!(!frustum.aabb(&Aabb::from_points(&[[0., 0., 11.]])));
+    }
+}
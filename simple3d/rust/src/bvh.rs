@@ -0,0 +1,187 @@
+use crate::bounds::Aabb;
+use crate::object::Object;
+use crate::ray::{Hit, Ray};
+use crate::vector::{V3f64, Vector};
+
+/// Triangles per leaf at most
+const LEAF_SIZE: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+struct Triangle {
+    ps: [V3f64; 3],
+    object: usize,
+    face: usize,
+    triangle: [usize; 3],
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Leaf { start: usize, len: usize },
+    Inner { left: usize, right: usize },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    aabb: Aabb,
+    kind: Kind,
+}
+
+/// # Bounding Volume Hierarchy
+/// over the triangles of a set of objects, split at the centroid median of the longest axis
+pub struct Bvh {
+    nodes: Vec<Node>,
+    tris: Vec<Triangle>,
+}
+
+impl Bvh {
+    pub fn new<'a>(objects: impl IntoIterator<Item = &'a Object>) -> Self {
+        let mut tris = Vec::new();
+        for (object, obj) in objects.into_iter().enumerate() {
+            let vs = obj.vertices();
+            for (face, triangle) in obj.face_triangles() {
+                tris.push(Triangle {
+                    ps: triangle.map(|i| vs[i]),
+                    object,
+                    face,
+                    triangle,
+                });
+            }
+        }
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * tris.len() / LEAF_SIZE + 1),
+            tris,
+        };
+        if !bvh.tris.is_empty() {
+            bvh.build(0, bvh.tris.len());
+        }
+        bvh
+    }
+
+    fn centroid(t: &Triangle) -> V3f64 {
+        t.ps[0].addv(&t.ps[1]).addv(&t.ps[2]).divs(3.)
+    }
+
+    /// # Returns
+    /// index of the node built for `tris[start..start + len]`
+    fn build(&mut self, start: usize, len: usize) -> usize {
+        let tris = &mut self.tris[start..start + len];
+        let aabb = tris
+            .iter()
+            .map(|t| Aabb::from_points(&t.ps))
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            aabb,
+            kind: Kind::Leaf { start, len },
+        });
+        if len <= LEAF_SIZE {
+            return id;
+        }
+        let cs = tris.iter().map(Self::centroid).collect::<Vec<_>>();
+        let size = Aabb::from_points(&cs).size();
+        let axis = (0..3).fold(0, |a, i| if size[i] > size[a] { i } else { a });
+        let mid = len / 2;
+        tris.select_nth_unstable_by(mid, |a, b| {
+            Self::centroid(a)[axis].total_cmp(&Self::centroid(b)[axis])
+        });
+        let left = self.build(start, mid);
+        let right = self.build(start + mid, len - mid);
+        self.nodes[id].kind = Kind::Inner { left, right };
+        id
+    }
+
+    /// Closest hit within `max` distance, `Hit::object` indexes the objects given to `new`
+    pub fn closest(&self, ray: &Ray, max: f64) -> Option<Hit> {
+        let mut best = None;
+        self.traverse(ray, max, |tri, max| {
+            if let Some((distance, bary)) = ray.triangle(tri.ps)
+                && distance < *max
+            {
+                *max = distance;
+                best = Some(Hit {
+                    object: tri.object,
+                    face: tri.face,
+                    triangle: tri.triangle,
+                    distance,
+                    bary,
+                });
+            }
+            false
+        });
+        best
+    }
+
+    /// `true` when anything lies on `ray` closer than `max`
+    pub fn occluded(&self, ray: &Ray, max: f64) -> bool {
+        let mut found = false;
+        self.traverse(ray, max, |tri, max| {
+            found = ray.triangle(tri.ps).is_some_and(|(t, _)| t < *max);
+            found
+        });
+        found
+    }
+
+    /// Calls `f` on triangles in boxes the ray enters before `max`,
+    /// `f` may shrink `max` and stops the traversal by returning `true`
+    fn traverse(&self, ray: &Ray, mut max: f64, mut f: impl FnMut(&Triangle, &mut f64) -> bool) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            match ray.aabb(&node.aabb) {
+                Some((t0, _)) if t0 <= max => {}
+                _ => continue,
+            }
+            match node.kind {
+                Kind::Leaf { start, len } => {
+                    for tri in &self.tris[start..start + len] {
+                        if f(tri, &mut max) {
+                            return;
+                        }
+                    }
+                }
+                Kind::Inner { left, right } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive;
+    use crate::transform::Transform;
+
+    #[test]
+    fn closest_test() {
+        let objects = (0..8)
+            .map(|i| {
+                let mut s = primitive::sphere(0.4, 12, 6);
+                let i = i as f64;
+                s.transform(&Transform::e().translate([i - 4., (i * 1.7).sin(), 5. + i % 3.]));
+                s
+            })
+            .collect::<Vec<_>>();
+        let bvh = Bvh::new(&objects);
+        for i in 0..100 {
+            let a = i as f64 * 0.05;
+            let ray = Ray::new([0., 0., -1.], [a.sin() * 0.8, a.cos() * 0.2, 1.]);
+            let brute = ray.pick(&objects);
+            let hit = bvh.closest(&ray, f64::INFINITY);
+            assert_eq!(
+                brute.map(|h| (h.object, h.face)),
+                hit.map(|h| (h.object, h.face))
+            );
+            if let Some(h) = hit {
+                assert!(bvh.occluded(&ray, h.distance + 1e-6));
+                assert!(!bvh.occluded(&ray, h.distance - 1e-6));
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::project::{persp, screen, unpersp, unscreen};
+use crate::ray::Ray;
+use crate::transform::Transform;
+use crate::vector::{V2f64, V3f64, Vector, Vector3};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub position: V3f64,
+    pub target: V3f64,
+    pub up: V3f64,
+    /// vertical field of view in radians, measured across the shorter screen side
+    pub fov: f64,
+}
+
+impl Default for Camera {
+    /// The camera `project::persp` assumes: at the origin, looking at `+z`, 90° field of view
+    fn default() -> Self {
+        Self {
+            position: [0.; 3],
+            target: [0., 0., 1.],
+            up: [0., 1., 0.],
+            fov: 90_f64.to_radians(),
+        }
+    }
+}
+
+impl Camera {
+    pub fn new(position: V3f64, target: V3f64) -> Self {
+        Self {
+            position,
+            target,
+            ..Self::default()
+        }
+    }
+
+    /// # Returns
+    /// `[right, up, front]` unit vectors in world space
+    pub fn basis(&self) -> [V3f64; 3] {
+        let f = self.target.subv(&self.position).normalized();
+        let r = self.up.cross(&f).normalized();
+        let u = f.cross(&r);
+        [r, u, f]
+    }
+
+    /// # View Transform
+    /// world space to camera space, where the camera looks at `+z` with `+y` up
+    pub fn view(&self) -> Transform {
+        let [r, u, f] = self.basis();
+        let p = self.position;
+        Transform::new([
+            [r[0], r[1], r[2], -r.dot(&p)],
+            [u[0], u[1], u[2], -u.dot(&p)],
+            [f[0], f[1], f[2], -f.dot(&p)],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Scale from `persp` coordinates to the normalized `[-1, 1]` range
+    #[inline]
+    pub fn focal(&self) -> f64 {
+        1. / (self.fov / 2.).tan()
+    }
+
+    /// # Params
+    /// `p` in camera space
+    pub fn screen(&self, p: V3f64, w: usize, h: usize) -> V2f64 {
+        screen(persp(p).muls(self.focal()), w, h)
+    }
+
+    /// # Unprojection
+    /// world-space ray from the camera through the screen point `p`, e.g. a pixel center
+    pub fn ray(&self, p: V2f64, w: usize, h: usize) -> Ray {
+        let [r, u, f] = self.basis();
+        let [x, y, z] = unpersp(unscreen(p, w, h).divs(self.focal()), 1.);
+        let d = r.muls(x).addv(&u.muls(y)).addv(&f.muls(z));
+        Ray::new(self.position, d)
+    }
+}
@@ -1,4 +1,8 @@
-use crate::vector::{Convert, V2f64, V2i32, Vector};
+use crate::color;
+use crate::draw::DrawTarget;
+use crate::texture::Texture;
+use crate::vector::{Convert, V2f64, V2i32, V3f64, Vector};
+use common::filter::{self, Filter};
 
 #[derive(Clone, Copy)]
 pub struct Point {
@@ -15,13 +19,11 @@ impl Point {
         let Self { p, d } = *self;
         let d = d as i32;
         let r = d / 2;
-        (-r..(-r + d))
-            .map(move |i| {
-                (-r..(-r + d))
-                    .filter(move |&j| [i, j].magnitude() <= r)
-                    .map(move |j| [i, j].addv(&p))
-            })
-            .flatten()
+        (-r..(-r + d)).flat_map(move |i| {
+            (-r..(-r + d))
+                .filter(move |&j| [i, j].magnitude() <= r)
+                .map(move |j| [i, j].addv(&p))
+        })
     }
 }
 
@@ -190,10 +192,144 @@ impl Iterator for DrawLine {
     }
 }
 
+#[derive(Clone, Copy)]
+pub struct Triangle {
+    pub p1: V2f64,
+    pub p2: V2f64,
+    pub p3: V2f64,
+}
+
+impl Triangle {
+    pub fn new(p1: V2f64, p2: V2f64, p3: V2f64) -> Self {
+        Self { p1, p2, p3 }
+    }
+
+    fn edge(a: V2f64, b: V2f64, c: V2f64) -> f64 {
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+    }
+
+    /// Screen-space barycentric coordinates of `c`, negative outside
+    pub fn barycentric(&self, c: V2f64) -> V3f64 {
+        let Self { p1, p2, p3 } = *self;
+        let area = Self::edge(p1, p2, p3);
+        [
+            Self::edge(p2, p3, c) / area,
+            Self::edge(p3, p1, c) / area,
+            Self::edge(p1, p2, c) / area,
+        ]
+    }
+
+    /// # Returns
+    /// the pixel rows and columns around the triangle inside `[xmin, xmax] x [ymin, ymax]`,
+    /// no rows when the triangle has no area
+    fn bounds(&self, xmin: i32, xmax: i32, ymin: i32, ymax: i32) -> ([i32; 2], [i32; 2]) {
+        let Self { p1, p2, p3 } = *self;
+        if Self::edge(p1, p2, p3).abs() < 1e-12 {
+            return ([0, -1], [0, -1]);
+        }
+        let ps = [p1, p2, p3];
+        let min = |i: usize| {
+            ps.iter()
+                .map(|p| p[i])
+                .fold(f64::INFINITY, f64::min)
+                .floor() as i32
+        };
+        let max = |i: usize| {
+            ps.iter()
+                .map(|p| p[i])
+                .fold(f64::NEG_INFINITY, f64::max)
+                .ceil() as i32
+        };
+        (
+            [min(0).max(xmin), max(0).min(xmax)],
+            [min(1).max(ymin), max(1).min(ymax)],
+        )
+    }
+
+    /// # Coverage Mask
+    /// bit `i` is set when the sample at `offsets[i]` inside the pixel is covered,
+    /// pixels without covered samples are skipped
+    pub fn coverage<'a>(
+        &self,
+        offsets: &'a [V2f64],
+        xmin: i32,
+        xmax: i32,
+        ymin: i32,
+        ymax: i32,
+    ) -> impl Iterator<Item = (V2i32, u32)> + 'a {
+        let tri = *self;
+        let ([x0, x1], [y0, y1]) = self.bounds(xmin, xmax, ymin, ymax);
+        (y0..=y1).flat_map(move |y| {
+            (x0..=x1).filter_map(move |x| {
+                let mask = offsets.iter().enumerate().fold(0, |mask, (i, o)| {
+                    let b = tri.barycentric([x as f64 + o[0], y as f64 + o[1]]);
+                    if b.iter().all(|&b| b >= 0.) {
+                        mask | 1 << i
+                    } else {
+                        mask
+                    }
+                });
+                (mask != 0).then_some(([x, y], mask))
+            })
+        })
+    }
+
+    /// # Returns
+    /// covered pixels with the screen-space barycentric coordinates of their centers
+    pub fn draw(
+        &self,
+        xmin: i32,
+        xmax: i32,
+        ymin: i32,
+        ymax: i32,
+    ) -> impl Iterator<Item = (V2i32, V3f64)> {
+        let tri = *self;
+        let ([x0, x1], [y0, y1]) = self.bounds(xmin, xmax, ymin, ymax);
+        (y0..=y1).flat_map(move |y| {
+            (x0..=x1).filter_map(move |x| {
+                let b = tri.barycentric([x as f64 + 0.5, y as f64 + 0.5]);
+                b.iter().all(|&b| b >= 0.).then_some(([x, y], b))
+            })
+        })
+    }
+}
+
+/// # MSAA Sample Positions
+/// inside a pixel, `[0, 0]` is the top-left corner
+pub fn msaa_pattern(samples: usize) -> &'static [V2f64] {
+    const D: f64 = 1. / 16.;
+    match samples {
+        1 => &[[0.5, 0.5]],
+        2 => &[[0.75, 0.75], [0.25, 0.25]],
+        4 => &[
+            [0.375, 0.125],
+            [0.875, 0.375],
+            [0.125, 0.625],
+            [0.625, 0.875],
+        ],
+        8 => &[
+            [0.5 + D, 0.5 - 3. * D],
+            [0.5 - D, 0.5 + 3. * D],
+            [0.5 + 5. * D, 0.5 + D],
+            [0.5 - 3. * D, 0.5 - 5. * D],
+            [0.5 - 5. * D, 0.5 + 5. * D],
+            [0.5 - 7. * D, 0.5 - D],
+            [0.5 + 3. * D, 0.5 + 7. * D],
+            [0.5 + 7. * D, 0.5 - 7. * D],
+        ],
+        _ => panic!("unsupported msaa sample count {}", samples),
+    }
+}
+
 pub struct Canvas {
     w: usize,
     h: usize,
     vec: Vec<[u8; 3]>,
+    depth: Vec<f64>,
+    /// samples per pixel, `1` disables MSAA
+    msaa: usize,
+    samples: Vec<[u8; 3]>,
+    sample_depth: Vec<f64>,
 }
 
 impl Canvas {
@@ -202,11 +338,79 @@ impl Canvas {
             w,
             h,
             vec: vec![fill; w * h],
+            depth: vec![f64::INFINITY; w * h],
+            msaa: 1,
+            samples: Vec::new(),
+            sample_depth: Vec::new(),
+        }
+    }
+
+    /// # Multisample Anti-Aliasing
+    /// triangles and lines keep 2, 4 or 8 colors and depths per pixel and resolve to their mean,
+    /// triangles are still shaded once per pixel. Other drawing writes resolved pixels
+    /// directly, which later triangles or lines covering the same pixels replace.
+    pub fn with_msaa(mut self, samples: usize) -> Self {
+        msaa_pattern(samples);
+        self.msaa = samples;
+        if samples == 1 {
+            self.samples = Vec::new();
+            self.sample_depth = Vec::new();
+        } else {
+            self.samples = self
+                .vec
+                .iter()
+                .flat_map(|&c| std::iter::repeat_n(c, samples))
+                .collect();
+            self.sample_depth = self
+                .depth
+                .iter()
+                .flat_map(|&z| std::iter::repeat_n(z, samples))
+                .collect();
         }
+        self
+    }
+
+    /// Reads a frame written by `output_ppm`, or any `P6`/`P3` image
+    pub fn load_ppm(path: &str) -> Result<Self, String> {
+        let tex = Texture::load_ppm(path)?;
+        let w = tex.width();
+        let h = tex.height();
+        Ok(Self {
+            w,
+            h,
+            vec: tex.pixels().to_vec(),
+            depth: vec![f64::INFINITY; w * h],
+            msaa: 1,
+            samples: Vec::new(),
+            sample_depth: Vec::new(),
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.w
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.h
     }
 
+    /// Also resets the depth buffer
     pub fn fill(&mut self, color: [u8; 3]) {
         self.vec.fill(color);
+        self.depth.fill(f64::INFINITY);
+        self.samples.fill(color);
+        self.sample_depth.fill(f64::INFINITY);
+    }
+
+    /// Mean in linear light of the samples of pixel `i`, also the nearest sample depth
+    fn resolve(&mut self, i: usize) {
+        let n = self.msaa;
+        self.vec[i] = color::mean(&self.samples[i * n..(i + 1) * n]);
+        self.depth[i] = self.sample_depth[i * n..(i + 1) * n]
+            .iter()
+            .fold(f64::INFINITY, |m, &z| m.min(z));
     }
 
     pub fn dot(&mut self, p: V2i32, radius: usize, rgb: [u8; 3]) {
@@ -222,6 +426,10 @@ impl Canvas {
     }
 
     pub fn line(&mut self, p1: V2i32, p2: V2i32, width: usize, rgb: [u8; 3]) {
+        if self.msaa > 1 {
+            let center = |p: V2i32| [p[0] as f64 + 0.5, p[1] as f64 + 0.5];
+            return self.line_msaa(center(p1), center(p2), width, rgb);
+        }
         let Self { w, h, .. } = *self;
         let bound = width as i32 / 2 + 1;
         let xmin = -bound;
@@ -242,10 +450,135 @@ impl Canvas {
         }
     }
 
+    /// Samples within `width / 2` of the segment are covered, no depth test
+    fn line_msaa(&mut self, p1: V2f64, p2: V2f64, width: usize, rgb: [u8; 3]) {
+        let Self { w, h, msaa: n, .. } = *self;
+        let r = width as f64 / 2.;
+        let d = p2.subv(&p1);
+        let len2 = d.dot(&d);
+        let covered = |p: V2f64| {
+            let t = if len2 > 0. {
+                (p.subv(&p1).dot(&d) / len2).clamp(0., 1.)
+            } else {
+                0.
+            };
+            let q = p1.addv(&d.muls(t)).subv(&p);
+            q.dot(&q) <= r * r
+        };
+        let offsets = msaa_pattern(n);
+        let bound = r.ceil() as i32 + 1;
+        let floor = |p: V2f64| [p[0].floor() as i32, p[1].floor() as i32];
+        let (xmax, ymax) = (w as i32 + bound, h as i32 + bound);
+        for [px, py] in Line::new(floor(p1), floor(p2)).draw(-bound, xmax, -bound, ymax) {
+            for y in (py - bound).max(0)..(py + bound + 1).min(h as i32) {
+                for x in (px - bound).max(0)..(px + bound + 1).min(w as i32) {
+                    let i = y as usize * w + x as usize;
+                    let mut hit = false;
+                    for (k, o) in offsets.iter().enumerate() {
+                        if covered([x as f64 + o[0], y as f64 + o[1]]) {
+                            self.samples[i * n + k] = rgb;
+                            hit = true;
+                        }
+                    }
+                    if hit {
+                        self.resolve(i);
+                    }
+                }
+            }
+        }
+    }
+
+    /// # Params
+    /// `ps` are screen coordinates, `zs` the view-space depths of the vertices.
+    /// `shade` receives perspective-correct barycentric coordinates
+    /// and is only called for pixels passing the depth test.
+    pub fn triangle(
+        &mut self,
+        ps: [V2f64; 3],
+        zs: [f64; 3],
+        mut shade: impl FnMut(V3f64) -> [u8; 3],
+    ) {
+        let Self { w, h, .. } = *self;
+        let [p1, p2, p3] = ps;
+        let inv = zs.map(|z| 1. / z);
+        if self.msaa > 1 {
+            return self.triangle_msaa(ps, inv, shade);
+        }
+        for ([x, y], b) in Triangle::new(p1, p2, p3).draw(0, w as i32 - 1, 0, h as i32 - 1) {
+            let b = b.mulv(&inv);
+            let sum = b[0] + b[1] + b[2];
+            let z = 1. / sum;
+            let i = y as usize * w + x as usize;
+            if z < self.depth[i] {
+                self.depth[i] = z;
+                self.vec[i] = shade(b.muls(z));
+            }
+        }
+    }
+
+    /// Depth tested per sample, shaded once per pixel at the centroid of the covered samples
+    fn triangle_msaa(
+        &mut self,
+        [p1, p2, p3]: [V2f64; 3],
+        inv: [f64; 3],
+        mut shade: impl FnMut(V3f64) -> [u8; 3],
+    ) {
+        let Self { w, h, msaa: n, .. } = *self;
+        let tri = Triangle::new(p1, p2, p3);
+        let offsets = msaa_pattern(n);
+        let z = |b: V3f64| 1. / b.mulv(&inv).iter().sum::<f64>();
+        for ([x, y], mask) in tri.coverage(offsets, 0, w as i32 - 1, 0, h as i32 - 1) {
+            let i = y as usize * w + x as usize;
+            let mut passed = Vec::with_capacity(n);
+            let mut centroid = [0.; 2];
+            for (k, o) in offsets.iter().enumerate() {
+                if mask >> k & 1 == 0 {
+                    continue;
+                }
+                let p = [x as f64 + o[0], y as f64 + o[1]];
+                centroid = centroid.addv(&p);
+                let zs = z(tri.barycentric(p));
+                if zs < self.sample_depth[i * n + k] {
+                    passed.push((k, zs));
+                }
+            }
+            if passed.is_empty() {
+                continue;
+            }
+            let centroid = centroid.divs(mask.count_ones() as f64);
+            let b = tri.barycentric(centroid).mulv(&inv);
+            let rgb = shade(b.muls(z(tri.barycentric(centroid))));
+            for (k, zs) in passed {
+                self.samples[i * n + k] = rgb;
+                self.sample_depth[i * n + k] = zs;
+            }
+            self.resolve(i);
+        }
+    }
+
+    pub fn depth(&self, x: usize, y: usize) -> Option<f64> {
+        if x < self.w && y < self.h {
+            Some(self.depth[y * self.w + x])
+        } else {
+            None
+        }
+    }
+
+    /// Mean color in linear light of the pixels in `[x0, x1) x [y0, y1)`, which must be non-empty and inside
+    pub fn average(&self, x0: usize, x1: usize, y0: usize, y1: usize) -> [u8; 3] {
+        assert!(x0 < x1 && x1 <= self.w);
+        assert!(y0 < y1 && y1 <= self.h);
+        color::mean((y0..y1).flat_map(|y| &self.vec[y * self.w + x0..y * self.w + x1]))
+    }
+
+    /// # Safety
+    /// `x` must be less than the width and `y` less than the height
     pub unsafe fn pixel_unchecked(&self, x: usize, y: usize) -> &[u8; 3] {
         unsafe { self.vec.get_unchecked(y * self.w + x) }
     }
 
+    /// # Safety
+    /// `x` must be less than the width and `y` less than the height
     pub unsafe fn pixel_unchecked_mut(&mut self, x: usize, y: usize) -> &mut [u8; 3] {
         unsafe { self.vec.get_unchecked_mut(y * self.w + x) }
     }
@@ -266,6 +599,22 @@ impl Canvas {
         }
     }
 
+    /// Runs `chain` on the resolved pixels in linear light
+    pub fn filter(&mut self, chain: &[Filter]) {
+        if chain.is_empty() {
+            return;
+        }
+        let mut pixels = self
+            .vec
+            .iter()
+            .map(|&c| color::to_linear(c))
+            .collect::<Vec<_>>();
+        filter::apply(chain, self.w, self.h, &mut pixels);
+        for (c, rgb) in self.vec.iter_mut().zip(pixels) {
+            *c = color::from_linear(rgb);
+        }
+    }
+
     pub fn output_ppm(&self, file_name: &str) {
         use std::env;
         use std::fmt::Write as FmtWrite;
@@ -294,6 +643,153 @@ impl Canvas {
     }
 }
 
+/// # 3x5 Bitmap Font
+/// rows from top to bottom, bit 2 is the left column
+const GLYPHS: &str = " 0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ.,:-+/()=%!?'_";
+const FONT: [[u8; 5]; 51] = [
+    [0b000, 0b000, 0b000, 0b000, 0b000],
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b010, 0b101, 0b111, 0b101, 0b101],
+    [0b110, 0b101, 0b110, 0b101, 0b110],
+    [0b011, 0b100, 0b100, 0b100, 0b011],
+    [0b110, 0b101, 0b101, 0b101, 0b110],
+    [0b111, 0b100, 0b110, 0b100, 0b111],
+    [0b111, 0b100, 0b110, 0b100, 0b100],
+    [0b011, 0b100, 0b101, 0b101, 0b011],
+    [0b101, 0b101, 0b111, 0b101, 0b101],
+    [0b111, 0b010, 0b010, 0b010, 0b111],
+    [0b001, 0b001, 0b001, 0b101, 0b010],
+    [0b101, 0b101, 0b110, 0b101, 0b101],
+    [0b100, 0b100, 0b100, 0b100, 0b111],
+    [0b101, 0b111, 0b111, 0b101, 0b101],
+    [0b110, 0b101, 0b101, 0b101, 0b101],
+    [0b010, 0b101, 0b101, 0b101, 0b010],
+    [0b110, 0b101, 0b110, 0b100, 0b100],
+    [0b010, 0b101, 0b101, 0b110, 0b011],
+    [0b110, 0b101, 0b110, 0b101, 0b101],
+    [0b011, 0b100, 0b010, 0b001, 0b110],
+    [0b111, 0b010, 0b010, 0b010, 0b010],
+    [0b101, 0b101, 0b101, 0b101, 0b111],
+    [0b101, 0b101, 0b101, 0b101, 0b010],
+    [0b101, 0b101, 0b111, 0b111, 0b101],
+    [0b101, 0b101, 0b010, 0b101, 0b101],
+    [0b101, 0b101, 0b010, 0b010, 0b010],
+    [0b111, 0b001, 0b010, 0b100, 0b111],
+    [0b000, 0b000, 0b000, 0b000, 0b010],
+    [0b000, 0b000, 0b000, 0b010, 0b100],
+    [0b000, 0b010, 0b000, 0b010, 0b000],
+    [0b000, 0b000, 0b111, 0b000, 0b000],
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+    [0b001, 0b001, 0b010, 0b100, 0b100],
+    [0b001, 0b010, 0b010, 0b010, 0b001],
+    [0b100, 0b010, 0b010, 0b010, 0b100],
+    [0b000, 0b111, 0b000, 0b111, 0b000],
+    [0b101, 0b001, 0b010, 0b100, 0b101],
+    [0b010, 0b010, 0b010, 0b000, 0b010],
+    [0b110, 0b001, 0b010, 0b000, 0b010],
+    [0b010, 0b010, 0b000, 0b000, 0b000],
+    [0b000, 0b000, 0b000, 0b000, 0b111],
+];
+
+/// Unknown characters are drawn as `?`, lowercase as uppercase
+fn glyph(c: char) -> &'static [u8; 5] {
+    let c = c.to_ascii_uppercase();
+    let i = GLYPHS.find(c).unwrap_or(GLYPHS.find('?').unwrap());
+    &FONT[i]
+}
+
+impl DrawTarget for Canvas {
+    fn width(&self) -> usize {
+        self.w
+    }
+
+    fn height(&self) -> usize {
+        self.h
+    }
+
+    fn clear(&mut self, rgb: [u8; 3]) {
+        self.fill(rgb);
+    }
+
+    fn dot(&mut self, p: V2f64, size: usize, rgb: [u8; 3]) {
+        Canvas::dot(self, p.cvt(), size, rgb);
+    }
+
+    fn line(&mut self, p1: V2f64, p2: V2f64, width: usize, rgb: [u8; 3]) {
+        if self.msaa > 1 {
+            return self.line_msaa(p1, p2, width, rgb);
+        }
+        Canvas::line(self, p1.cvt(), p2.cvt(), width, rgb);
+    }
+
+    /// Scanlines through the pixel centers, no depth test
+    fn polygon(&mut self, ps: &[V2f64], rgb: [u8; 3]) {
+        let Self { w, h, .. } = *self;
+        let ymin = ps.iter().fold(f64::INFINITY, |m, p| m.min(p[1]));
+        let ymax = ps.iter().fold(f64::NEG_INFINITY, |m, p| m.max(p[1]));
+        if ps.len() < 3 || ymin >= h as f64 || ymax < 0. {
+            return;
+        }
+        let y0 = (ymin - 0.5).ceil().max(0.) as usize;
+        let y1 = ((ymax - 0.5).ceil().max(0.) as usize).min(h);
+        let mut xs = Vec::new();
+        for y in y0..y1 {
+            let yc = y as f64 + 0.5;
+            xs.clear();
+            for (i, &[xa, ya]) in ps.iter().enumerate() {
+                let [xb, yb] = ps[(i + 1) % ps.len()];
+                if (ya <= yc) != (yb <= yc) {
+                    xs.push(xa + (yc - ya) / (yb - ya) * (xb - xa));
+                }
+            }
+            xs.sort_by(f64::total_cmp);
+            for span in xs.chunks_exact(2) {
+                let x0 = (span[0] - 0.5).ceil().clamp(0., w as f64) as usize;
+                let x1 = (span[1] - 0.5).ceil().clamp(0., w as f64) as usize;
+                self.vec[y * w + x0..y * w + x1].fill(rgb);
+            }
+        }
+    }
+
+    /// Built-in 3x5 font scaled to whole pixels, `\n` starts a new line
+    fn text(&mut self, p: V2f64, size: usize, s: &str, rgb: [u8; 3]) {
+        let Self { w, h, .. } = *self;
+        let scale = (size / 6).max(1) as i32;
+        let [x0, y0]: V2i32 = p.cvt();
+        for (row, line) in s.lines().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                let ox = x0 + col as i32 * 4 * scale;
+                let oy = y0 + row as i32 * 6 * scale;
+                for (gy, bits) in glyph(c).iter().enumerate() {
+                    for gx in 0..3 {
+                        if bits >> (2 - gx) & 1 == 0 {
+                            continue;
+                        }
+                        for dy in 0..scale {
+                            for dx in 0..scale {
+                                let x = ox + gx * scale + dx;
+                                let y = oy + gy as i32 * scale + dy;
+                                if 0 <= x && x < w as i32 && 0 <= y && y < h as i32 {
+                                    self.vec[y as usize * w + x as usize] = rgb;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,4 +806,30 @@ mod tests {
         canvas.line(p1, p2, 7, red);
         canvas.output_ppm("line");
     }
+
+    #[test]
+    fn msaa_test() {
+        let white = [0xFF; 3];
+        let red = [0xFF, 0x00, 0x00];
+        let mut canvas = Canvas::new(8, 8, white).with_msaa(4);
+        let [a, b, c, d] = [[1., 1.], [7., 1.], [7., 7.], [1., 7.]];
+        canvas.triangle([a, b, c], [1.; 3], |_| red);
+        canvas.triangle([a, c, d], [1.; 3], |_| red);
+        for y in 1..7 {
+            for x in 1..7 {
+                assert_eq!(canvas.pixel(x, y), Some(&red));
+            }
+        }
+        assert_eq!(canvas.pixel(0, 0), Some(&white));
+        let mut canvas = Canvas::new(8, 8, white).with_msaa(4);
+        canvas.triangle([[0., 0.], [8., 0.], [0., 8.]], [1.; 3], |_| red);
+        let diagonal = canvas.pixel(3, 4).unwrap();
+        assert!(diagonal[1] > 0x00 && diagonal[1] < 0xFF);
+        canvas.triangle([[0., 0.], [8., 0.], [0., 8.]], [2.; 3], |_| [0x00; 3]);
+        assert_eq!(canvas.pixel(1, 1), Some(&red));
+        canvas.fill(white);
+        DrawTarget::line(&mut canvas, [0.5, 4.], [7.5, 4.], 2, red);
+        assert_eq!(canvas.pixel(4, 3), Some(&red));
+        assert_eq!(canvas.pixel(4, 1), Some(&white));
+    }
 }
@@ -0,0 +1,51 @@
+//! # Linear Light
+//! Canvas bytes are sRGB encoded, so averaging them directly darkens
+//! every edge and blend. Mixing is done on decoded linear values instead.
+
+use common::color::{srgb_decode, srgb_encode};
+use std::sync::OnceLock;
+
+pub type Rgb = common::color::Rgb;
+
+/// Decodes a byte through a lookup table
+pub fn to_linear(rgb: [u8; 3]) -> Rgb {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    let table = TABLE.get_or_init(|| std::array::from_fn(|i| srgb_decode(i as f32 / 255.)));
+    rgb.map(|c| table[c as usize])
+}
+
+/// Encodes and rounds to the nearest byte
+pub fn from_linear(rgb: Rgb) -> [u8; 3] {
+    rgb.map(|c| (srgb_encode(c.clamp(0., 1.)) * 255.).round() as u8)
+}
+
+/// # Returns
+/// the mean of `colors` in linear light, black if there are none
+pub fn mean<'a>(colors: impl IntoIterator<Item = &'a [u8; 3]>) -> [u8; 3] {
+    let mut sum = [0.; 3];
+    let mut n = 0;
+    for rgb in colors {
+        let c = to_linear(*rgb);
+        for k in 0..3 {
+            sum[k] += c[k];
+        }
+        n += 1;
+    }
+    from_linear(sum.map(|c| c / n.max(1) as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_test() {
+        for i in 0..=255 {
+            assert_eq!(from_linear(to_linear([i; 3])), [i; 3]);
+        }
+        // half covered white on black is brighter than the byte midpoint
+        assert_eq!(mean(&[[0xFF; 3], [0x00; 3]]), [0xBC; 3]);
+        assert_eq!(mean(&[[0xFF, 0x00, 0x80]; 3]), [0xFF, 0x00, 0x80]);
+        assert_eq!(mean(&[]), [0x00; 3]);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::vector::V2f64;
+
+/// # Drawing Backend
+/// 2D primitives in screen coordinates, origin at the top-left corner.
+/// Sizes and widths are in pixels, text is positioned by its top-left corner.
+pub trait DrawTarget {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn clear(&mut self, rgb: [u8; 3]);
+    /// `size` is the diameter
+    fn dot(&mut self, p: V2f64, size: usize, rgb: [u8; 3]);
+    fn line(&mut self, p1: V2f64, p2: V2f64, width: usize, rgb: [u8; 3]);
+    /// Filled with the even-odd rule
+    fn polygon(&mut self, ps: &[V2f64], rgb: [u8; 3]);
+    /// `size` is the height of a line of text
+    fn text(&mut self, p: V2f64, size: usize, s: &str, rgb: [u8; 3]);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Clear([u8; 3]),
+    Dot(V2f64, usize, [u8; 3]),
+    Line(V2f64, V2f64, usize, [u8; 3]),
+    Polygon(Vec<V2f64>, [u8; 3]),
+    Text(V2f64, usize, String, [u8; 3]),
+}
+
+/// # Command Recorder
+/// keeps every drawing call to replay it on other targets or save it as text, one command per line:
+/// ```text
+/// size W H
+/// clear R G B
+/// dot X Y SIZE R G B
+/// line X1 Y1 X2 Y2 WIDTH R G B
+/// polygon R G B X1 Y1 X2 Y2 ...
+/// text X Y SIZE R G B TEXT
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recorder {
+    w: usize,
+    h: usize,
+    commands: Vec<Command>,
+}
+
+impl Recorder {
+    pub fn new(w: usize, h: usize) -> Self {
+        Self {
+            w,
+            h,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn replay(&self, target: &mut impl DrawTarget) {
+        for command in &self.commands {
+            match command {
+                Command::Clear(rgb) => target.clear(*rgb),
+                Command::Dot(p, size, rgb) => target.dot(*p, *size, *rgb),
+                Command::Line(p1, p2, width, rgb) => target.line(*p1, *p2, *width, *rgb),
+                Command::Polygon(ps, rgb) => target.polygon(ps, *rgb),
+                Command::Text(p, size, s, rgb) => target.text(*p, *size, s, *rgb),
+            }
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let rgb = |[r, g, b]: [u8; 3]| format!("{} {} {}", r, g, b);
+        let mut s = format!("size {} {}\n", self.w, self.h);
+        for command in &self.commands {
+            let line = match command {
+                Command::Clear(c) => format!("clear {}", rgb(*c)),
+                Command::Dot([x, y], size, c) => format!("dot {} {} {} {}", x, y, size, rgb(*c)),
+                Command::Line([x1, y1], [x2, y2], width, c) => {
+                    format!("line {} {} {} {} {} {}", x1, y1, x2, y2, width, rgb(*c))
+                }
+                Command::Polygon(ps, c) => {
+                    ps.iter().fold(format!("polygon {}", rgb(*c)), |s, [x, y]| {
+                        format!("{} {} {}", s, x, y)
+                    })
+                }
+                Command::Text([x, y], size, text, c) => {
+                    format!("text {} {} {} {} {}", x, y, size, rgb(*c), text)
+                }
+            };
+            s += &line;
+            s += "\n";
+        }
+        s
+    }
+
+    /// # Returns
+    /// errors name the 1-based line number
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut recorder = None;
+        for (i, line) in src.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let err = |msg: String| format!("{}: {}", i + 1, msg);
+            let head = line.split(' ').next().unwrap_or_default();
+            // the text itself is the rest of the line, spaces included
+            let fields = if head == "text" {
+                line.splitn(8, ' ').skip(1).collect::<Vec<_>>()
+            } else {
+                line.split_whitespace().skip(1).collect::<Vec<_>>()
+            };
+            let count = match head {
+                "size" => 2,
+                "clear" => 3,
+                "dot" => 6,
+                "line" => 8,
+                "text" => 6,
+                "polygon" if fields.len() >= 3 && fields.len() % 2 == 1 => fields.len(),
+                "polygon" => return Err(err("expected a color and pairs of coordinates".into())),
+                _ => return Err(err(format!("unknown command `{}`", head))),
+            };
+            let text = (head == "text").then(|| fields.get(6).copied().unwrap_or_default());
+            if fields.len() < count || (text.is_none() && fields.len() > count) {
+                return Err(err(format!("`{}` expects {} numbers", head, count)));
+            }
+            let ns = fields[..count]
+                .iter()
+                .map(|s| {
+                    s.parse::<f64>()
+                        .map_err(|_| err(format!("invalid number `{}`", s)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let rgb = |ns: &[f64]| [ns[0], ns[1], ns[2]].map(|c| c.clamp(0., 255.) as u8);
+            if head == "size" {
+                recorder = Some(Self::new(ns[0] as usize, ns[1] as usize));
+                continue;
+            }
+            let Some(recorder) = recorder.as_mut() else {
+                return Err(err("expected `size` first".into()));
+            };
+            recorder.commands.push(match head {
+                "clear" => Command::Clear(rgb(&ns)),
+                "dot" => Command::Dot([ns[0], ns[1]], ns[2] as usize, rgb(&ns[3..])),
+                "line" => Command::Line(
+                    [ns[0], ns[1]],
+                    [ns[2], ns[3]],
+                    ns[4] as usize,
+                    rgb(&ns[5..]),
+                ),
+                "polygon" => Command::Polygon(
+                    ns[3..].chunks_exact(2).map(|p| [p[0], p[1]]).collect(),
+                    rgb(&ns),
+                ),
+                _ => Command::Text(
+                    [ns[0], ns[1]],
+                    ns[2] as usize,
+                    text.unwrap_or_default().to_string(),
+                    rgb(&ns[3..]),
+                ),
+            });
+        }
+        recorder.ok_or_else(|| "missing `size`".to_string())
+    }
+}
+
+impl DrawTarget for Recorder {
+    fn width(&self) -> usize {
+        self.w
+    }
+
+    fn height(&self) -> usize {
+        self.h
+    }
+
+    /// Also drops the commands recorded so far
+    fn clear(&mut self, rgb: [u8; 3]) {
+        self.commands.clear();
+        self.commands.push(Command::Clear(rgb));
+    }
+
+    fn dot(&mut self, p: V2f64, size: usize, rgb: [u8; 3]) {
+        self.commands.push(Command::Dot(p, size, rgb));
+    }
+
+    fn line(&mut self, p1: V2f64, p2: V2f64, width: usize, rgb: [u8; 3]) {
+        self.commands.push(Command::Line(p1, p2, width, rgb));
+    }
+
+    fn polygon(&mut self, ps: &[V2f64], rgb: [u8; 3]) {
+        self.commands.push(Command::Polygon(ps.to_vec(), rgb));
+    }
+
+    /// New lines are replaced by spaces to keep one command per line
+    fn text(&mut self, p: V2f64, size: usize, s: &str, rgb: [u8; 3]) {
+        let s = s.replace(['\n', '\r'], " ");
+        self.commands.push(Command::Text(p, size, s, rgb));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    #[test]
+    fn replay_test() {
+        let mut recorder = Recorder::new(16, 12);
+        recorder.clear([0xFF; 3]);
+        recorder.dot([2.5, 3.5], 3, [0xFF, 0x00, 0x00]);
+        recorder.line([0.5, 0.5], [15.5, 11.5], 1, [0x00; 3]);
+        recorder.polygon(&[[8., 1.], [15., 1.], [15., 6.]], [0x00, 0x00, 0xFF]);
+        recorder.text([1., 6.], 5, "Hi 3d!", [0x80, 0x00, 0xFF]);
+        let text = recorder.serialize();
+        assert!(text.starts_with("size 16 12\nclear 255 255 255\ndot 2.5 3.5 3 255 0 0\n"));
+        let parsed = Recorder::parse(&text).unwrap();
+        assert_eq!(parsed, recorder);
+        let mut direct = Canvas::new(16, 12, [0x00; 3]);
+        direct.clear([0xFF; 3]);
+        DrawTarget::dot(&mut direct, [2.5, 3.5], 3, [0xFF, 0x00, 0x00]);
+        DrawTarget::line(&mut direct, [0.5, 0.5], [15.5, 11.5], 1, [0x00; 3]);
+        direct.polygon(&[[8., 1.], [15., 1.], [15., 6.]], [0x00, 0x00, 0xFF]);
+        direct.text([1., 6.], 5, "Hi 3d!", [0x80, 0x00, 0xFF]);
+        let mut replayed = Canvas::new(16, 12, [0x00; 3]);
+        parsed.replay(&mut replayed);
+        for y in 0..12 {
+            for x in 0..16 {
+                assert_eq!(direct.pixel(x, y), replayed.pixel(x, y));
+            }
+        }
+        assert_eq!(replayed.pixel(14, 2), Some(&[0x00, 0x00, 0xFF]));
+        assert_eq!(replayed.pixel(1, 6), Some(&[0x80, 0x00, 0xFF]));
+        assert!(Recorder::parse("size 4 4\nline 0 0 1").is_err());
+        assert!(Recorder::parse("dot 0 0 1 0 0 0").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! # High Dynamic Range
+//! An f32 framebuffer that keeps values above `1`, written as
+//! Portable FloatMap (`.pfm`) or Radiance RGBE (`.hdr`) for grading in external tools.
+//! Values are linear light like `motion::Accumulator` sums, so sub-frames average and filters run
+//! without conversion. `1` is the byte `255` once sRGB encoded, external tools expect the same.
+
+use crate::canvas::Canvas;
+use crate::color::{self, Rgb};
+use common::filter::{self, Filter};
+use common::hdr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Pfm,
+    Rgbe,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Pfm => "pfm",
+            Format::Rgbe => "hdr",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    w: usize,
+    h: usize,
+    vec: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(w: usize, h: usize) -> Self {
+        Self {
+            w,
+            h,
+            vec: vec![[0.; 3]; w * h],
+        }
+    }
+
+    /// Decodes the sRGB bytes to linear light
+    pub fn from_canvas(canvas: &Canvas) -> Self {
+        let w = canvas.width();
+        let h = canvas.height();
+        let vec = (0..w * h)
+            .map(|i| color::to_linear(*canvas.pixel(i % w, i / w).unwrap()))
+            .collect();
+        Self { w, h, vec }
+    }
+
+    /// Clamps to the displayable range and encodes to sRGB bytes
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.w, self.h, [0x00; 3]);
+        for (i, &rgb) in self.vec.iter().enumerate() {
+            *canvas.pixel_mut(i % self.w, i / self.w).unwrap() = color::from_linear(rgb);
+        }
+        canvas
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.w
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.h
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Option<&Rgb> {
+        if x < self.w && y < self.h {
+            Some(&self.vec[y * self.w + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn pixel_mut(&mut self, x: usize, y: usize) -> Option<&mut Rgb> {
+        if x < self.w && y < self.h {
+            Some(&mut self.vec[y * self.w + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn fill(&mut self, rgb: Rgb) {
+        self.vec.fill(rgb);
+    }
+
+    /// Adds `other` weighted by `weight`, both must have the same size
+    pub fn add_scaled(&mut self, other: &Image, weight: f32) {
+        assert!(self.w == other.w && self.h == other.h);
+        for (a, b) in self.vec.iter_mut().zip(&other.vec) {
+            for k in 0..3 {
+                a[k] += b[k] * weight;
+            }
+        }
+    }
+
+    /// Runs `chain`, keeping values above `1`
+    pub fn filter(&mut self, chain: &[Filter]) {
+        filter::apply(chain, self.w, self.h, &mut self.vec);
+    }
+
+    /// Little-endian PFM, rows from the bottom
+    pub fn encode_pfm(&self) -> Vec<u8> {
+        hdr::encode_pfm(self.w, self.h, &self.vec)
+    }
+
+    /// Radiance RGBE, scanlines are run-length encoded when the width allows it
+    pub fn encode_rgbe(&self) -> Vec<u8> {
+        hdr::encode_rgbe(self.w, self.h, &self.vec)
+    }
+
+    /// Writes `output/{file_name}.pfm` or `.hdr`
+    pub fn output(&self, file_name: &str, format: Format) {
+        use std::env;
+        use std::fs;
+        let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let output_dir = root + "/output";
+        if !fs::exists(&output_dir).unwrap() {
+            fs::create_dir_all(&output_dir).unwrap();
+        }
+        let output_path = format!("{}/{}.{}", output_dir, file_name, format.extension());
+        let buf = match format {
+            Format::Pfm => self.encode_pfm(),
+            Format::Rgbe => self.encode_rgbe(),
+        };
+        fs::write(&output_path, buf).unwrap();
+        println!("Generated {}", output_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_test() {
+        let mut image = Image::new(20, 2);
+        for x in 0..20 {
+            let c = if x < 10 { 4. } else { x as f32 / 7. };
+            *image.pixel_mut(x, 1).unwrap() = [c, 0.25, 0.];
+        }
+        let pfm = image.encode_pfm();
+        assert!(pfm.starts_with(b"PF\n20 2\n-1.0\n"));
+        // the bottom row comes first
+        assert_eq!(pfm[13..17], 4f32.to_le_bytes());
+        let header = hdr::rgbe_header(20, 2).len();
+        assert!(hdr::rgbe_complete(20, 2, &image.encode_rgbe()[header..]));
+        // linear light, encoded to sRGB bytes
+        let canvas = image.to_canvas();
+        assert_eq!(canvas.pixel(0, 1), Some(&[0xFF, 0x89, 0x00]));
+        assert_eq!(
+            Image::from_canvas(&canvas).to_canvas().pixel(0, 1),
+            canvas.pixel(0, 1)
+        );
+    }
+}
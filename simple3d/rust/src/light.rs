@@ -0,0 +1,98 @@
+use crate::vector::{V3f64, Vector};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+    Directional {
+        /// the direction light travels in
+        direction: V3f64,
+        color: V3f64,
+    },
+    Point {
+        position: V3f64,
+        color: V3f64,
+    },
+    Spot {
+        position: V3f64,
+        direction: V3f64,
+        /// half angle of the cone in radians
+        angle: f64,
+        color: V3f64,
+    },
+}
+
+impl Light {
+    /// # Returns
+    /// unit vector from `p` towards the light, distance and received color
+    pub fn incident(&self, p: V3f64) -> (V3f64, f64, V3f64) {
+        match *self {
+            Self::Directional { direction, color } => {
+                (direction.normalized().neg(), f64::INFINITY, color)
+            }
+            Self::Point { position, color } => {
+                let d = position.subv(&p);
+                let m = d.magnitude();
+                (d.divs(m), m, color)
+            }
+            Self::Spot {
+                position,
+                direction,
+                angle,
+                color,
+            } => {
+                let d = position.subv(&p);
+                let m = d.magnitude();
+                let l = d.divs(m);
+                let cos = l.neg().dot(&direction.normalized());
+                // soft edge over the outer tenth of the cone
+                let outer = angle.cos();
+                let inner = (angle * 0.9).cos();
+                let k = ((cos - outer) / (inner - outer).max(1e-9)).clamp(0., 1.);
+                (l, m, color.muls(k))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Lighting {
+    pub ambient: V3f64,
+    pub lights: Vec<Light>,
+}
+
+impl Default for Lighting {
+    /// Unlit: full ambient and no lights, so albedo passes through unchanged
+    fn default() -> Self {
+        Self {
+            ambient: [1.; 3],
+            lights: Vec::new(),
+        }
+    }
+}
+
+impl Lighting {
+    /// # Lambert Irradiance
+    /// `n` is the unit surface normal at `p`
+    pub fn irradiance(&self, p: V3f64, n: V3f64) -> V3f64 {
+        self.irradiance_visible(p, n, |_| 1.)
+    }
+
+    /// Same as `irradiance`, with each light scaled by `visibility` of its index, e.g. for shadows
+    pub fn irradiance_visible(
+        &self,
+        p: V3f64,
+        n: V3f64,
+        visibility: impl Fn(usize) -> f64,
+    ) -> V3f64 {
+        self.lights
+            .iter()
+            .enumerate()
+            .fold(self.ambient, |sum, (i, light)| {
+                let (l, _, color) = light.incident(p);
+                let cos = n.dot(&l);
+                if cos <= 0. {
+                    return sum;
+                }
+                sum.addv(&color.muls(cos * visibility(i)))
+            })
+    }
+}
@@ -1,16 +1,207 @@
+use crate::animation::{Animation, Easing, Timeline, Track};
+use crate::ascii::Ascii;
+use crate::camera::Camera;
 use crate::canvas::Canvas;
+use crate::draw::DrawTarget;
 use crate::object::Object;
-use crate::project::{persp, screen};
-use crate::vector::Convert;
+use crate::quaternion::Quat;
+use crate::scene::{Node, Scene};
+use crate::scenefile::{Output, Renderer, SceneFile};
+use crate::svg::Svg;
+use crate::terminal::Player;
+use std::fs;
+use std::path::Path;
 
 fn main() {
-    let w = 16 * 60;
-    let h = 9 * 60;
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.get(1).map(String::as_str) {
+        None => demo(&mut demo_canvas(), |i, canvas| {
+            canvas.output_ppm(&format!("frame-{:02}", i))
+        }),
+        Some("render") if args.len() == 3 => render_file(&args[2], Renderer::Raster),
+        Some("trace") if args.len() == 3 => render_file(&args[2], Renderer::Trace),
+        Some("preview") if args.len() <= 3 => preview(args.get(2).map(String::as_str)),
+        Some("play") if args.len() >= 3 => play(&args[2..]),
+        Some("ascii") => ascii(&args[2..]),
+        Some("svg") if args.len() <= 3 => svg(args.get(2).map(String::as_str)),
+        _ => {
+            eprintln!(
+                "usage: {} [render|trace <scene.txt> | preview [scene.txt] | play [--fps N] <frames>... | ascii [--cols N] [--invert] [--edges T] [scene.txt] | svg [scene.txt]]",
+                args[0]
+            );
+            std::process::exit(2);
+        }
+    }
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    std::process::exit(1);
+}
+
+fn load_scene(path: &str) -> SceneFile {
+    let path = Path::new(path);
+    let src =
+        fs::read_to_string(path).unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)));
+    let dir = path.parent().unwrap_or(Path::new("."));
+    SceneFile::parse(&src, dir).unwrap_or_else(|e| fail(format!("{}:{}", path.display(), e)))
+}
+
+fn render_file(path: &str, renderer: Renderer) {
+    if let Err(e) = load_scene(path).render(renderer) {
+        fail(format!("{}:{}", path, e));
+    }
+}
+
+/// Plays the demo or the rasterized frames of a scene file in the terminal
+fn preview(path: Option<&str>) {
+    let cols = terminal::columns();
+    match path {
+        None => {
+            let mut player = Player::new(cols, 60).unwrap_or_else(|e| fail(e));
+            demo(&mut demo_canvas(), |_, canvas| {
+                player.frame(canvas).unwrap_or_else(|e| fail(e))
+            });
+        }
+        Some(path) => {
+            let file = load_scene(path);
+            let mut player = Player::new(cols, file.output.fps).unwrap_or_else(|e| fail(e));
+            let rendered = file.frames(Renderer::Raster, |_, canvas| {
+                player.frame(canvas).unwrap_or_else(|e| fail(e))
+            });
+            drop(player);
+            if let Err(e) = rendered {
+                fail(format!("{}:{}", path, e));
+            }
+        }
+    }
+}
+
+/// # Params
+/// `args` are PPM files or directories of them, such as the frames written by ppmdemo,
+/// directories are played in file name order
+fn play(args: &[String]) {
+    let mut fps = 60;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--fps" {
+            fps = args
+                .next()
+                .and_then(|s| s.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or_else(|| fail("--fps expects a positive integer"));
+            continue;
+        }
+        let path = Path::new(arg);
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)
+                .unwrap_or_else(|e| fail(format!("{}: {}", arg, e)))
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "ppm"))
+                .collect::<Vec<_>>();
+            entries.sort();
+            paths.extend(entries);
+        } else {
+            paths.push(path.to_path_buf());
+        }
+    }
+    let mut player = Player::new(terminal::columns(), fps).unwrap_or_else(|e| fail(e));
+    for path in paths {
+        match Canvas::load_ppm(&path.to_string_lossy()) {
+            Ok(canvas) => player.frame(&canvas).unwrap_or_else(|e| fail(e)),
+            Err(e) => {
+                drop(player);
+                fail(e);
+            }
+        }
+    }
+}
+
+/// Writes every frame of the demo or a scene file, separated by empty lines, to one text file
+fn ascii(args: &[String]) {
+    let mut ascii = Ascii::default();
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cols" => {
+                ascii.cols = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&n| n > 0)
+                    .unwrap_or_else(|| fail("--cols expects a positive integer"));
+            }
+            "--invert" => ascii.invert = true,
+            "--edges" => {
+                let threshold = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&t: &f64| t >= 0.)
+                    .unwrap_or_else(|| fail("--edges expects a non-negative threshold"));
+                ascii.edges = Some(threshold);
+            }
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => fail(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    let mut text = String::new();
+    let mut f = |_, canvas: &Canvas| {
+        text += &ascii.render(canvas);
+        text += "\n";
+    };
+    let name = match path {
+        None => {
+            demo(&mut demo_canvas(), &mut f);
+            "frame".to_string()
+        }
+        Some(path) => {
+            let file = load_scene(path);
+            if let Err(e) = file.frames(Renderer::Raster, &mut f) {
+                fail(format!("{}:{}", path, e));
+            }
+            file.output.name
+        }
+    };
+    ascii::output_txt(&text, &name);
+}
+
+/// Writes the wireframes of every frame of the demo or a scene file as SVG documents
+fn svg(path: Option<&str>) {
+    match path {
+        None => {
+            let [w, h] = DEMO_SIZE;
+            demo(&mut Svg::new(w, h, None), |i, svg| {
+                svg.output_svg(&format!("frame-{:02}", i))
+            });
+        }
+        Some(path) => {
+            let file = load_scene(path);
+            let Output { w, h, ref name, .. } = file.output;
+            let mut svg = Svg::new(w, h, None);
+            let written = file.draw_frames(&mut svg, |i, svg| {
+                svg.output_svg(&format!("{}-{:02}", name, i))
+            });
+            if let Err(e) = written {
+                fail(format!("{}:{}", path, e));
+            }
+        }
+    }
+}
+
+const DEMO_SIZE: [usize; 2] = [16 * 60, 9 * 60];
+
+fn demo_canvas() -> Canvas {
+    let [w, h] = DEMO_SIZE;
+    Canvas::new(w, h, [0x00; 3])
+}
+
+/// Draws the spinning pyramid on `target`, `f` receives every frame
+fn demo<T: DrawTarget>(target: &mut T, mut f: impl FnMut(usize, &T)) {
     let bg = [0xC0, 0xC0, 0xC0];
     let fg = [0x80, 0x00, 0xFF];
     let line_width = 3;
-    let mut canvas = Canvas::new(w, h, bg);
-    let mut pyramid = Object::new(
+    let pyramid = Object::new(
         vec![
             [0., 2. * 2_f64.sqrt() * 2. / 3., 0.],              // 0
             [0., -2. * 2_f64.sqrt() * 1. / 3., 2.],             // 1
@@ -20,25 +211,56 @@ fn main() {
         vec![vec![0, 1], vec![0, 2], vec![0, 3], vec![1, 2, 3]],
     );
     let fps = 60;
-    let secs = 1;
-    let rad = 6_f64.to_radians();
-    pyramid.translate_local([0., 0., 5.]);
-    let rtsf = pyramid.ry_local_tsf(rad);
-    for i in 0..(fps * secs) {
-        for [p1, p2] in pyramid.lines() {
-            let p1 = screen(persp(p1), w, h).cvt();
-            let p2 = screen(persp(p2), w, h).cvt();
-            canvas.line(p1, p2, line_width, fg);
-        }
-        pyramid.transform(&rtsf);
-        canvas.output_ppm(&format!("frame-{:02}", i));
-        canvas.fill(bg);
+    let camera = Camera::default();
+    let mut scene = Scene::new();
+    let id = scene.add(None, Node::new("pyramid").with_object(pyramid));
+    let mut animation = Animation::new();
+    animation.translation = Track::new().key(0., [0., 0., 5.], Easing::Linear);
+    animation.rotation = Track::new()
+        .key(0., Quat::ry_rad(0_f64.to_radians()), Easing::Linear)
+        .key(1. / 3., Quat::ry_rad(120_f64.to_radians()), Easing::Linear)
+        .key(2. / 3., Quat::ry_rad(240_f64.to_radians()), Easing::Linear)
+        .key(1., Quat::ry_rad(360_f64.to_radians()), Easing::Linear);
+    let mut timeline = Timeline::new();
+    timeline.bind(id, animation);
+    for (i, t) in timeline.frames(fps).enumerate() {
+        timeline.apply(&mut scene, t);
+        scene.update();
+        target.clear(bg);
+        let objects = scene.objects().collect::<Vec<_>>();
+        render::wireframes(
+            target,
+            &camera,
+            objects.iter().map(|(_, object)| (object, line_width, fg)),
+        );
+        f(i, target);
     }
 }
 
+pub mod animation;
+pub mod ascii;
+pub mod bounds;
+pub mod bvh;
+pub mod camera;
 pub mod canvas;
+pub mod color;
 pub mod coordsys;
+pub mod draw;
+pub mod hdr;
+pub mod light;
+pub mod motion;
 pub mod object;
+pub mod primitive;
 pub mod project;
+pub mod quaternion;
+pub mod ray;
+pub mod render;
+pub mod scene;
+pub mod scenefile;
+pub mod shadow;
+pub mod svg;
+pub mod terminal;
+pub mod texture;
+pub mod tracer;
 pub mod transform;
 pub mod vector;
@@ -0,0 +1,60 @@
+use crate::canvas::Canvas;
+use crate::color;
+
+/// Running sum of sub-frames in linear light
+pub struct Accumulator {
+    sum: Vec<[f64; 3]>,
+    n: usize,
+}
+
+impl Accumulator {
+    pub fn new(w: usize, h: usize) -> Self {
+        Self {
+            sum: vec![[0.; 3]; w * h],
+            n: 0,
+        }
+    }
+
+    pub fn add(&mut self, canvas: &Canvas) {
+        let w = canvas.width();
+        for (i, sum) in self.sum.iter_mut().enumerate() {
+            let rgb = color::to_linear(*canvas.pixel(i % w, i / w).unwrap());
+            for k in 0..3 {
+                sum[k] += rgb[k] as f64;
+            }
+        }
+        self.n += 1;
+    }
+
+    /// Writes the mean of the added sub-frames to `canvas` and starts over
+    pub fn resolve(&mut self, canvas: &mut Canvas) {
+        let w = canvas.width();
+        let n = self.n.max(1) as f64;
+        for (i, sum) in self.sum.iter_mut().enumerate() {
+            *canvas.pixel_mut(i % w, i / w).unwrap() =
+                color::from_linear(sum.map(|c| (c / n) as f32));
+            *sum = [0.; 3];
+        }
+        self.n = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulate_test() {
+        let mut acc = Accumulator::new(4, 1);
+        let mut canvas = Canvas::new(4, 1, [0x00; 3]);
+        for k in 0..4 {
+            canvas.fill([0x00; 3]);
+            *canvas.pixel_mut(k, 0).unwrap() = [0xFF, 0x80, 0x00];
+            acc.add(&canvas);
+        }
+        acc.resolve(&mut canvas);
+        for x in 0..4 {
+            assert_eq!(canvas.pixel(x, 0), Some(&[0x89, 0x42, 0x00]));
+        }
+    }
+}
@@ -1,23 +1,125 @@
+use crate::bounds::{Aabb, BoundingSphere};
 use crate::coordsys::CoordSys;
 use crate::transform as tsf;
 use crate::transform::Transform;
-use crate::vector::{V3f64, Vector};
+use crate::vector::{V2f64, V3f64, Vector};
 
+#[derive(Clone)]
 pub struct Object {
     cs: CoordSys,
     vs: Vec<V3f64>,
     es: Vec<Vec<usize>>,
+    uvs: Option<Vec<V2f64>>,
+    aabb: Aabb,
+    sphere: BoundingSphere,
 }
 
 impl Object {
     pub fn new(vs: Vec<V3f64>, es: Vec<Vec<usize>>) -> Self {
+        let aabb = Aabb::from_points(&vs);
+        let sphere = BoundingSphere::from_points(&vs, &aabb);
         Self {
             cs: CoordSys::global(),
             vs,
             es,
+            uvs: None,
+            aabb,
+            sphere,
         }
     }
 
+    /// # Params
+    /// one `uv` per vertex, indexed the same as `vs`
+    pub fn with_uvs(mut self, uvs: Vec<V2f64>) -> Self {
+        assert_eq!(uvs.len(), self.vs.len());
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// # Supported Statements
+    /// `v`, `vt`, `f` and `l`, other statements are ignored.
+    /// Vertices referenced with different `vt` are duplicated so that uvs stay per-vertex.
+    pub fn from_obj(src: &str) -> Result<Self, String> {
+        let mut vs = Vec::new();
+        let mut vts = Vec::new();
+        let mut out_vs = Vec::new();
+        let mut out_uvs = Vec::new();
+        let mut has_uvs = false;
+        let mut indices = std::collections::HashMap::new();
+        let mut es = Vec::new();
+        for (n, line) in src.lines().enumerate() {
+            let n = n + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            let Some(kind) = tokens.next() else {
+                continue;
+            };
+            let float = |s: &str| {
+                s.parse::<f64>()
+                    .map_err(|_| format!("line {}: invalid number `{}`", n, s))
+            };
+            let index = |s: &str, len: usize| {
+                let i = s
+                    .parse::<i64>()
+                    .map_err(|_| format!("line {}: invalid index `{}`", n, s))?;
+                let i = if i < 0 { len as i64 + i } else { i - 1 };
+                if 0 <= i && (i as usize) < len {
+                    Ok(i as usize)
+                } else {
+                    Err(format!("line {}: index `{}` out of range", n, s))
+                }
+            };
+            match kind {
+                "v" => {
+                    let xyz = tokens.take(3).map(float).collect::<Result<Vec<_>, _>>()?;
+                    let [x, y, z] = xyz[..] else {
+                        return Err(format!("line {}: expected 3 coordinates", n));
+                    };
+                    vs.push([x, y, z]);
+                }
+                "vt" => {
+                    let uv = tokens.take(2).map(float).collect::<Result<Vec<_>, _>>()?;
+                    let [u, v] = match uv[..] {
+                        [u] => [u, 0.],
+                        [u, v] => [u, v],
+                        _ => return Err(format!("line {}: expected texture coordinates", n)),
+                    };
+                    vts.push([u, v]);
+                }
+                "f" | "l" => {
+                    let mut e = Vec::new();
+                    for token in tokens {
+                        let mut parts = token.split('/');
+                        let v = index(parts.next().unwrap_or_default(), vs.len())?;
+                        let vt = match parts.next() {
+                            Some(s) if !s.is_empty() => Some(index(s, vts.len())?),
+                            _ => None,
+                        };
+                        has_uvs |= vt.is_some();
+                        let i = *indices.entry((v, vt)).or_insert_with(|| {
+                            out_vs.push(vs[v]);
+                            out_uvs.push(vt.map(|vt| vts[vt]).unwrap_or_default());
+                            out_vs.len() - 1
+                        });
+                        e.push(i);
+                    }
+                    if e.len() < 2 {
+                        return Err(format!("line {}: expected at least 2 vertices", n));
+                    }
+                    es.push(e);
+                }
+                _ => {}
+            }
+        }
+        let obj = Self::new(out_vs, es);
+        Ok(if has_uvs { obj.with_uvs(out_uvs) } else { obj })
+    }
+
+    pub fn load_obj(path: &str) -> Result<Self, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::from_obj(&src).map_err(|e| format!("{}: {}", path, e))
+    }
+
     pub fn coordsys(&self) -> &CoordSys {
         &self.cs
     }
@@ -30,6 +132,18 @@ impl Object {
         &self.es
     }
 
+    pub fn uvs(&self) -> Option<&[V2f64]> {
+        self.uvs.as_deref()
+    }
+
+    pub fn aabb(&self) -> &Aabb {
+        &self.aabb
+    }
+
+    pub fn bounding_sphere(&self) -> &BoundingSphere {
+        &self.sphere
+    }
+
     pub fn translate_local_tsf(&mut self, v: V3f64) -> Transform {
         let [x, y, z] = v;
         Transform::new(tsf::translate(self.cs.x().muls(x)))
@@ -72,13 +186,80 @@ impl Object {
     pub fn transform(&mut self, ts: &Transform) {
         ts.transform(self.cs.as_mat_mut());
         ts.transform(&mut self.vs);
+        self.aabb = Aabb::from_points(&self.vs);
+        self.sphere = BoundingSphere::from_points(&self.vs, &self.aabb);
     }
 
     pub fn lines(&self) -> impl Iterator<Item = [V3f64; 2]> {
         self.es
             .iter()
-            .map(|e| (0..e.len()).map(|i| (e[i], e[(i + 1) % e.len()])))
-            .flatten()
+            .flat_map(|e| (0..e.len()).map(|i| (e[i], e[(i + 1) % e.len()])))
             .map(|(i, j)| [self.vs[i], self.vs[j]])
     }
+
+    /// # Triangulation
+    /// fan triangulation of every face with at least 3 vertices, edges are skipped
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> {
+        self.face_triangles().map(|(_, tri)| tri)
+    }
+
+    /// Same as `triangles`, paired with the index of the face in `edges`
+    pub fn face_triangles(&self) -> impl Iterator<Item = (usize, [usize; 3])> {
+        self.es
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.len() >= 3)
+            .flat_map(|(f, e)| (1..e.len() - 1).map(move |i| (f, [e[0], e[i], e[i + 1]])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obj_test() {
+        let src = "\
+# two triangles sharing an edge, vertex 1 with two uvs, and a line without uvs
+v 0 0 0
+v 1 0 0
+v 1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0.5
+f 1/1 2/2 3/3
+f -1/-2 2/2 1/4 # relative indices
+l 1 3
+";
+        let obj = Object::from_obj(src).unwrap();
+        assert_eq!(
+            obj.vertices(),
+            [
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 1., 0.],
+                [0., 0., 0.],
+                [0., 0., 0.],
+                [1., 1., 0.]
+            ]
+        );
+        let uvs = obj.uvs().unwrap();
+        assert_eq!(
+            uvs,
+            [[0., 0.], [1., 0.], [1., 1.], [0.5, 0.], [0., 0.], [0., 0.]]
+        );
+        assert_eq!(obj.edges(), [vec![0, 1, 2], vec![2, 1, 3], vec![4, 5]]);
+        assert_eq!(obj.triangles().count(), 2);
+        assert_eq!(
+            Object::from_obj("v 0 0 0\nf 1/2\n").err().unwrap(),
+            "line 2: index `2` out of range"
+        );
+        assert!(
+            Object::from_obj("v 0 0 0\nf 1 1 1\n")
+                .unwrap()
+                .uvs()
+                .is_none()
+        );
+    }
 }
@@ -0,0 +1,78 @@
+use crate::object::Object;
+use crate::vector::{V2f64, V3f64};
+use std::f64::consts::PI;
+
+const QUAD_UVS: [V2f64; 4] = [[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+
+/// # Layout
+/// centered at the origin, every face owns its 4 vertices so each face maps the whole texture
+pub fn cube(size: f64) -> Object {
+    let s = size / 2.;
+    // (normal axis, sign)
+    let faces = [(2, -1.), (0, 1.), (2, 1.), (0, -1.), (1, 1.), (1, -1.)];
+    let mut vs = Vec::with_capacity(24);
+    let mut uvs = Vec::with_capacity(24);
+    let mut es = Vec::with_capacity(6);
+    for (axis, sign) in faces {
+        let u = if axis == 1 { 0 } else { 2 - axis };
+        let v = if axis == 1 { 2 } else { 1 };
+        let base = vs.len();
+        for [a, b] in QUAD_UVS {
+            let mut p = [0.; 3];
+            p[axis] = sign * s;
+            p[u] = (a * 2. - 1.) * s * if axis == 1 { 1. } else { -sign };
+            p[v] = (b * 2. - 1.) * s * if axis == 1 { -sign } else { 1. };
+            vs.push(p);
+            uvs.push([a, b]);
+        }
+        es.push((base..base + 4).collect());
+    }
+    Object::new(vs, es).with_uvs(uvs)
+}
+
+/// # Layout
+/// square on the `xz` plane, centered at the origin, facing `+y`
+pub fn plane(size: f64) -> Object {
+    let s = size / 2.;
+    let vs = vec![[-s, 0., -s], [s, 0., -s], [s, 0., s], [-s, 0., s]];
+    Object::new(vs, vec![vec![0, 1, 2, 3]]).with_uvs(QUAD_UVS.to_vec())
+}
+
+/// # Layout
+/// uv sphere around the `y` axis, the seam column is duplicated so `u` runs from 0 to 1
+pub fn sphere(radius: f64, slices: usize, stacks: usize) -> Object {
+    assert!(slices >= 3);
+    assert!(stacks >= 2);
+    let mut vs: Vec<V3f64> = Vec::with_capacity((slices + 1) * (stacks + 1));
+    let mut uvs = Vec::with_capacity((slices + 1) * (stacks + 1));
+    for j in 0..=stacks {
+        let v = j as f64 / stacks as f64;
+        let phi = PI * v;
+        for i in 0..=slices {
+            let u = i as f64 / slices as f64;
+            let theta = 2. * PI * u;
+            vs.push([
+                radius * phi.sin() * theta.sin(),
+                -radius * phi.cos(),
+                -radius * phi.sin() * theta.cos(),
+            ]);
+            uvs.push([u, v]);
+        }
+    }
+    let row = slices + 1;
+    let mut es = Vec::with_capacity(slices * stacks);
+    for j in 0..stacks {
+        for i in 0..slices {
+            let a = j * row + i;
+            let b = a + row;
+            es.push(if j == 0 {
+                vec![a, b + 1, b]
+            } else if j == stacks - 1 {
+                vec![a, a + 1, b]
+            } else {
+                vec![a, a + 1, b + 1, b]
+            });
+        }
+    }
+    Object::new(vs, es).with_uvs(uvs)
+}
@@ -19,3 +19,22 @@ pub fn screen(p: V2f64, w: usize, h: usize) -> V2f64 {
     let [x, y] = p.mulv(&[sx, sy]);
     [1. + x, 1. - y].divs(2.).mulv(&[w, h])
 }
+
+/// # Inverse of `screen`
+pub fn unscreen(p: V2f64, w: usize, h: usize) -> V2f64 {
+    assert!(w != 0);
+    assert!(h != 0);
+    let w = w as f64;
+    let h = h as f64;
+    let sx = if w > h { h / w } else { 1. };
+    let sy = if h > w { w / h } else { 1. };
+    let [x, y] = p.divv(&[w, h]).muls(2.);
+    [x - 1., 1. - y].divv(&[sx, sy])
+}
+
+/// # Inverse of `persp`
+/// `z` is the depth the point is lifted back to
+pub fn unpersp(p: V2f64, z: f64) -> V3f64 {
+    let [x, y] = p.muls(z);
+    [x, y, z]
+}
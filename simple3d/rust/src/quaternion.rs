@@ -0,0 +1,131 @@
+use crate::vector::{V3f64, V4f64, Vector};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Quat {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quat {
+    pub const fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new(1., 0., 0., 0.)
+    }
+
+    pub fn axis_rad(axis: V3f64, rad: f64) -> Self {
+        let [x, y, z] = axis.normalized().muls((rad / 2.).sin());
+        Self::new((rad / 2.).cos(), x, y, z)
+    }
+
+    pub fn rx_rad(rad: f64) -> Self {
+        Self::axis_rad([1., 0., 0.], rad)
+    }
+
+    pub fn ry_rad(rad: f64) -> Self {
+        Self::axis_rad([0., 1., 0.], rad)
+    }
+
+    pub fn rz_rad(rad: f64) -> Self {
+        Self::axis_rad([0., 0., 1.], rad)
+    }
+
+    #[inline]
+    fn as_v4(&self) -> V4f64 {
+        [self.w, self.x, self.y, self.z]
+    }
+
+    #[inline]
+    fn from_v4([w, x, y, z]: V4f64) -> Self {
+        Self::new(w, x, y, z)
+    }
+
+    pub fn dot(&self, rhs: &Self) -> f64 {
+        self.as_v4().dot(&rhs.as_v4())
+    }
+
+    pub fn normalized(&self) -> Self {
+        Self::from_v4(self.as_v4().normalized())
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// # Spherical Linear Interpolation
+    /// always takes the shorter arc
+    pub fn slerp(&self, rhs: &Self, t: f64) -> Self {
+        let a = self.as_v4();
+        let mut b = rhs.as_v4();
+        let mut cos = a.dot(&b);
+        if cos < 0. {
+            b = b.neg();
+            cos = -cos;
+        }
+        if cos > 1. - 1e-9 {
+            return Self::from_v4(a.addv(&b.subv(&a).muls(t)).normalized());
+        }
+        let theta = cos.acos();
+        let sin = theta.sin();
+        let wa = ((1. - t) * theta).sin() / sin;
+        let wb = (t * theta).sin() / sin;
+        Self::from_v4(a.muls(wa).addv(&b.muls(wb)))
+    }
+
+    /// # Rotation Matrix
+    /// homogeneous, compatible with `transform`
+    pub fn mat(&self) -> [V4f64; 4] {
+        let Self { w, x, y, z } = self.normalized();
+        [
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - w * z),
+                2. * (x * z + w * y),
+                0.,
+            ],
+            [
+                2. * (x * y + w * z),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - w * x),
+                0.,
+            ],
+            [
+                2. * (x * z - w * y),
+                2. * (y * z + w * x),
+                1. - 2. * (x * x + y * y),
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ]
+    }
+}
+
+impl std::ops::Mul<Self> for Quat {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let Self { w, x, y, z } = self;
+        let Self {
+            w: rw,
+            x: rx,
+            y: ry,
+            z: rz,
+        } = rhs;
+        Self {
+            w: w * rw - x * rx - y * ry - z * rz,
+            x: w * rx + x * rw + y * rz - z * ry,
+            y: w * ry - x * rz + y * rw + z * rx,
+            z: w * rz + x * ry - y * rx + z * rw,
+        }
+    }
+}
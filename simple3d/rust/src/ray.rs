@@ -0,0 +1,165 @@
+use crate::bounds::Aabb;
+use crate::object::Object;
+use crate::vector::{V3f64, Vector, Vector3};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: V3f64,
+    /// unit length, so parameters along the ray are distances
+    pub dir: V3f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hit {
+    /// index of the object in the picked sequence
+    pub object: usize,
+    /// index of the face in `Object::edges`
+    pub face: usize,
+    /// vertex indices of the triangle of the face that was hit
+    pub triangle: [usize; 3],
+    pub distance: f64,
+    /// weights of `triangle`'s vertices at the hit point
+    pub bary: V3f64,
+}
+
+impl Ray {
+    pub fn new(origin: V3f64, dir: V3f64) -> Self {
+        Self {
+            origin,
+            dir: dir.normalized(),
+        }
+    }
+
+    #[inline]
+    pub fn at(&self, t: f64) -> V3f64 {
+        self.origin.addv(&self.dir.muls(t))
+    }
+
+    /// # Möller–Trumbore
+    /// two-sided, returns the distance and barycentric coordinates of the hit
+    pub fn triangle(&self, [p1, p2, p3]: [V3f64; 3]) -> Option<(f64, V3f64)> {
+        let e1 = p2.subv(&p1);
+        let e2 = p3.subv(&p1);
+        let p = self.dir.cross(&e2);
+        let det = e1.dot(&p);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv = 1. / det;
+        let s = self.origin.subv(&p1);
+        let u = s.dot(&p) * inv;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&e1);
+        let v = self.dir.dot(&q) * inv;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+        let t = e2.dot(&q) * inv;
+        if t <= 1e-9 {
+            return None;
+        }
+        Some((t, [1. - u - v, u, v]))
+    }
+
+    /// # Slab Test
+    /// returns the entry and exit distances, entry is `0` when the origin is inside
+    pub fn aabb(&self, b: &Aabb) -> Option<(f64, f64)> {
+        let mut t0 = 0_f64;
+        let mut t1 = f64::INFINITY;
+        for i in 0..3 {
+            let inv = 1. / self.dir[i];
+            let mut near = (b.min[i] - self.origin[i]) * inv;
+            let mut far = (b.max[i] - self.origin[i]) * inv;
+            if near > far {
+                (near, far) = (far, near);
+            }
+            // NaN from 0 * inf means the ray lies on the slab boundary, keep the interval
+            t0 = if near.is_nan() { t0 } else { t0.max(near) };
+            t1 = if far.is_nan() { t1 } else { t1.min(far) };
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+
+    /// Closest hit on `obj`, with `object` set to `0`
+    pub fn object(&self, obj: &Object) -> Option<Hit> {
+        self.aabb(obj.aabb())?;
+        let vs = obj.vertices();
+        let mut best: Option<Hit> = None;
+        for (face, tri) in obj.face_triangles() {
+            let Some((t, bary)) = self.triangle(tri.map(|i| vs[i])) else {
+                continue;
+            };
+            if best.is_none_or(|h| t < h.distance) {
+                best = Some(Hit {
+                    object: 0,
+                    face,
+                    triangle: tri,
+                    distance: t,
+                    bary,
+                });
+            }
+        }
+        best
+    }
+
+    /// Closest hit among `objects`
+    pub fn pick<'a>(&self, objects: impl IntoIterator<Item = &'a Object>) -> Option<Hit> {
+        let mut best: Option<Hit> = None;
+        for (i, obj) in objects.into_iter().enumerate() {
+            if best.is_some_and(|h| match self.aabb(obj.aabb()) {
+                Some((t0, _)) => t0 > h.distance,
+                None => true,
+            }) {
+                continue;
+            }
+            if let Some(hit) = self.object(obj)
+                && best.is_none_or(|h| hit.distance < h.distance)
+            {
+                best = Some(Hit { object: i, ..hit });
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::primitive;
+    use crate::project::{persp, screen};
+    use crate::transform::Transform;
+
+    #[test]
+    fn pick_test() {
+        let (w, h) = (160, 90);
+        let camera = Camera::default();
+        let mut near = primitive::cube(1.);
+        near.transform(&Transform::e().translate([0.5, 0., 3.]));
+        let mut far = primitive::cube(4.);
+        far.transform(&Transform::e().translate([0., 0., 10.]));
+        let objects = [far, near];
+        // pixel covering the point (0.25, 0.25) on the near cube's front face
+        let [x, y] = screen(persp([0.25, 0.25, 2.5]), w, h);
+        let ray = camera.ray([x, y], w, h);
+        let [rx, ry, rz] = ray.at(2.5 / ray.dir[2]);
+        assert!((rx - 0.25).abs() < 1e-9 && (ry - 0.25).abs() < 1e-9 && (rz - 2.5).abs() < 1e-9);
+        let hit = ray.pick(&objects).unwrap();
+        assert_eq!((hit.object, hit.face), (1, 0));
+        let vs = objects[1].vertices();
+        let p = (0..3).fold([0.; 3], |p, i| {
+            p.addv(&vs[hit.triangle[i]].muls(hit.bary[i]))
+        });
+        assert!(p.distance(&ray.at(hit.distance)) < 1e-9);
+        // pixel missing the near cube hits the far one
+        let [x, y] = screen(persp([-0.5, 0., 8.]), w, h);
+        let hit = camera.ray([x, y], w, h).pick(&objects).unwrap();
+        assert_eq!(hit.object, 0);
+        assert!((hit.distance - 8.0156).abs() < 1e-3);
+    }
+}
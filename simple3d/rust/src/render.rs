@@ -0,0 +1,269 @@
+use crate::bounds::Frustum;
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::draw::DrawTarget;
+use crate::light::Lighting;
+use crate::object::Object;
+use crate::shadow::Shadows;
+use crate::texture::{Sampler, Texture};
+use crate::vector::{V2f64, V3f64, Vector, Vector3};
+
+/// Triangles and lines are clipped to the view space in front of this depth
+pub const NEAR: f64 = 1e-2;
+
+/// # Frustum Culling
+/// `false` when the bounding sphere or box of `obj` is entirely off-screen
+pub fn visible(camera: &Camera, obj: &Object, w: usize, h: usize) -> bool {
+    let frustum = Frustum::new(camera, w, h, NEAR);
+    frustum.sphere(obj.bounding_sphere()) && frustum.aabb(obj.aabb())
+}
+
+fn view_vertices(camera: &Camera, obj: &Object) -> Vec<V3f64> {
+    let mut vs = obj.vertices().to_vec();
+    camera.view().transform(&mut vs);
+    vs
+}
+
+/// # Near-Plane Clipping
+/// the part of the view-space triangle `ps` in front of `NEAR` as a convex polygon
+/// of up to 4 vertices, each paired with its barycentric coordinates in `ps`
+fn clip_near(ps: [V3f64; 3]) -> Vec<(V3f64, V3f64)> {
+    let corners = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let (a, b) = (ps[i], ps[j]);
+        if a[2] >= NEAR {
+            polygon.push((a, corners[i]));
+        }
+        if (a[2] < NEAR) != (b[2] < NEAR) {
+            let t = (NEAR - a[2]) / (b[2] - a[2]);
+            let lerp = |a: V3f64, b: V3f64| a.addv(&b.subv(&a).muls(t));
+            polygon.push((lerp(a, b), lerp(corners[i], corners[j])));
+        }
+    }
+    polygon
+}
+
+/// # Params
+/// `shade` receives the triangle's vertex indices and perspective-correct barycentric coordinates,
+/// also for the parts of triangles clipped against the near plane
+pub fn rasterize(
+    canvas: &mut Canvas,
+    camera: &Camera,
+    obj: &Object,
+    mut shade: impl FnMut([usize; 3], V3f64) -> [u8; 3],
+) {
+    let w = canvas.width();
+    let h = canvas.height();
+    if !visible(camera, obj, w, h) {
+        return;
+    }
+    let vs = view_vertices(camera, obj);
+    for tri in obj.triangles() {
+        let polygon = clip_near(tri.map(|i| vs[i]));
+        for k in 1..polygon.len().saturating_sub(1) {
+            let corners = [polygon[0], polygon[k], polygon[k + 1]];
+            let zs = corners.map(|(p, _)| p[2]);
+            let ps = corners.map(|(p, _)| camera.screen(p, w, h));
+            let bs = corners.map(|(_, b)| b);
+            canvas.triangle(ps, zs, |b| {
+                shade(
+                    tri,
+                    (0..3).fold([0.; 3], |sum, k| sum.addv(&bs[k].muls(b[k]))),
+                )
+            });
+        }
+    }
+}
+
+pub fn solid(canvas: &mut Canvas, camera: &Camera, obj: &Object, rgb: [u8; 3]) {
+    rasterize(canvas, camera, obj, |_, _| rgb);
+}
+
+pub fn interpolate_uv(uvs: &[V2f64], [i, j, k]: [usize; 3], b: V3f64) -> V2f64 {
+    uvs[i]
+        .muls(b[0])
+        .addv(&uvs[j].muls(b[1]))
+        .addv(&uvs[k].muls(b[2]))
+}
+
+/// Falls back to `solid` with black when `obj` has no uvs
+pub fn textured(
+    canvas: &mut Canvas,
+    camera: &Camera,
+    obj: &Object,
+    tex: &Texture,
+    sampler: Sampler,
+) {
+    let Some(uvs) = obj.uvs() else {
+        return solid(canvas, camera, obj, [0x00; 3]);
+    };
+    rasterize(canvas, camera, obj, |tri, b| {
+        tex.sample(interpolate_uv(uvs, tri, b), sampler)
+    });
+}
+
+/// # Flat Shading
+/// one world-space normal per triangle, flipped towards the camera.
+/// With `shadows` lighting is evaluated per pixel, otherwise once per triangle.
+pub fn lit(
+    canvas: &mut Canvas,
+    camera: &Camera,
+    obj: &Object,
+    lighting: &Lighting,
+    shadows: Option<&Shadows>,
+    mut albedo: impl FnMut([usize; 3], V3f64) -> [u8; 3],
+) {
+    let vs = obj.vertices();
+    let mut cache = None;
+    rasterize(canvas, camera, obj, |tri, b| {
+        let ps = tri.map(|i| vs[i]);
+        let (n, flat) = match cache {
+            Some((t, n, e)) if t == tri => (n, e),
+            _ => {
+                let [p1, p2, p3] = ps;
+                let mut n = p2.subv(&p1).cross(&p3.subv(&p1)).normalized();
+                let c = p1.addv(&p2).addv(&p3).divs(3.);
+                if n.dot(&camera.position.subv(&c)) < 0. {
+                    n = n.neg();
+                }
+                let e = lighting.irradiance(c, n);
+                cache = Some((tri, n, e));
+                (n, e)
+            }
+        };
+        let irradiance = match shadows {
+            None => flat,
+            Some(shadows) => {
+                let p = (0..3).fold([0.; 3], |p, i| p.addv(&ps[i].muls(b[i])));
+                lighting.irradiance_visible(p, n, |i| shadows.visibility(lighting, i, p, n))
+            }
+        };
+        let rgb = albedo(tri, b);
+        std::array::from_fn(|i| (rgb[i] as f64 * irradiance[i]).round().clamp(0., 255.) as u8)
+    });
+}
+
+/// # Returns
+/// screen-space end points and mean view-space depth of every line of `obj`,
+/// lines are clipped against the near plane before projection
+pub fn project_lines(
+    camera: &Camera,
+    obj: &Object,
+    w: usize,
+    h: usize,
+) -> Vec<(V2f64, V2f64, f64)> {
+    if !visible(camera, obj, w, h) {
+        return Vec::new();
+    }
+    let mut view = obj.clone();
+    view.transform(&camera.view());
+    let mut lines = Vec::new();
+    for [mut p1, mut p2] in view.lines() {
+        if p1[2] < NEAR && p2[2] < NEAR {
+            continue;
+        }
+        if p1[2] < NEAR || p2[2] < NEAR {
+            let t = (NEAR - p1[2]) / (p2[2] - p1[2]);
+            let p = p1.addv(&p2.subv(&p1).muls(t));
+            if p1[2] < NEAR {
+                p1 = p;
+            } else {
+                p2 = p;
+            }
+        }
+        let depth = (p1[2] + p2[2]) / 2.;
+        lines.push((camera.screen(p1, w, h), camera.screen(p2, w, h), depth));
+    }
+    lines
+}
+
+pub fn wireframe(
+    target: &mut impl DrawTarget,
+    camera: &Camera,
+    obj: &Object,
+    width: usize,
+    rgb: [u8; 3],
+) {
+    wireframes(target, camera, [(obj, width, rgb)]);
+}
+
+/// # Painter's Algorithm
+/// lines of all objects are drawn from the farthest to the nearest,
+/// each object with its line width and color
+pub fn wireframes<'a>(
+    target: &mut impl DrawTarget,
+    camera: &Camera,
+    objects: impl IntoIterator<Item = (&'a Object, usize, [u8; 3])>,
+) {
+    let w = target.width();
+    let h = target.height();
+    let mut lines = objects
+        .into_iter()
+        .flat_map(|(obj, width, rgb)| {
+            project_lines(camera, obj, w, h)
+                .into_iter()
+                .map(move |(p1, p2, depth)| (p1, p2, depth, width, rgb))
+        })
+        .collect::<Vec<_>>();
+    lines.sort_by(|a, b| b.2.total_cmp(&a.2));
+    for (p1, p2, _, width, rgb) in lines {
+        target.line(p1, p2, width, rgb);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::{Filter, Wrap};
+
+    #[test]
+    fn textured_test() {
+        // a quad receding from `z = 1` on the left to `z = 3` on the right, `u` along `x`
+        let obj = Object::from_obj(
+            "v -1 -1 1\nv 1 -1 3\nv 1 1 3\nv -1 1 1\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             f 1/1 2/2 3/3 4/4\n",
+        )
+        .unwrap();
+        let tex = Texture::from_fn(256, 1, |[u, _]| [(u * 256.) as u8, 0, 0]);
+        let mut canvas = Canvas::new(64, 64, [0; 3]);
+        let sampler = Sampler::new(Filter::Nearest, Wrap::Clamp);
+        textured(&mut canvas, &Camera::default(), &obj, &tex, sampler);
+        for x in [8, 24, 32, 40] {
+            // the ray through the pixel center meets the quad where `x / z = s`
+            let s = (x as f64 + 0.5) / 32. - 1.;
+            let u = (1. + s) / (2. * (1. - s));
+            let affine = (1. + s) * 0.75;
+            let r = canvas.pixel(x, 32).unwrap()[0] as f64 / 256.;
+            assert!((r - u).abs() < 1. / 128., "{} {} {}", x, r, u);
+            assert!((r - affine).abs() > 0.05, "{} {} {}", x, r, affine);
+        }
+    }
+
+    #[test]
+    fn near_test() {
+        // a floor at `y = -1` reaching behind the camera, `u` along `z`
+        let obj = Object::from_obj(
+            "v -10 -1 -10\nv 10 -1 -10\nv 10 -1 10\nv -10 -1 10\n\
+             vt 0 0\nvt 0 0\nvt 1 0\nvt 1 0\n\
+             f 1/1 2/2 3/3 4/4\n",
+        )
+        .unwrap();
+        let tex = Texture::from_fn(256, 1, |[u, _]| [(u * 256.) as u8, 0, 0]);
+        let mut canvas = Canvas::new(64, 64, [0; 3]);
+        let sampler = Sampler::new(Filter::Nearest, Wrap::Clamp);
+        textured(&mut canvas, &Camera::default(), &obj, &tex, sampler);
+        for y in [40, 50, 63] {
+            // the ray through the pixel center meets the floor at `z = -1 / s`
+            let s = 1. - (y as f64 + 0.5) / 32.;
+            let z = -1. / s;
+            let depth = canvas.depth(32, y).unwrap();
+            assert!((depth - z).abs() < 1e-9, "{} {} {}", y, depth, z);
+            let r = canvas.pixel(32, y).unwrap()[0] as f64 / 256.;
+            assert!((r - (z + 10.) / 20.).abs() < 1. / 128., "{} {} {}", y, r, z);
+        }
+        assert_eq!(canvas.depth(32, 20), Some(f64::INFINITY));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::object::Object;
+use crate::ray::{Hit, Ray};
+use crate::transform::Transform;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeId(usize);
+
+pub struct Node {
+    pub name: String,
+    /// relative to the parent node, or to the global coordinate system for roots
+    pub local: Transform,
+    /// model-space geometry, `None` for pure grouping nodes
+    pub object: Option<Object>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Transform,
+}
+
+impl Node {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            local: Transform::e(),
+            object: None,
+            parent: None,
+            children: Vec::new(),
+            world: Transform::e(),
+        }
+    }
+
+    pub fn with_local(mut self, local: Transform) -> Self {
+        self.local = local;
+        self
+    }
+
+    pub fn with_object(mut self, object: Object) -> Self {
+        self.object = Some(object);
+        self
+    }
+
+    /// Applies `ts` in the parent space, e.g. rotating around the parent origin
+    pub fn transform(&mut self, ts: &Transform) {
+        self.local = *ts * self.local;
+    }
+
+    /// Applies `ts` in the node's own space, e.g. spinning around its own axis
+    pub fn transform_local(&mut self, ts: &Transform) {
+        self.local = self.local * *ts;
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// Valid after `Scene::update`
+    pub fn world(&self) -> Transform {
+        self.world
+    }
+}
+
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, parent: Option<NodeId>, mut node: Node) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        node.parent = parent;
+        node.children.clear();
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        self.nodes.push(node);
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> + use<> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> {
+        self.ids().filter(|&id| self.nodes[id.0].parent.is_none())
+    }
+
+    /// # Panics
+    /// if `parent` is `id` itself or one of its descendants
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let mut p = parent;
+        while let Some(q) = p {
+            assert!(q != id, "reparenting would create a cycle");
+            p = self.nodes[q.0].parent;
+        }
+        if let Some(old) = self.nodes[id.0].parent {
+            self.nodes[old.0].children.retain(|&c| c != id);
+        }
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        self.nodes[id.0].parent = parent;
+    }
+
+    /// Propagates world transforms from the roots down
+    pub fn update(&mut self) {
+        let mut stack = self
+            .roots()
+            .map(|id| (id, Transform::e()))
+            .collect::<Vec<_>>();
+        while let Some((id, parent)) = stack.pop() {
+            let node = &mut self.nodes[id.0];
+            node.world = parent * node.local;
+            let world = node.world;
+            stack.extend(node.children.iter().map(|&c| (c, world)));
+        }
+    }
+
+    /// # Returns
+    /// world-space copies of every node's object, valid after `update`
+    pub fn objects(&self) -> impl Iterator<Item = (NodeId, Object)> {
+        self.nodes.iter().enumerate().filter_map(|(i, n)| {
+            let mut object = n.object.clone()?;
+            object.transform(&n.world);
+            Some((NodeId(i), object))
+        })
+    }
+
+    /// # Returns
+    /// the node whose world-space object is hit first, valid after `update`
+    pub fn pick(&self, ray: &Ray) -> Option<(NodeId, Hit)> {
+        let (ids, objects): (Vec<_>, Vec<_>) = self.objects().unzip();
+        let hit = ray.pick(&objects)?;
+        Some((ids[hit.object], hit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn position(scene: &Scene, id: NodeId) -> [f64; 3] {
+        let mut p = [[0.; 3]];
+        scene.node(id).world().transform(&mut p);
+        p[0]
+    }
+
+    #[test]
+    fn propagation_test() {
+        let mut scene = Scene::new();
+        let planet = scene.add(
+            None,
+            Node::new("planet").with_local(Transform::e().translate([0., 0., 5.])),
+        );
+        let moon = scene.add(
+            Some(planet),
+            Node::new("moon").with_local(Transform::e().translate([2., 0., 0.])),
+        );
+        scene.update();
+        let [x, y, z] = position(&scene, moon);
+        assert!((x - 2.).abs() < 1e-9 && y.abs() < 1e-9 && (z - 5.).abs() < 1e-9);
+        scene
+            .node_mut(planet)
+            .transform_local(&Transform::e().ry_rad(FRAC_PI_2));
+        scene.update();
+        let [x, y, z] = position(&scene, moon);
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9 && (z - 3.).abs() < 1e-9);
+        assert_eq!(scene.find("moon"), Some(moon));
+        assert_eq!(scene.node(planet).children(), &[moon]);
+    }
+}
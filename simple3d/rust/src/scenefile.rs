@@ -0,0 +1,1233 @@
+//! # Scene Description Format
+//! Line based, `#` starts a comment. Top-level statements and blocks:
+//! ```text
+//! background 192 192 192
+//! ambient 51 51 51
+//! output { size 960 540; fps 60; duration 1; name frame; shadows 1024; msaa 4; blur 8 180; hdr rgbe }
+//! output { filter bloom 0.8 8 0.5; filter vignette; filter curves 0 0 0.5 0.6 1 1 }   # in order
+//! camera { position 0 0 0; target 0 0 1; up 0 1 0; fov 90 }
+//! light directional { direction 0 -1 1; color 255 255 255; intensity 1 }
+//! light point { position 0 2 0; color 255 255 255 }
+//! light spot { position 0 2 0; direction 0 -1 0; angle 30 }
+//! object name {
+//!     primitive cube 1          # cube SIZE | plane SIZE | sphere RADIUS SLICES STACKS
+//!     mesh "model.obj"          # relative to the scene file
+//!     parent other
+//!     translate 0 0 5
+//!     rotate 0 1 0 45           # axis and degrees
+//!     scale 1 1 1
+//!     color 128 0 255
+//!     texture "image.ppm" bilinear clamp
+//!     noise gradient 7 4 5 0 0 0 255 255 255   # value|gradient|simplex|worley, seed, period,
+//!                                              # octaves and optional colors, tiles over the uvs
+//!     wireframe 3 128 0 255     # line width and optional color, black by default
+//!     reflect 0.3               # mirror share, ray tracer only
+//!     refract 0.5 1.5           # transmitted share and index of refraction, ray tracer only
+//!     key 0 rotate 0 1 0 0 linear
+//!     key 1 rotate 0 1 0 90 bezier 0.42 0 0.58 1
+//! }
+//! ```
+//! Inside a block, statements are separated by new lines or `;`.
+//! Keyframed channels (`translate`, `rotate`, `scale`) replace the static value of that channel.
+//! Objects without color, texture or wireframe are drawn as thin black wireframes.
+//! Without `ambient`, scenes with lights get a dim ambient term and scenes without are unlit.
+
+use crate::animation::{Animation, Easing, Timeline, Track};
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::draw::DrawTarget;
+use crate::hdr::{Format, Image};
+use crate::light::{Light, Lighting};
+use crate::motion::Accumulator;
+use crate::object::Object;
+use crate::primitive;
+use crate::quaternion::Quat;
+use crate::render;
+use crate::scene::{Node, NodeId, Scene};
+use crate::shadow::Shadows;
+use crate::texture::{Filter, Sampler, Texture, Wrap};
+use crate::tracer::{Material, Surface, Tracer};
+use crate::vector::V3f64;
+use common::filter;
+use common::noise::{Basis, Noise};
+use common::shutter::Shutter;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl SceneError {
+    fn new(line: usize, col: usize, msg: impl Into<String>) -> Self {
+        Self {
+            line,
+            col,
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    text: String,
+    quoted: bool,
+    line: usize,
+    col: usize,
+}
+
+impl Token {
+    fn is(&self, s: &str) -> bool {
+        !self.quoted && self.text == s
+    }
+
+    fn error(&self, msg: impl Into<String>) -> SceneError {
+        SceneError::new(self.line, self.col, msg)
+    }
+}
+
+/// # Returns
+/// tokens of every logical line, `;` splits lines and `{`, `}` are tokens of their own
+fn tokenize(src: &str) -> Result<Vec<Vec<Token>>, SceneError> {
+    let mut lines = Vec::new();
+    for (n, text) in src.lines().enumerate() {
+        let line = n + 1;
+        let mut tokens = Vec::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some(&(i, c)) = chars.peek() {
+            let col = text[..i].chars().count() + 1;
+            match c {
+                '#' => break,
+                ';' => {
+                    chars.next();
+                    lines.push(std::mem::take(&mut tokens));
+                }
+                '{' => {
+                    chars.next();
+                    tokens.push(Token {
+                        text: c.to_string(),
+                        quoted: false,
+                        line,
+                        col,
+                    });
+                    lines.push(std::mem::take(&mut tokens));
+                }
+                '}' => {
+                    chars.next();
+                    lines.push(std::mem::take(&mut tokens));
+                    lines.push(vec![Token {
+                        text: c.to_string(),
+                        quoted: false,
+                        line,
+                        col,
+                    }]);
+                }
+                '"' => {
+                    chars.next();
+                    let mut s = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, c)) => s.push(c),
+                            None => return Err(SceneError::new(line, col, "unterminated string")),
+                        }
+                    }
+                    tokens.push(Token {
+                        text: s,
+                        quoted: true,
+                        line,
+                        col,
+                    });
+                }
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                _ => {
+                    let mut s = String::new();
+                    while let Some(&(_, c)) = chars.peek() {
+                        if c.is_whitespace() || "#;{}\"".contains(c) {
+                            break;
+                        }
+                        s.push(c);
+                        chars.next();
+                    }
+                    tokens.push(Token {
+                        text: s,
+                        quoted: false,
+                        line,
+                        col,
+                    });
+                }
+            }
+        }
+        lines.push(tokens);
+    }
+    Ok(lines.into_iter().filter(|l| !l.is_empty()).collect())
+}
+
+/// Cursor over the arguments of one statement
+struct Args<'a> {
+    head: &'a Token,
+    rest: &'a [Token],
+}
+
+impl<'a> Args<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Self {
+            head: &tokens[0],
+            rest: &tokens[1..],
+        }
+    }
+
+    fn end_error(&self, msg: &str) -> SceneError {
+        let last = self.rest.last().unwrap_or(self.head);
+        SceneError::new(
+            last.line,
+            last.col + last.text.chars().count() + 2 * last.quoted as usize,
+            format!("{} after `{}`", msg, self.head.text),
+        )
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.rest.first()
+    }
+
+    fn next(&mut self, what: &str) -> Result<&'a Token, SceneError> {
+        let (first, rest) = self
+            .rest
+            .split_first()
+            .ok_or_else(|| self.end_error(&format!("expected {}", what)))?;
+        self.rest = rest;
+        Ok(first)
+    }
+
+    fn word(&mut self) -> Result<&'a Token, SceneError> {
+        let t = self.next("a word")?;
+        if t.quoted {
+            return Err(t.error("expected a word, found a string"));
+        }
+        Ok(t)
+    }
+
+    fn string(&mut self) -> Result<String, SceneError> {
+        Ok(self.next("a string")?.text.clone())
+    }
+
+    fn number(&mut self) -> Result<f64, SceneError> {
+        self.number_if(|_| true, "")
+    }
+
+    /// # Params
+    /// `msg` is reported at the number when `ok` rejects it
+    fn number_if(&mut self, ok: impl Fn(f64) -> bool, msg: &str) -> Result<f64, SceneError> {
+        let t = self.next("a number")?;
+        let n = t
+            .text
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite() && !t.quoted)
+            .ok_or_else(|| t.error(format!("expected a number, found `{}`", t.text)))?;
+        if !ok(n) {
+            return Err(t.error(msg));
+        }
+        Ok(n)
+    }
+
+    fn positive(&mut self) -> Result<f64, SceneError> {
+        self.number_if(|n| n > 0., "expected a positive number")
+    }
+
+    fn count(&mut self, min: usize) -> Result<usize, SceneError> {
+        let t = self.next("an integer")?;
+        match t.text.parse::<usize>() {
+            Ok(n) if n >= min && !t.quoted => Ok(n),
+            _ => Err(t.error(format!("expected an integer of at least {}", min))),
+        }
+    }
+
+    fn vec3(&mut self) -> Result<V3f64, SceneError> {
+        Ok([self.number()?, self.number()?, self.number()?])
+    }
+
+    fn rgb(&mut self) -> Result<[u8; 3], SceneError> {
+        let mut rgb = [0; 3];
+        for c in rgb.iter_mut() {
+            let t = self.next("a color channel")?;
+            *c = t
+                .text
+                .parse::<u8>()
+                .ok()
+                .filter(|_| !t.quoted)
+                .ok_or_else(|| {
+                    t.error(format!(
+                        "expected a color channel in 0..=255, found `{}`",
+                        t.text
+                    ))
+                })?;
+        }
+        Ok(rgb)
+    }
+
+    fn direction(&mut self) -> Result<V3f64, SceneError> {
+        let t = self.peek();
+        let v = self.vec3()?;
+        match t {
+            Some(t) if v == [0.; 3] => Err(t.error("direction must not be zero")),
+            _ => Ok(v),
+        }
+    }
+
+    fn axis_angle(&mut self) -> Result<Quat, SceneError> {
+        let axis = self.direction()?;
+        Ok(Quat::axis_rad(axis, self.number()?.to_radians()))
+    }
+
+    fn easing(&mut self) -> Result<Easing, SceneError> {
+        let Some(t) = self.peek() else {
+            return Ok(Easing::Linear);
+        };
+        self.rest = &self.rest[1..];
+        Ok(match t.text.as_str() {
+            "step" => Easing::Step,
+            "linear" => Easing::Linear,
+            "ease-in" => Easing::EaseIn,
+            "ease-out" => Easing::EaseOut,
+            "ease-in-out" => Easing::EaseInOut,
+            "bezier" => Easing::CubicBezier(
+                self.number()?,
+                self.number()?,
+                self.number()?,
+                self.number()?,
+            ),
+            _ => return Err(t.error(format!("unknown easing `{}`", t.text))),
+        })
+    }
+
+    fn finish(&self) -> Result<(), SceneError> {
+        match self.rest.first() {
+            Some(t) => Err(t.error(format!("unexpected `{}`", t.text))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Output {
+    pub w: usize,
+    pub h: usize,
+    pub fps: usize,
+    /// seconds, `None` follows the longest animation, `0` renders a single still frame
+    pub duration: Option<f64>,
+    pub name: String,
+    /// shadow map resolution of the rasterizer, `0` disables shadows
+    pub shadows: usize,
+    /// samples per pixel of the rasterizer, see `Canvas::with_msaa`
+    pub msaa: usize,
+    /// motion blur of both renderers
+    pub shutter: Shutter,
+    /// also writes every frame in this floating point format
+    pub hdr: Option<Format>,
+    /// post-processing of every finished frame, see `filter::Filter::parse`
+    pub filters: Vec<filter::Filter>,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self {
+            w: 16 * 60,
+            h: 9 * 60,
+            fps: 60,
+            duration: None,
+            name: "frame".to_string(),
+            shadows: 0,
+            msaa: 1,
+            shutter: Shutter::default(),
+            hdr: None,
+            filters: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Cube(f64),
+    Plane(f64),
+    Sphere(f64, usize, usize),
+    Mesh(PathBuf),
+}
+
+/// Side of the textures baked from noise
+const NOISE_SIZE: usize = 256;
+
+#[derive(Clone, Debug)]
+pub enum TextureSource {
+    File(PathBuf),
+    /// fBm tiling every `period` lattice cells across the uvs, blending two colors
+    Noise {
+        basis: Basis,
+        seed: u64,
+        period: usize,
+        octaves: usize,
+        colors: [[u8; 3]; 2],
+    },
+}
+
+impl TextureSource {
+    fn load(&self) -> Result<Texture, String> {
+        match self {
+            TextureSource::File(path) => Texture::load_ppm(&path.to_string_lossy()),
+            TextureSource::Noise {
+                basis,
+                seed,
+                period,
+                octaves,
+                colors: [low, high],
+            } => {
+                let noise = Noise::new(*seed).with_period(*period);
+                let scale = *period as f64;
+                Ok(Texture::from_fn(NOISE_SIZE, NOISE_SIZE, |[u, v]| {
+                    let n = noise.fbm(*basis, [u * scale, v * scale], *octaves, 0.5);
+                    let k = (n * 0.5 + 0.5).clamp(0., 1.);
+                    std::array::from_fn(|i| {
+                        (low[i] as f64 + (high[i] as f64 - low[i] as f64) * k).round() as u8
+                    })
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ObjectDesc {
+    pub name: String,
+    pub shape: Option<Shape>,
+    pub parent: Option<String>,
+    pub translate: V3f64,
+    pub rotate: Quat,
+    pub scale: V3f64,
+    pub color: Option<[u8; 3]>,
+    pub texture: Option<(TextureSource, Sampler)>,
+    /// line width and color
+    pub wireframe: Option<(usize, [u8; 3])>,
+    pub reflect: f64,
+    /// share of refracted light and index of refraction
+    pub refract: (f64, f64),
+    pub animation: Animation,
+    line: usize,
+    col: usize,
+}
+
+impl ObjectDesc {
+    fn new(name: String, line: usize, col: usize) -> Self {
+        Self {
+            name,
+            shape: None,
+            parent: None,
+            translate: [0.; 3],
+            rotate: Quat::identity(),
+            scale: [1.; 3],
+            color: None,
+            texture: None,
+            wireframe: None,
+            reflect: 0.,
+            refract: (0., 1.),
+            animation: Animation::new(),
+            line,
+            col,
+        }
+    }
+
+    fn error(&self, msg: impl Into<String>) -> SceneError {
+        SceneError::new(self.line, self.col, msg)
+    }
+
+    /// Static channels become single keyframes of otherwise empty tracks
+    fn animation(&self) -> Animation {
+        let mut a = self.animation.clone();
+        if a.translation.is_empty() {
+            a.translation = Track::new().key(0., self.translate, Easing::Step);
+        }
+        if a.rotation.is_empty() {
+            a.rotation = Track::new().key(0., self.rotate, Easing::Step);
+        }
+        if a.scale.is_empty() {
+            a.scale = Track::new().key(0., self.scale, Easing::Step);
+        }
+        a
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneFile {
+    pub output: Output,
+    pub background: [u8; 3],
+    pub camera: Camera,
+    pub lighting: Lighting,
+    pub objects: Vec<ObjectDesc>,
+}
+
+impl Default for SceneFile {
+    fn default() -> Self {
+        Self {
+            output: Output::default(),
+            background: [0xC0; 3],
+            camera: Camera::default(),
+            lighting: Lighting::default(),
+            objects: Vec::new(),
+        }
+    }
+}
+
+fn color(rgb: [u8; 3]) -> V3f64 {
+    rgb.map(|c| c as f64 / 255.)
+}
+
+impl SceneFile {
+    /// # Params
+    /// `dir` resolves relative mesh and texture paths
+    pub fn parse(src: &str, dir: &Path) -> Result<Self, SceneError> {
+        let lines = tokenize(src)?;
+        let mut file = Self::default();
+        let mut ambient_set = false;
+        let mut i = 0;
+        while i < lines.len() {
+            let line = &lines[i];
+            i += 1;
+            let head = &line[0];
+            if head.is("}") {
+                return Err(head.error("unmatched `}`"));
+            }
+            if !line.last().unwrap().is("{") {
+                let mut args = Args::new(line);
+                match head.text.as_str() {
+                    "background" => file.background = args.rgb()?,
+                    "ambient" => {
+                        file.lighting.ambient = color(args.rgb()?);
+                        ambient_set = true;
+                    }
+                    _ => return Err(head.error(format!("unknown statement `{}`", head.text))),
+                }
+                args.finish()?;
+                continue;
+            }
+            let header = &line[..line.len() - 1];
+            let mut body = Vec::new();
+            loop {
+                let Some(line) = lines.get(i) else {
+                    return Err(line.last().unwrap().error("unclosed `{`"));
+                };
+                i += 1;
+                if line[0].is("}") {
+                    break;
+                }
+                if line.iter().any(|t| t.is("{")) {
+                    let t = line.iter().find(|t| t.is("{")).unwrap();
+                    return Err(t.error("nested blocks are not allowed"));
+                }
+                body.push(line);
+            }
+            let mut args = Args::new(header);
+            match head.text.as_str() {
+                "output" => {
+                    args.finish()?;
+                    file.parse_output(&body)?;
+                }
+                "camera" => {
+                    args.finish()?;
+                    file.parse_camera(head, &body)?;
+                }
+                "light" => {
+                    let kind = args.word()?.clone();
+                    args.finish()?;
+                    file.lighting.lights.push(parse_light(&kind, &body)?);
+                }
+                "object" => {
+                    let name = args.word()?;
+                    args.finish()?;
+                    if file.objects.iter().any(|o| o.name == name.text) {
+                        return Err(name.error(format!("duplicate object `{}`", name.text)));
+                    }
+                    let desc = parse_object(name, &body, dir)?;
+                    file.objects.push(desc);
+                }
+                _ => return Err(head.error(format!("unknown block `{}`", head.text))),
+            }
+        }
+        if !ambient_set && !file.lighting.lights.is_empty() {
+            file.lighting.ambient = [0.2; 3];
+        }
+        for o in file.objects.iter() {
+            let mut p = o.parent.as_deref();
+            let mut depth = 0;
+            while let Some(name) = p {
+                let Some(parent) = file.objects.iter().find(|q| q.name == name) else {
+                    return Err(o.error(format!("unknown parent `{}`", name)));
+                };
+                depth += 1;
+                if depth > file.objects.len() {
+                    return Err(o.error("parent chain forms a cycle"));
+                }
+                p = parent.parent.as_deref();
+            }
+        }
+        Ok(file)
+    }
+
+    fn parse_output(&mut self, body: &[&Vec<Token>]) -> Result<(), SceneError> {
+        for line in body {
+            let mut args = Args::new(line);
+            let out = &mut self.output;
+            match line[0].text.as_str() {
+                "size" => {
+                    out.w = args.count(1)?;
+                    out.h = args.count(1)?;
+                }
+                "fps" => out.fps = args.count(1)?,
+                "duration" => {
+                    let t = args.number_if(|n| n >= 0., "duration must not be negative")?;
+                    out.duration = Some(t);
+                }
+                "name" => out.name = args.string()?,
+                "shadows" => out.shadows = args.count(0)?,
+                "msaa" => {
+                    let n = args.number_if(
+                        |n| [1., 2., 4., 8.].contains(&n),
+                        "msaa must be 1, 2, 4 or 8 samples",
+                    )?;
+                    out.msaa = n as usize;
+                }
+                "blur" => {
+                    let samples = args.count(1)?;
+                    let angle = args.number_if(
+                        |n| 0. < n && n <= 360.,
+                        "shutter angle must be between 0 and 360 degrees",
+                    )?;
+                    out.shutter = Shutter::new(samples, angle);
+                }
+                "hdr" => {
+                    let format = args.string()?;
+                    out.hdr = Some(match format.as_str() {
+                        "pfm" => Format::Pfm,
+                        "rgbe" => Format::Rgbe,
+                        _ => return Err(line[1].error("expected `pfm` or `rgbe`")),
+                    });
+                }
+                "filter" => {
+                    let name = args.word()?;
+                    let mut numbers = Vec::new();
+                    while args.peek().is_some() {
+                        numbers.push(args.number()?);
+                    }
+                    let f =
+                        filter::Filter::parse(&name.text, &numbers).map_err(|e| name.error(e))?;
+                    out.filters.push(f);
+                }
+                _ => return Err(unknown(&line[0], "output")),
+            }
+            args.finish()?;
+        }
+        Ok(())
+    }
+
+    fn parse_camera(&mut self, head: &Token, body: &[&Vec<Token>]) -> Result<(), SceneError> {
+        for line in body {
+            let mut args = Args::new(line);
+            let cam = &mut self.camera;
+            match line[0].text.as_str() {
+                "position" => cam.position = args.vec3()?,
+                "target" => cam.target = args.vec3()?,
+                "up" => cam.up = args.vec3()?,
+                "fov" => {
+                    let fov = args.number_if(
+                        |n| 0. < n && n < 180.,
+                        "fov must be between 0 and 180 degrees",
+                    )?;
+                    cam.fov = fov.to_radians();
+                }
+                _ => return Err(unknown(&line[0], "camera")),
+            }
+            args.finish()?;
+        }
+        if self.camera.position == self.camera.target {
+            return Err(head.error("camera position and target must differ"));
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// the scene graph, an animation timeline and the per-node draw settings
+    pub fn build(&self) -> Result<Built, SceneError> {
+        let mut scene = Scene::new();
+        let mut timeline = Timeline::new();
+        let mut ids = Vec::with_capacity(self.objects.len());
+        let mut pending = (0..self.objects.len()).collect::<Vec<_>>();
+        let mut draws = Vec::new();
+        // add parents before children
+        while !pending.is_empty() {
+            pending.retain(|&i| {
+                let o = &self.objects[i];
+                let parent = match o.parent.as_deref() {
+                    None => None,
+                    Some(name) => match ids.iter().find(|(n, _)| n == name) {
+                        Some((_, id)) => Some(*id),
+                        None => return true,
+                    },
+                };
+                let id = scene.add(parent, Node::new(&o.name));
+                ids.push((o.name.clone(), id));
+                timeline.bind(id, o.animation());
+                draws.push((id, i));
+                false
+            });
+        }
+        let mut styles = Vec::with_capacity(draws.len());
+        for (id, i) in draws {
+            let o = &self.objects[i];
+            let object = match &o.shape {
+                None => None,
+                Some(Shape::Cube(size)) => Some(primitive::cube(*size)),
+                Some(Shape::Plane(size)) => Some(primitive::plane(*size)),
+                Some(Shape::Sphere(r, slices, stacks)) => {
+                    Some(primitive::sphere(*r, *slices, *stacks))
+                }
+                Some(Shape::Mesh(path)) => {
+                    Some(Object::load_obj(&path.to_string_lossy()).map_err(|e| o.error(e))?)
+                }
+            };
+            let texture = match &o.texture {
+                None => None,
+                Some(_) if object.as_ref().is_some_and(|obj| obj.uvs().is_none()) => {
+                    return Err(o.error(format!("object `{}` is textured but has no uvs", o.name)));
+                }
+                Some((source, sampler)) => Some((source.load().map_err(|e| o.error(e))?, *sampler)),
+            };
+            scene.node_mut(id).object = object;
+            styles.push(Style {
+                id,
+                color: o.color,
+                texture,
+                wireframe: o.wireframe,
+                reflect: o.reflect,
+                refract: o.refract,
+            });
+        }
+        Ok(Built {
+            scene,
+            timeline,
+            styles,
+        })
+    }
+
+    pub fn render(&self, renderer: Renderer) -> Result<(), SceneError> {
+        let name = &self.output.name;
+        let Some(format) = self.output.hdr else {
+            return self.frames(renderer, |i, canvas| {
+                canvas.output_ppm(&format!("{}-{:02}", name, i))
+            });
+        };
+        self.hdr_frames(renderer, |i, image| {
+            let name = format!("{}-{:02}", name, i);
+            image.to_canvas().output_ppm(&name);
+            image.output(&name, format);
+        })
+    }
+
+    /// Steps the animation, `f` receives the frame index, the index into `offsets`,
+    /// the styles and the world-space objects at that time after the start of the frame
+    fn animate(
+        &self,
+        offsets: &[f64],
+        mut f: impl FnMut(usize, usize, &[Style], Vec<(NodeId, Object)>),
+    ) -> Result<(), SceneError> {
+        let Built {
+            mut scene,
+            timeline,
+            styles,
+        } = self.build()?;
+        let Output { fps, duration, .. } = self.output;
+        let duration = duration.unwrap_or(timeline.duration());
+        let frames = ((duration * fps as f64).round() as usize).max(1);
+        for i in 0..frames {
+            for (k, dt) in offsets.iter().enumerate() {
+                timeline.apply(&mut scene, i as f64 / fps as f64 + dt);
+                scene.update();
+                f(i, k, &styles, scene.objects().collect());
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws one sub-frame of the rasterizer on `canvas`
+    fn rasterize(&self, canvas: &mut Canvas, styles: &[Style], objects: &[(NodeId, Object)]) {
+        let drawn = styles
+            .iter()
+            .filter_map(|style| {
+                let (_, object) = objects.iter().find(|(id, _)| *id == style.id)?;
+                Some((style, object))
+            })
+            .collect::<Vec<_>>();
+        let shadow_size = self.output.shadows;
+        let shadows = (shadow_size > 0).then(|| {
+            let casters = drawn
+                .iter()
+                .filter(|(style, _)| style.color.is_some() || style.texture.is_some())
+                .map(|(_, object)| *object)
+                .collect::<Vec<_>>();
+            Shadows::new(&self.lighting, &casters, shadow_size)
+        });
+        for (style, object) in drawn {
+            let lighting = &self.lighting;
+            style.draw(canvas, &self.camera, lighting, shadows.as_ref(), object);
+        }
+    }
+
+    fn tracer<'a>(&self, styles: &'a [Style], mut objects: Vec<(NodeId, Object)>) -> Tracer<'a> {
+        let surfaces = styles
+            .iter()
+            .filter_map(|style| {
+                let i = objects.iter().position(|(id, _)| *id == style.id)?;
+                let (_, object) = objects.swap_remove(i);
+                style.surface(object)
+            })
+            .collect();
+        let background = color(self.background);
+        Tracer::new(surfaces, self.lighting.clone(), background)
+    }
+
+    /// # Frame Loop
+    /// `f` receives the index and the canvas of every frame in order, after the output filters
+    pub fn frames(
+        &self,
+        renderer: Renderer,
+        mut f: impl FnMut(usize, &Canvas),
+    ) -> Result<(), SceneError> {
+        let Output {
+            w,
+            h,
+            fps,
+            msaa,
+            shutter,
+            ..
+        } = self.output;
+        let mut canvas = Canvas::new(w, h, self.background).with_msaa(msaa);
+        let offsets = shutter.offsets(fps);
+        let mut accumulator = (offsets.len() > 1).then(|| Accumulator::new(w, h));
+        self.animate(&offsets, |i, k, styles, objects| {
+            match renderer {
+                Renderer::Raster => self.rasterize(&mut canvas, styles, &objects),
+                Renderer::Trace => self
+                    .tracer(styles, objects)
+                    .render(&mut canvas, &self.camera),
+            }
+            if let Some(acc) = accumulator.as_mut() {
+                acc.add(&canvas);
+                if k + 1 < offsets.len() {
+                    canvas.fill(self.background);
+                    return;
+                }
+                acc.resolve(&mut canvas);
+            }
+            canvas.filter(&self.output.filters);
+            f(i, &canvas);
+            canvas.fill(self.background);
+        })
+    }
+
+    /// # HDR Frame Loop
+    /// like `frames`, the ray tracer keeps its values above `1`
+    /// and motion blur is averaged in linear light in floating point
+    pub fn hdr_frames(
+        &self,
+        renderer: Renderer,
+        mut f: impl FnMut(usize, &Image),
+    ) -> Result<(), SceneError> {
+        let Output {
+            w,
+            h,
+            fps,
+            msaa,
+            shutter,
+            ..
+        } = self.output;
+        let mut canvas = Canvas::new(w, h, self.background).with_msaa(msaa);
+        let mut image = Image::new(w, h);
+        let mut sum = Image::new(w, h);
+        let offsets = shutter.offsets(fps);
+        let weight = 1. / offsets.len() as f32;
+        self.animate(&offsets, |i, k, styles, objects| {
+            match renderer {
+                Renderer::Raster => {
+                    self.rasterize(&mut canvas, styles, &objects);
+                    image = Image::from_canvas(&canvas);
+                    canvas.fill(self.background);
+                }
+                Renderer::Trace => self
+                    .tracer(styles, objects)
+                    .render_hdr(&mut image, &self.camera),
+            }
+            sum.add_scaled(&image, weight);
+            if k + 1 == offsets.len() {
+                sum.filter(&self.output.filters);
+                f(i, &sum);
+                sum.fill([0.; 3]);
+            }
+        })
+    }
+
+    /// # Wireframe Frame Loop
+    /// draws the wireframes of every frame on `target`, nearer lines over farther ones,
+    /// faces are not drawn and the shutter is ignored. `f` receives the index and the target of every frame in order.
+    pub fn draw_frames<T: DrawTarget>(
+        &self,
+        target: &mut T,
+        mut f: impl FnMut(usize, &T),
+    ) -> Result<(), SceneError> {
+        self.animate(&[0.], |i, _, styles, objects| {
+            target.clear(self.background);
+            let drawn = styles.iter().filter_map(|style| {
+                let (width, rgb) = style.wireframe?;
+                let (_, object) = objects.iter().find(|(id, _)| *id == style.id)?;
+                Some((object, width, rgb))
+            });
+            render::wireframes(target, &self.camera, drawn);
+            f(i, target);
+        })
+    }
+}
+
+fn unknown(t: &Token, block: &str) -> SceneError {
+    t.error(format!("unknown {} statement `{}`", block, t.text))
+}
+
+fn parse_light(kind: &Token, body: &[&Vec<Token>]) -> Result<Light, SceneError> {
+    let mut position = [0.; 3];
+    let mut direction = [0., -1., 0.];
+    let mut angle = 30_f64.to_radians();
+    let mut rgb = [0xFF; 3];
+    let mut intensity = 1.;
+    for line in body {
+        let mut args = Args::new(line);
+        match line[0].text.as_str() {
+            "position" if !kind.is("directional") => position = args.vec3()?,
+            "direction" if !kind.is("point") => direction = args.direction()?,
+            "angle" if kind.is("spot") => {
+                let deg = args.number_if(
+                    |n| 0. < n && n < 90.,
+                    "angle must be between 0 and 90 degrees",
+                )?;
+                angle = deg.to_radians();
+            }
+            "color" => rgb = args.rgb()?,
+            "intensity" => intensity = args.number()?,
+            _ => return Err(unknown(&line[0], &format!("{} light", kind.text))),
+        }
+        args.finish()?;
+    }
+    let color = color(rgb).map(|c| c * intensity);
+    Ok(match kind.text.as_str() {
+        "directional" => Light::Directional { direction, color },
+        "point" => Light::Point { position, color },
+        "spot" => Light::Spot {
+            position,
+            direction,
+            angle,
+            color,
+        },
+        _ => return Err(kind.error(format!("unknown light kind `{}`", kind.text))),
+    })
+}
+
+fn parse_object(name: &Token, body: &[&Vec<Token>], dir: &Path) -> Result<ObjectDesc, SceneError> {
+    let mut o = ObjectDesc::new(name.text.clone(), name.line, name.col);
+    for line in body {
+        let mut args = Args::new(line);
+        let head = &line[0];
+        match head.text.as_str() {
+            "primitive" | "mesh" if o.shape.is_some() => {
+                return Err(head.error("object already has a shape"));
+            }
+            "primitive" => {
+                let kind = args.word()?;
+                o.shape = Some(match kind.text.as_str() {
+                    "cube" => Shape::Cube(args.positive()?),
+                    "plane" => Shape::Plane(args.positive()?),
+                    "sphere" => Shape::Sphere(args.positive()?, args.count(3)?, args.count(2)?),
+                    _ => return Err(kind.error(format!("unknown primitive `{}`", kind.text))),
+                });
+            }
+            "mesh" => o.shape = Some(Shape::Mesh(dir.join(args.string()?))),
+            "parent" => {
+                let parent = args.word()?;
+                if parent.text == o.name {
+                    return Err(parent.error("object cannot be its own parent"));
+                }
+                o.parent = Some(parent.text.clone());
+            }
+            "translate" => o.translate = args.vec3()?,
+            "rotate" => o.rotate = args.axis_angle()? * o.rotate,
+            "scale" => {
+                let s = args.number()?;
+                o.scale = match args.peek() {
+                    Some(_) => [s, args.number()?, args.number()?],
+                    None => [s; 3],
+                };
+            }
+            "color" => o.color = Some(args.rgb()?),
+            "texture" => {
+                let path = dir.join(args.string()?);
+                let mut sampler = Sampler::default();
+                while let Some(t) = args.peek() {
+                    match t.text.as_str() {
+                        "nearest" => sampler.filter = Filter::Nearest,
+                        "bilinear" => sampler.filter = Filter::Bilinear,
+                        "repeat" => sampler.wrap = Wrap::Repeat,
+                        "clamp" => sampler.wrap = Wrap::Clamp,
+                        _ => break,
+                    }
+                    args.next("")?;
+                }
+                o.texture = Some((TextureSource::File(path), sampler));
+            }
+            "noise" => {
+                let kind = args.word()?;
+                let basis = match kind.text.as_str() {
+                    "value" => Basis::Value,
+                    "gradient" => Basis::Gradient,
+                    "simplex" => Basis::Simplex,
+                    "worley" => Basis::Worley,
+                    _ => return Err(kind.error(format!("unknown noise `{}`", kind.text))),
+                };
+                let seed = args.count(0)? as u64;
+                let period = args.count(1)?;
+                let octaves = args.count(1)?;
+                let colors = match args.peek() {
+                    Some(_) => [args.rgb()?, args.rgb()?],
+                    None => [[0x00; 3], [0xFF; 3]],
+                };
+                let source = TextureSource::Noise {
+                    basis,
+                    seed,
+                    period,
+                    octaves,
+                    colors,
+                };
+                o.texture = Some((source, Sampler::new(Filter::Bilinear, Wrap::Repeat)));
+            }
+            "wireframe" => {
+                let width = args.count(1)?;
+                let rgb = match args.peek() {
+                    Some(_) => args.rgb()?,
+                    None => [0x00; 3],
+                };
+                o.wireframe = Some((width, rgb));
+            }
+            "reflect" => {
+                o.reflect =
+                    args.number_if(|n| (0. ..=1.).contains(&n), "expected a share in [0, 1]")?;
+            }
+            "refract" => {
+                let k =
+                    args.number_if(|n| (0. ..=1.).contains(&n), "expected a share in [0, 1]")?;
+                let ior = args.positive()?;
+                o.refract = (k, ior);
+            }
+            "key" => {
+                let time = args.number_if(|n| n >= 0., "key time must not be negative")?;
+                let channel = args.word()?;
+                let a = &mut o.animation;
+                match channel.text.as_str() {
+                    "translate" => {
+                        let v = args.vec3()?;
+                        a.translation = a.translation.clone().key(time, v, args.easing()?);
+                    }
+                    "rotate" => {
+                        let q = args.axis_angle()?;
+                        a.rotation = a.rotation.clone().key(time, q, args.easing()?);
+                    }
+                    "scale" => {
+                        let v = args.vec3()?;
+                        a.scale = a.scale.clone().key(time, v, args.easing()?);
+                    }
+                    _ => {
+                        return Err(
+                            channel.error(format!("unknown animation channel `{}`", channel.text))
+                        );
+                    }
+                }
+            }
+            _ => return Err(unknown(head, "object")),
+        }
+        args.finish()?;
+    }
+    if o.reflect + o.refract.0 > 1. {
+        return Err(o.error("reflect and refract shares add up to more than 1"));
+    }
+    if o.color.is_none() && o.texture.is_none() && o.wireframe.is_none() {
+        o.wireframe = Some((1, [0x00; 3]));
+    }
+    Ok(o)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Renderer {
+    Raster,
+    Trace,
+}
+
+pub struct Style {
+    pub id: NodeId,
+    pub color: Option<[u8; 3]>,
+    pub texture: Option<(Texture, Sampler)>,
+    pub wireframe: Option<(usize, [u8; 3])>,
+    pub reflect: f64,
+    pub refract: (f64, f64),
+}
+
+impl Style {
+    /// # Returns
+    /// `None` for wireframe-only styles, which the ray tracer skips
+    pub fn surface(&self, object: Object) -> Option<Surface<'_>> {
+        if self.color.is_none() && self.texture.is_none() {
+            return None;
+        }
+        let material = Material {
+            color: color(self.color.unwrap_or([0xFF; 3])),
+            reflect: self.reflect,
+            refract: self.refract.0,
+            ior: self.refract.1,
+        };
+        Some(Surface {
+            object,
+            material,
+            texture: self.texture.as_ref().map(|(tex, sampler)| (tex, *sampler)),
+        })
+    }
+
+    /// Faces are filled when a color or texture is given, wireframes are drawn on top.
+    /// Textures need uvs, without them the color is used.
+    pub fn draw(
+        &self,
+        canvas: &mut Canvas,
+        camera: &Camera,
+        lighting: &Lighting,
+        shadows: Option<&Shadows>,
+        object: &Object,
+    ) {
+        match (&self.texture, object.uvs(), self.color) {
+            (Some((tex, sampler)), Some(uvs), _) => {
+                render::lit(canvas, camera, object, lighting, shadows, |tri, b| {
+                    tex.sample(render::interpolate_uv(uvs, tri, b), *sampler)
+                });
+            }
+            (_, _, Some(rgb)) => {
+                render::lit(canvas, camera, object, lighting, shadows, |_, _| rgb);
+            }
+            _ => {}
+        }
+        if let Some((width, rgb)) = self.wireframe {
+            render::wireframe(canvas, camera, object, width, rgb);
+        }
+    }
+}
+
+pub struct Built {
+    pub scene: Scene,
+    pub timeline: Timeline,
+    pub styles: Vec<Style>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::filter::Filter;
+
+    #[test]
+    fn parse_test() {
+        let src = "
+            background 0 0 0
+            output { size 320 200; fps 24; filter blur 1.5; filter sobel }
+            light point {
+                position 0 2 0  # above
+                intensity 0.5
+            }
+            object group {
+                key 0 rotate 0 1 0 0
+                key 1 rotate 0 1 0 90 bezier 0.42 0 0.58 1
+            }
+            object box {
+                parent group
+                primitive cube 1
+                color 255 0 0
+            }
+            object ground {
+                primitive plane 4
+                noise worley 3 4 2 0 0 0 255 0 0
+            }
+        ";
+        let file = SceneFile::parse(src, Path::new(".")).unwrap();
+        assert_eq!(
+            (file.output.w, file.output.h, file.output.fps),
+            (320, 200, 24)
+        );
+        assert_eq!(
+            file.output.filters,
+            [Filter::GaussianBlur { sigma: 1.5 }, Filter::Sobel]
+        );
+        assert_eq!(file.lighting.lights.len(), 1);
+        assert_eq!(file.objects[1].parent.as_deref(), Some("group"));
+        assert_eq!(file.objects[1].shape, Some(Shape::Cube(1.)));
+        assert_eq!(file.objects[0].animation.duration(), 1.);
+        let built = file.build().unwrap();
+        assert_eq!(built.styles.len(), 3);
+        let (texture, _) = built.styles[2].texture.as_ref().unwrap();
+        assert_eq!(texture.width(), NOISE_SIZE);
+        assert!(texture.pixels().iter().all(|p| p[1] == 0 && p[2] == 0));
+
+        let err = |src| SceneFile::parse(src, Path::new(".")).unwrap_err();
+        assert_eq!(
+            err("object a {\n  translate 0 x 0\n}").to_string(),
+            "2:15: expected a number, found `x`"
+        );
+        assert_eq!(
+            err("object a {\n  scale\n}").to_string(),
+            "2:8: expected a number after `scale`"
+        );
+        assert_eq!(err("object a {\n  color 1 2 3\n").msg, "unclosed `{`");
+        assert_eq!(err("object a {\n  parent b\n}").msg, "unknown parent `b`");
+        assert_eq!(
+            err("object a {\n  noise perlin 1 2 3\n}").msg,
+            "unknown noise `perlin`"
+        );
+        assert_eq!(
+            err("output { filter glow }").to_string(),
+            "1:17: unknown filter `glow`"
+        ); // textures need uvs, which meshes without `vt` lack
+        let dir = std::env::temp_dir();
+        std::fs::write(
+            dir.join("simple3d-no-uvs.obj"),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        )
+        .unwrap();
+        let src = "object a {\n  mesh simple3d-no-uvs.obj\n  noise value 1 2 3\n}";
+        assert_eq!(
+            SceneFile::parse(src, &dir)
+                .unwrap()
+                .build()
+                .err()
+                .unwrap()
+                .to_string(),
+            "1:8: object `a` is textured but has no uvs"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::bounds::BoundingSphere;
+use crate::camera::Camera;
+use crate::canvas::Triangle;
+use crate::light::{Light, Lighting};
+use crate::object::Object;
+use crate::transform::Transform;
+use crate::vector::{V2f64, V3f64, Vector};
+
+#[derive(Clone, Copy, Debug)]
+enum Projection {
+    /// half extent of the square covered by the map
+    Ortho(f64),
+    /// same as `Camera::focal`
+    Persp(f64),
+}
+
+/// # Shadow Map
+/// depth of the closest surface seen from a light, for directional and spot lights
+pub struct ShadowMap {
+    size: usize,
+    depth: Vec<f64>,
+    view: Transform,
+    projection: Projection,
+    /// constant depth bias in world units, scaled up on surfaces at grazing angles
+    pub bias: f64,
+    /// percentage-closer filtering kernel radius in texels, `0` for hard shadows
+    pub pcf: usize,
+}
+
+impl ShadowMap {
+    /// # Returns
+    /// `None` for point lights or when there is nothing to cast shadows
+    pub fn new(light: &Light, objects: &[&Object], size: usize) -> Option<Self> {
+        assert!(size != 0);
+        let aabb = objects
+            .iter()
+            .map(|o| *o.aabb())
+            .reduce(|a, b| a.union(&b))
+            .filter(|b| !b.is_empty())?;
+        let bounds = BoundingSphere::from_points(&[aabb.min, aabb.max], &aabb);
+        let radius = bounds.radius.max(1e-6);
+        let (camera, projection, bias) = match *light {
+            Light::Directional { direction, .. } => {
+                let position = bounds
+                    .center
+                    .subv(&direction.normalized().muls(2. * radius));
+                let camera = Self::camera(position, direction);
+                // two texels of the orthographic map
+                (camera, Projection::Ortho(radius), 4. * radius / size as f64)
+            }
+            Light::Spot {
+                position,
+                direction,
+                angle,
+                ..
+            } => {
+                let camera = Camera {
+                    fov: 2. * angle,
+                    ..Self::camera(position, direction)
+                };
+                let projection = Projection::Persp(camera.focal());
+                (camera, projection, 4. * radius / size as f64)
+            }
+            Light::Point { .. } => return None,
+        };
+        let mut map = Self {
+            size,
+            depth: vec![f64::INFINITY; size * size],
+            view: camera.view(),
+            projection,
+            bias,
+            pcf: 1,
+        };
+        for obj in objects {
+            map.draw(obj);
+        }
+        Some(map)
+    }
+
+    fn camera(position: V3f64, direction: V3f64) -> Camera {
+        let d = direction.normalized();
+        let up = if d[1].abs() > 0.99 {
+            [0., 0., 1.]
+        } else {
+            [0., 1., 0.]
+        };
+        Camera {
+            up,
+            ..Camera::new(position, position.addv(&d))
+        }
+    }
+
+    /// # Returns
+    /// map coordinates in texels and the light-space depth
+    fn project(&self, p: V3f64) -> (V2f64, f64) {
+        let mut v = [p];
+        self.view.transform(&mut v);
+        let [x, y, z] = v[0];
+        let [x, y] = match self.projection {
+            Projection::Ortho(r) => [x / r, y / r],
+            Projection::Persp(f) => [x / z * f, y / z * f],
+        };
+        let s = self.size as f64;
+        ([(x + 1.) / 2. * s, (1. - y) / 2. * s], z)
+    }
+
+    fn draw(&mut self, obj: &Object) {
+        let vs = obj.vertices();
+        let max = self.size as i32 - 1;
+        for tri in obj.triangles() {
+            let ps = tri.map(|i| self.project(vs[i]));
+            if ps.iter().any(|(_, z)| *z <= 0.) {
+                continue;
+            }
+            let [(p1, z1), (p2, z2), (p3, z3)] = ps;
+            for ([x, y], b) in Triangle::new(p1, p2, p3).draw(0, max, 0, max) {
+                let z = match self.projection {
+                    Projection::Ortho(_) => b[0] * z1 + b[1] * z2 + b[2] * z3,
+                    Projection::Persp(_) => 1. / (b[0] / z1 + b[1] / z2 + b[2] / z3),
+                };
+                let d = &mut self.depth[y as usize * self.size + x as usize];
+                *d = d.min(z);
+            }
+        }
+    }
+
+    /// # Returns
+    /// fraction of the `pcf` kernel around `p` that is lit, `cos` is between the normal and the light
+    pub fn visibility(&self, p: V3f64, cos: f64) -> f64 {
+        let ([x, y], z) = self.project(p);
+        if z <= 0. {
+            return 1.;
+        }
+        let tan = (1. - cos * cos).max(0.).sqrt() / cos.max(0.1);
+        let bias = self.bias * (1. + tan);
+        let r = self.pcf as i64;
+        let (cx, cy) = (x.floor() as i64, y.floor() as i64);
+        let mut lit = 0;
+        for j in -r..=r {
+            for i in -r..=r {
+                let (x, y) = (cx + i, cy + j);
+                let s = self.size as i64;
+                // outside the map nothing was drawn, so nothing occludes
+                if x < 0
+                    || y < 0
+                    || x >= s
+                    || y >= s
+                    || z - bias <= self.depth[(y * s + x) as usize]
+                {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f64 / ((2 * r + 1) * (2 * r + 1)) as f64
+    }
+}
+
+/// One optional shadow map per light of a `Lighting`
+pub struct Shadows {
+    maps: Vec<Option<ShadowMap>>,
+}
+
+impl Shadows {
+    pub fn new(lighting: &Lighting, objects: &[&Object], size: usize) -> Self {
+        Self {
+            maps: lighting
+                .lights
+                .iter()
+                .map(|light| ShadowMap::new(light, objects, size))
+                .collect(),
+        }
+    }
+
+    /// # Params
+    /// `i` indexes `Lighting::lights`, `n` is the unit surface normal at `p`
+    pub fn visibility(&self, lighting: &Lighting, i: usize, p: V3f64, n: V3f64) -> f64 {
+        match &self.maps[i] {
+            Some(map) => {
+                let (l, _, _) = lighting.lights[i].incident(p);
+                map.visibility(p, n.dot(&l))
+            }
+            None => 1.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive;
+
+    #[test]
+    fn visibility_test() {
+        let floor = primitive::plane(10.);
+        let mut cube = primitive::cube(1.);
+        cube.transform(&Transform::e().translate([0., 2., 0.]));
+        let light = Light::Directional {
+            direction: [0., -1., 0.],
+            color: [1.; 3],
+        };
+        let map = ShadowMap::new(&light, &[&floor, &cube], 256).unwrap();
+        assert_eq!(map.visibility([0., 0., 0.], 1.), 0.);
+        assert_eq!(map.visibility([3., 0., 3.], 1.), 1.);
+        assert_eq!(map.visibility([0., 2.5, 0.], 1.), 1.);
+        let spot = Light::Spot {
+            position: [0., 5., 0.],
+            direction: [0., -1., 0.],
+            angle: 60_f64.to_radians(),
+            color: [1.; 3],
+        };
+        let map = ShadowMap::new(&spot, &[&floor, &cube], 256).unwrap();
+        assert_eq!(map.visibility([0., 0., 0.], 1.), 0.);
+        assert_eq!(map.visibility([3., 0., 3.], 1.), 1.);
+    }
+}
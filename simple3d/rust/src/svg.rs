@@ -0,0 +1,181 @@
+use crate::draw::DrawTarget;
+use crate::vector::V2f64;
+use std::fmt::Write;
+
+/// # Vector Output
+/// elements are written in drawing order, later ones overlap earlier ones
+pub struct Svg {
+    w: usize,
+    h: usize,
+    background: Option<[u8; 3]>,
+    elements: Vec<String>,
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Svg {
+    /// # Params
+    /// `background` of `None` leaves the document transparent
+    pub fn new(w: usize, h: usize, background: Option<[u8; 3]>) -> Self {
+        Self {
+            w,
+            h,
+            background,
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn document(&self) -> String {
+        let Self { w, h, .. } = *self;
+        let mut s = String::new();
+        writeln!(
+            s,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#
+        )
+        .unwrap();
+        if let Some(rgb) = self.background {
+            writeln!(
+                s,
+                r#"<rect width="{}" height="{}" fill="{}"/>"#,
+                w,
+                h,
+                hex(rgb)
+            )
+            .unwrap();
+        }
+        writeln!(s, r#"<g stroke-linecap="round">"#).unwrap();
+        for e in &self.elements {
+            s += e;
+            s += "\n";
+        }
+        s += "</g>\n</svg>\n";
+        s
+    }
+
+    pub fn output_svg(&self, file_name: &str) {
+        use std::env;
+        use std::fs;
+        let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let output_dir = root + "/output";
+        if !fs::exists(&output_dir).unwrap() {
+            fs::create_dir_all(&output_dir).unwrap();
+        }
+        let output_path = format!("{}/{}.svg", output_dir, file_name);
+        fs::write(&output_path, self.document()).unwrap();
+        println!("Generated {}", output_path);
+    }
+}
+
+impl DrawTarget for Svg {
+    fn width(&self) -> usize {
+        self.w
+    }
+
+    fn height(&self) -> usize {
+        self.h
+    }
+
+    /// Also drops the elements drawn so far
+    fn clear(&mut self, rgb: [u8; 3]) {
+        self.background = Some(rgb);
+        self.elements.clear();
+    }
+
+    fn dot(&mut self, [x, y]: V2f64, size: usize, rgb: [u8; 3]) {
+        self.elements.push(format!(
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{}" fill="{}"/>"#,
+            x,
+            y,
+            size as f64 / 2.,
+            hex(rgb)
+        ));
+    }
+
+    fn line(&mut self, [x1, y1]: V2f64, [x2, y2]: V2f64, width: usize, rgb: [u8; 3]) {
+        self.elements.push(format!(
+            r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="{}"/>"#,
+            x1,
+            y1,
+            x2,
+            y2,
+            hex(rgb),
+            width
+        ));
+    }
+
+    fn polygon(&mut self, ps: &[V2f64], rgb: [u8; 3]) {
+        let points = ps
+            .iter()
+            .map(|[x, y]| format!("{:.2},{:.2}", x, y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.elements.push(format!(
+            r#"<polygon points="{}" fill="{}" fill-rule="evenodd"/>"#,
+            points,
+            hex(rgb)
+        ));
+    }
+
+    fn text(&mut self, [x, y]: V2f64, size: usize, s: &str, rgb: [u8; 3]) {
+        for (i, line) in s.lines().enumerate() {
+            self.elements.push(format!(
+                r#"<text x="{:.2}" y="{:.2}" font-family="monospace" font-size="{}" dominant-baseline="hanging" fill="{}">{}</text>"#,
+                x,
+                y + (i * size) as f64,
+                size,
+                hex(rgb),
+                escape(line)
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::primitive;
+    use crate::render;
+    use crate::transform::Transform;
+
+    #[test]
+    fn document_test() {
+        let mut svg = Svg::new(64, 48, Some([0xC0; 3]));
+        svg.line([1., 2.], [3., 4.], 2, [0xFF, 0x00, 0x00]);
+        svg.text([0., 0.], 8, "a<b", [0x00; 3]);
+        let doc = svg.document();
+        assert!(
+            doc.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="64" height="48""#)
+        );
+        assert!(doc.contains(r##"<rect width="64" height="48" fill="#c0c0c0"/>"##));
+        assert!(doc.contains(
+            r##"<line x1="1.00" y1="2.00" x2="3.00" y2="4.00" stroke="#ff0000" stroke-width="2"/>"##
+        ));
+        assert!(doc.contains(">a&lt;b</text>"));
+        let mut near = primitive::cube(1.);
+        near.transform(&Transform::e().translate([0., 0., 3.]));
+        let mut far = primitive::cube(1.);
+        far.transform(&Transform::e().translate([0., 0., 9.]));
+        let mut svg = Svg::new(64, 48, None);
+        render::wireframes(
+            &mut svg,
+            &Camera::default(),
+            [(&near, 1, [0x00; 3]), (&far, 1, [0xFF; 3])],
+        );
+        let doc = svg.document();
+        assert!(!doc.contains("<rect"));
+        assert_eq!(doc.matches("<line").count(), 2 * near.lines().count());
+        let last_far = doc.rfind("#ffffff").unwrap();
+        let first_near = doc.find("#000000").unwrap();
+        assert!(last_far < first_near);
+    }
+}
@@ -0,0 +1,151 @@
+//! # Terminal Preview
+//! Every character cell shows two pixels: the foreground color paints the upper half block `▀`,
+//! the background color the lower half. Colors are 24-bit ANSI escapes.
+
+use crate::canvas::Canvas;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const CLEAR: &str = "\x1b[2J";
+const HOME: &str = "\x1b[H";
+const RESET: &str = "\x1b[0m";
+
+/// Terminal width from `COLUMNS`, 80 when unset
+pub fn columns() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(80)
+}
+
+/// # Downsampling
+/// box filter to at most `cols` columns, keeping the aspect ratio with square half blocks
+///
+/// # Returns
+/// one line per two pixel rows, each ending with a color reset and a new line
+pub fn half_blocks(canvas: &Canvas, cols: usize) -> String {
+    let w = canvas.width();
+    let h = canvas.height();
+    let cols = cols.clamp(1, w.max(1));
+    let rows = ((h * cols) as f64 / w.max(1) as f64).round().max(1.) as usize;
+    let lines = rows.div_ceil(2);
+    let bounds = |i: usize, n: usize, len: usize| {
+        let a = i * len / n;
+        let b = ((i + 1) * len / n).max(a + 1).min(len);
+        (a, b)
+    };
+    let pixel = |i: usize, j: usize| {
+        if j >= rows || w == 0 || h == 0 {
+            return None;
+        }
+        let (x0, x1) = bounds(i, cols, w);
+        let (y0, y1) = bounds(j, rows, h);
+        Some(canvas.average(x0, x1, y0, y1))
+    };
+    let mut s = String::new();
+    for line in 0..lines {
+        let mut fg = None;
+        let mut bg = None;
+        for i in 0..cols {
+            let top = pixel(i, 2 * line);
+            let bottom = pixel(i, 2 * line + 1);
+            if top != fg
+                && let Some([r, g, b]) = top
+            {
+                s += &format!("\x1b[38;2;{};{};{}m", r, g, b);
+                fg = top;
+            }
+            if bottom != bg {
+                match bottom {
+                    Some([r, g, b]) => s += &format!("\x1b[48;2;{};{};{}m", r, g, b),
+                    None => s += "\x1b[49m",
+                }
+                bg = bottom;
+            }
+            s += "▀";
+        }
+        s += RESET;
+        s += "\n";
+    }
+    s
+}
+
+pub fn print(canvas: &Canvas, cols: usize) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(half_blocks(canvas, cols).as_bytes())?;
+    out.flush()
+}
+
+/// # Animated Preview
+/// draws every frame over the previous one from the top-left corner at a fixed frame rate,
+/// the cursor is hidden until the player is dropped
+pub struct Player {
+    cols: usize,
+    interval: Duration,
+    next: Option<Instant>,
+}
+
+impl Player {
+    pub fn new(cols: usize, fps: usize) -> io::Result<Self> {
+        let mut out = io::stdout().lock();
+        write!(out, "{}{}", HIDE_CURSOR, CLEAR)?;
+        out.flush()?;
+        Ok(Self {
+            cols,
+            interval: Duration::from_secs_f64(1. / fps.max(1) as f64),
+            next: None,
+        })
+    }
+
+    /// Waits for the frame's turn, frames that render slower than the frame rate are not skipped
+    pub fn frame(&mut self, canvas: &Canvas) -> io::Result<()> {
+        let frame = half_blocks(canvas, self.cols);
+        let now = Instant::now();
+        if let Some(next) = self.next
+            && next > now
+        {
+            thread::sleep(next - now);
+        }
+        self.next = Some(self.next.map_or(now, |next| next.max(now)) + self.interval);
+        let mut out = io::stdout().lock();
+        write!(out, "{}{}", HOME, frame)?;
+        out.flush()
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        let mut out = io::stdout().lock();
+        let _ = write!(out, "{}{}", RESET, SHOW_CURSOR);
+        let _ = out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_blocks_test() {
+        let mut canvas = Canvas::new(4, 4, [0x00; 3]);
+        for y in 0..2 {
+            for x in 0..2 {
+                *canvas.pixel_mut(x, y).unwrap() = [0xFF, 0x00, 0x00];
+                *canvas.pixel_mut(x + 2, y + 2).unwrap() = [0x00, 0x00, 0xFF];
+            }
+        }
+        assert_eq!(
+            half_blocks(&canvas, 2),
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m▀\x1b[38;2;0;0;0m\x1b[48;2;0;0;255m▀\x1b[0m\n"
+        );
+        let s = half_blocks(&canvas, 4);
+        assert_eq!(s.lines().count(), 2);
+        assert_eq!(s.matches('▀').count(), 8);
+        *canvas.pixel_mut(1, 0).unwrap() = [0x00; 3];
+        assert_eq!(half_blocks(&canvas, 1), "\x1b[38;2;120;0;137m▀\x1b[0m\n");
+    }
+}
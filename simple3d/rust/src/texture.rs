@@ -0,0 +1,226 @@
+use crate::vector::V2f64;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Filter {
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Wrap {
+    #[default]
+    Repeat,
+    Clamp,
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Sampler {
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+impl Sampler {
+    pub fn new(filter: Filter, wrap: Wrap) -> Self {
+        Self { filter, wrap }
+    }
+}
+
+pub struct Texture {
+    w: usize,
+    h: usize,
+    vec: Vec<[u8; 3]>,
+}
+
+impl Texture {
+    pub fn new(w: usize, h: usize, vec: Vec<[u8; 3]>) -> Self {
+        assert!(w != 0);
+        assert!(h != 0);
+        assert_eq!(vec.len(), w * h);
+        Self { w, h, vec }
+    }
+
+    /// # Supported Formats
+    /// binary `P6` and ascii `P3`, `#` comments allowed in the header
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, String> {
+        let mut i = 0;
+        let mut fields = Vec::with_capacity(4);
+        while fields.len() < 4 {
+            while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'#') {
+                if bytes[i] == b'#' {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            let start = i;
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if start == i {
+                return Err("unexpected end of ppm header".to_string());
+            }
+            fields.push(String::from_utf8_lossy(&bytes[start..i]).into_owned());
+        }
+        let magic = fields[0].as_str();
+        let parse = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| format!("invalid ppm header field `{}`", s))
+        };
+        let w = parse(&fields[1])?;
+        let h = parse(&fields[2])?;
+        let max = parse(&fields[3])?;
+        if w == 0 || h == 0 {
+            return Err("empty ppm image".to_string());
+        }
+        if max == 0 || max > 0xFF {
+            return Err(format!("unsupported ppm max value {}", max));
+        }
+        let scale = |c: [usize; 3]| match c.into_iter().find(|&n| n > max) {
+            Some(n) => Err(format!("ppm sample {} exceeds max value {}", n, max)),
+            None => Ok(c.map(|n| (n * 0xFF / max) as u8)),
+        };
+        let vec = match magic {
+            "P6" => {
+                let data = bytes.get(i + 1..).unwrap_or_default();
+                if data.len() < w * h * 3 {
+                    return Err(format!(
+                        "expected {} bytes of pixel data, found {}",
+                        w * h * 3,
+                        data.len()
+                    ));
+                }
+                data.chunks_exact(3)
+                    .take(w * h)
+                    .map(|c| scale([c[0], c[1], c[2]].map(usize::from)))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            "P3" => {
+                let text = String::from_utf8_lossy(&bytes[i..]);
+                let ns = text
+                    .split_whitespace()
+                    .take(w * h * 3)
+                    .map(parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if ns.len() < w * h * 3 {
+                    return Err(format!(
+                        "expected {} samples, found {}",
+                        w * h * 3,
+                        ns.len()
+                    ));
+                }
+                ns.chunks_exact(3)
+                    .map(|c| scale([c[0], c[1], c[2]]))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            _ => return Err(format!("unsupported ppm magic `{}`", magic)),
+        };
+        Ok(Self { w, h, vec })
+    }
+
+    /// Evaluates `f` at the uv of every texel center, `uv` origin is the bottom-left corner
+    pub fn from_fn(w: usize, h: usize, f: impl Fn(V2f64) -> [u8; 3]) -> Self {
+        let vec = (0..w * h)
+            .map(|i| {
+                let u = ((i % w) as f64 + 0.5) / w as f64;
+                let v = 1. - ((i / w) as f64 + 0.5) / h as f64;
+                f([u, v])
+            })
+            .collect();
+        Self::new(w, h, vec)
+    }
+
+    pub fn load_ppm(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::from_ppm(&bytes).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.w
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.h
+    }
+
+    /// Row-major from the top-left corner
+    #[inline]
+    pub fn pixels(&self) -> &[[u8; 3]] {
+        &self.vec
+    }
+
+    fn wrap(n: i64, len: usize, wrap: Wrap) -> usize {
+        let len = len as i64;
+        match wrap {
+            Wrap::Repeat => n.rem_euclid(len) as usize,
+            Wrap::Clamp => n.clamp(0, len - 1) as usize,
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64, wrap: Wrap) -> [f64; 3] {
+        let x = Self::wrap(x, self.w, wrap);
+        let y = Self::wrap(y, self.h, wrap);
+        let [r, g, b] = self.vec[y * self.w + x];
+        [r as f64, g as f64, b as f64]
+    }
+
+    /// # Params
+    /// `uv` origin is the bottom-left corner, as in OBJ files
+    pub fn sample(&self, uv: V2f64, sampler: Sampler) -> [u8; 3] {
+        let [u, v] = uv;
+        let x = u * self.w as f64;
+        let y = (1. - v) * self.h as f64;
+        let rgb = match sampler.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64, sampler.wrap),
+            Filter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let [x0, y0] = [x0 as i64, y0 as i64];
+                let c00 = self.texel(x0, y0, sampler.wrap);
+                let c10 = self.texel(x0 + 1, y0, sampler.wrap);
+                let c01 = self.texel(x0, y0 + 1, sampler.wrap);
+                let c11 = self.texel(x0 + 1, y0 + 1, sampler.wrap);
+                std::array::from_fn(|i| {
+                    let top = c00[i] + (c10[i] - c00[i]) * fx;
+                    let bottom = c01[i] + (c11[i] - c01[i]) * fx;
+                    top + (bottom - top) * fy
+                })
+            }
+        };
+        rgb.map(|c| c.round().clamp(0., 255.) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_test() {
+        let ppm = b"P3\n# 2x2 checker\n2 2\n255\n255 0 0  0 0 0\n0 0 0  255 0 0\n";
+        let tex = Texture::from_ppm(ppm).unwrap();
+        let nearest = Sampler::new(Filter::Nearest, Wrap::Repeat);
+        assert_eq!(tex.sample([0.25, 0.75], nearest), [0xFF, 0, 0]);
+        assert_eq!(tex.sample([0.75, 0.75], nearest), [0, 0, 0]);
+        assert_eq!(tex.sample([1.25, 1.75], nearest), [0xFF, 0, 0]);
+        let clamp = Sampler::new(Filter::Nearest, Wrap::Clamp);
+        assert_eq!(tex.sample([1.25, 0.75], clamp), [0, 0, 0]);
+        let bilinear = Sampler::new(Filter::Bilinear, Wrap::Clamp);
+        assert_eq!(tex.sample([0.5, 0.5], bilinear), [0x80, 0, 0]);
+        // samples above the max value are rejected rather than wrapped
+        let ppm = b"P3\n1 1\n15\n15 16 0\n";
+        assert_eq!(
+            Texture::from_ppm(ppm).err().unwrap(),
+            "ppm sample 16 exceeds max value 15"
+        );
+        assert!(Texture::from_ppm(b"P6\n1 1\n100\n\x64\xC8\x00").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::hdr::Image;
+use crate::light::Lighting;
+use crate::object::Object;
+use crate::ray::{Hit, Ray};
+use crate::render;
+use crate::texture::{Sampler, Texture};
+use crate::vector::{V3f64, Vector, Vector3};
+use common::color::srgb_decode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Offset along the normal that keeps secondary rays from hitting their own surface
+const EPSILON: f64 = 1e-6;
+const TILE: usize = 32;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    /// diffuse color in `[0, 1]`
+    pub color: V3f64,
+    /// share of mirror reflection
+    pub reflect: f64,
+    /// share of refracted light
+    pub refract: f64,
+    /// index of refraction
+    pub ior: f64,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: [1.; 3],
+            reflect: 0.,
+            refract: 0.,
+            ior: 1.5,
+        }
+    }
+}
+
+pub struct Surface<'a> {
+    /// world space
+    pub object: Object,
+    pub material: Material,
+    pub texture: Option<(&'a Texture, Sampler)>,
+}
+
+pub struct Tracer<'a> {
+    surfaces: Vec<Surface<'a>>,
+    bvh: Bvh,
+    pub lighting: Lighting,
+    pub background: V3f64,
+    /// bounces of reflection and refraction
+    pub depth: usize,
+}
+
+fn reflect(d: V3f64, n: V3f64) -> V3f64 {
+    d.subv(&n.muls(2. * d.dot(&n)))
+}
+
+/// # Returns
+/// `None` on total internal reflection, `eta` is the ratio of the indices of refraction
+fn refract(d: V3f64, n: V3f64, eta: f64) -> Option<V3f64> {
+    let cos = -d.dot(&n);
+    let k = 1. - eta * eta * (1. - cos * cos);
+    if k < 0. {
+        return None;
+    }
+    Some(d.muls(eta).addv(&n.muls(eta * cos - k.sqrt())))
+}
+
+impl<'a> Tracer<'a> {
+    pub fn new(surfaces: Vec<Surface<'a>>, lighting: Lighting, background: V3f64) -> Self {
+        let bvh = Bvh::new(surfaces.iter().map(|s| &s.object));
+        Self {
+            surfaces,
+            bvh,
+            lighting,
+            background,
+            depth: 4,
+        }
+    }
+
+    fn albedo(&self, hit: &Hit) -> V3f64 {
+        let s = &self.surfaces[hit.object];
+        match (s.texture, s.object.uvs()) {
+            (Some((tex, sampler)), Some(uvs)) => {
+                let uv = render::interpolate_uv(uvs, hit.triangle, hit.bary);
+                tex.sample(uv, sampler).map(|c| c as f64 / 255.)
+            }
+            _ => s.material.color,
+        }
+    }
+
+    fn direct(&self, p: V3f64, n: V3f64) -> V3f64 {
+        self.lighting
+            .lights
+            .iter()
+            .fold(self.lighting.ambient, |sum, light| {
+                let (l, distance, color) = light.incident(p);
+                let cos = n.dot(&l);
+                if cos <= 0. {
+                    return sum;
+                }
+                let shadow = Ray::new(p.addv(&n.muls(EPSILON)), l);
+                if self.bvh.occluded(&shadow, distance) {
+                    return sum;
+                }
+                sum.addv(&color.muls(cos))
+            })
+    }
+
+    pub fn trace(&self, ray: &Ray, depth: usize) -> V3f64 {
+        let Some(hit) = self.bvh.closest(ray, f64::INFINITY) else {
+            return self.background;
+        };
+        let s = &self.surfaces[hit.object];
+        let m = s.material;
+        let vs = s.object.vertices();
+        let [p1, p2, p3] = hit.triangle.map(|i| vs[i]);
+        let mut n = p2.subv(&p1).cross(&p3.subv(&p1)).normalized();
+        let inside = n.dot(&ray.dir) > 0.;
+        if inside {
+            n = n.neg();
+        }
+        let p = ray.at(hit.distance);
+        let diffuse = 1. - m.reflect - m.refract;
+        let mut color = self
+            .albedo(&hit)
+            .mulv(&self.direct(p, n))
+            .muls(diffuse.max(0.));
+        if depth == 0 {
+            return color;
+        }
+        let mut reflect_share = m.reflect;
+        if m.refract > 0. {
+            let eta = if inside { m.ior } else { 1. / m.ior };
+            match refract(ray.dir, n, eta) {
+                Some(d) => {
+                    let r = Ray::new(p.subv(&n.muls(EPSILON)), d);
+                    color = color.addv(&self.trace(&r, depth - 1).muls(m.refract));
+                }
+                None => reflect_share += m.refract,
+            }
+        }
+        if reflect_share > 0. {
+            let r = Ray::new(p.addv(&n.muls(EPSILON)), reflect(ray.dir, n));
+            color = color.addv(&self.trace(&r, depth - 1).muls(reflect_share));
+        }
+        color
+    }
+
+    /// # Tile Rendering
+    /// tiles are handed out to one thread per core, every pixel is traced through its center
+    /// and converted by `f`
+    fn tiles<T: Send>(
+        &self,
+        w: usize,
+        h: usize,
+        camera: &Camera,
+        f: impl Fn(V3f64) -> T + Sync,
+    ) -> Vec<([usize; 4], Vec<T>)> {
+        let cols = w.div_ceil(TILE);
+        let tiles = cols * h.div_ceil(TILE);
+        let next = AtomicUsize::new(0);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            if i >= tiles {
+                                break done;
+                            }
+                            let x0 = i % cols * TILE;
+                            let y0 = i / cols * TILE;
+                            let x1 = (x0 + TILE).min(w);
+                            let y1 = (y0 + TILE).min(h);
+                            let mut pixels = Vec::with_capacity((x1 - x0) * (y1 - y0));
+                            for y in y0..y1 {
+                                for x in x0..x1 {
+                                    let ray = camera.ray([x as f64 + 0.5, y as f64 + 0.5], w, h);
+                                    pixels.push(f(self.trace(&ray, self.depth)));
+                                }
+                            }
+                            done.push(([x0, y0, x1, y1], pixels));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+    }
+
+    pub fn render(&self, canvas: &mut Canvas, camera: &Camera) {
+        let done = self.tiles(canvas.width(), canvas.height(), camera, |c| {
+            c.map(|c| (c * 255.).round().clamp(0., 255.) as u8)
+        });
+        for ([x0, y0, x1, _], pixels) in done {
+            for (i, rgb) in pixels.into_iter().enumerate() {
+                let x = x0 + i % (x1 - x0);
+                let y = y0 + i / (x1 - x0);
+                *canvas.pixel_mut(x, y).unwrap() = rgb;
+            }
+        }
+    }
+
+    /// Keeps the traced colors unclamped, decoded to linear light like `Image::from_canvas`
+    pub fn render_hdr(&self, image: &mut Image, camera: &Camera) {
+        let done = self.tiles(image.width(), image.height(), camera, |c| {
+            c.map(|c| srgb_decode(c as f32))
+        });
+        for ([x0, y0, x1, _], pixels) in done {
+            for (i, rgb) in pixels.into_iter().enumerate() {
+                let x = x0 + i % (x1 - x0);
+                let y = y0 + i / (x1 - x0);
+                *image.pixel_mut(x, y).unwrap() = rgb;
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::quaternion::Quat;
 use crate::vector::{V3f64, V4f64, Vector};
 
 /// # Transform Matrix Multiplication
@@ -178,17 +179,23 @@ impl Transform {
         }
     }
 
+    pub fn quat(self, q: &Quat) -> Self {
+        Self {
+            t: mul(&q.mat(), &self.t),
+        }
+    }
+
     pub fn transform(self, vs: &mut [V3f64]) {
         let t = self.t;
         let mut buf = [0.; 3];
-        for i in 0..vs.len() {
+        for v in vs.iter_mut() {
             for j in 0..3 {
                 for k in 0..3 {
-                    buf[j] += t[j][k] * vs[i][k];
+                    buf[j] += t[j][k] * v[k];
                 }
                 buf[j] += t[j][3];
             }
-            vs[i] = buf;
+            *v = buf;
             buf.fill(0.);
         }
     }
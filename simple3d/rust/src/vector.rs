@@ -149,8 +149,7 @@ where
 
     fn magnitude(&self) -> T {
         let mut sum = T::default();
-        for i in 0..N {
-            let n = self[i];
+        for &n in self.iter() {
             sum = sum + n * n;
         }
         sum.sqrt()